@@ -0,0 +1,88 @@
+//! Runs the Benchmark app's datasets through this crate and through `similar`
+//! and `dissimilar`, printing time and edit-script length side by side.
+//! `dissimilar` works on characters, so its length counts changed chunks.
+//!
+//! `cargo bench --bench comparisons`
+
+use std::time::{Duration, Instant};
+
+use deep_diff_rs::bench::datasets::{SwiftDataset, SWIFT_DATASETS};
+use deep_diff_rs::bench::Rng;
+use deep_diff_rs::{hackel, parallel};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// The Swift harness datasets, scaled down by one step so `dissimilar` stays practical.
+const DATASETS: &[SwiftDataset] = &[
+    SwiftDataset { count: 1_000, delete: (400, 500), insert: (500, 600), shuffle: (0, 40) },
+    SWIFT_DATASETS[0],
+    SWIFT_DATASETS[1],
+];
+
+const ITERATIONS: usize = 3;
+
+/// `dissimilar` diffs characters, which gets impractically slow past this many lines.
+const DISSIMILAR_MAX_COUNT: usize = 1_000;
+
+fn measure<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut length = 0;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        length = f();
+        best = best.min(start.elapsed());
+    }
+    (best, length)
+}
+
+/// Counts per-item edits so the numbers line up with this crate's changes.
+fn similar_edits(ops: &[DiffOp]) -> usize {
+    ops.iter()
+        .map(|op| match *op {
+            DiffOp::Equal { .. } => 0,
+            DiffOp::Delete { old_len, .. } => old_len,
+            DiffOp::Insert { new_len, .. } => new_len,
+            DiffOp::Replace { old_len, new_len, .. } => old_len + new_len,
+        })
+        .sum()
+}
+
+fn main() {
+    let mut rng = Rng(1);
+
+    for dataset in DATASETS {
+        let (old, new) = dataset.generate(&mut rng);
+        let old_text = old.join("\n");
+        let new_text = new.join("\n");
+
+        println!(
+            "#### - From {} elements to {} deleted, {} inserted and {} shuffled",
+            dataset.count,
+            dataset.delete.1 - dataset.delete.0,
+            dataset.insert.1 - dataset.insert.0,
+            dataset.shuffle.1 - dataset.shuffle.0,
+        );
+        println!();
+        println!("|{:<24}|{:>12}|{:>12}|", "", "Time(sec)", "Edits");
+        println!("|:{:-<23}|{:->11}:|{:->11}:|", "", "", "");
+
+        let mut results = vec![
+            ("deep-diff-rs", measure(|| hackel::diff(&old, &new).len())),
+            ("deep-diff-rs (parallel)", measure(|| parallel::diff_parallel(&old, &new).len())),
+            ("similar (myers)", measure(|| similar_edits(&capture_diff_slices(Algorithm::Myers, &old, &new)))),
+            ("similar (patience)", measure(|| similar_edits(&capture_diff_slices(Algorithm::Patience, &old, &new)))),
+        ];
+        if dataset.count <= DISSIMILAR_MAX_COUNT {
+            results.push(("dissimilar", measure(|| {
+                dissimilar::diff(&old_text, &new_text)
+                    .iter()
+                    .filter(|chunk| !matches!(chunk, dissimilar::Chunk::Equal(_)))
+                    .count()
+            })));
+        }
+
+        for (name, (time, edits)) in results {
+            println!("|{:<24}|{:>12}|{:>12}|", name, format!("`{:.4}`", time.as_secs_f64()), edits);
+        }
+        println!();
+    }
+}
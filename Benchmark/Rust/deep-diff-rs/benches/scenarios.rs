@@ -0,0 +1,45 @@
+//! Criterion groups for the exact datasets `main.swift` runs, one group per
+//! dataset, so numbers line up with the Swift harness across commits.
+//! `DeepDiff-rs` is the path the Swift app calls through `diffWithString`.
+//!
+//! `cargo bench --bench scenarios`
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use deep_diff_rs::bench::datasets::SWIFT_DATASETS;
+use deep_diff_rs::bench::Rng;
+use deep_diff_rs::hackel;
+
+/// Datasets this large take only 10 samples: one diff takes tens of
+/// milliseconds and criterion's default of 100 drags on.
+const LARGE_COUNT: usize = 100_000;
+
+fn swift_datasets(c: &mut Criterion) {
+    let mut rng = Rng(1);
+    for dataset in SWIFT_DATASETS {
+        let (old, new) = dataset.generate(&mut rng);
+        let old_c = old.iter().map(|item| CString::new(item.as_str()).unwrap()).collect::<Vec<_>>();
+        let new_c = new.iter().map(|item| CString::new(item.as_str()).unwrap()).collect::<Vec<_>>();
+        let old_ptrs = old_c.iter().map(|item| item.as_ptr()).collect::<Vec<*const c_char>>();
+        let new_ptrs = new_c.iter().map(|item| item.as_ptr()).collect::<Vec<*const c_char>>();
+
+        let mut group = c.benchmark_group(format!(
+            "swift-{}-delete-{}-insert-{}-shuffle-{}",
+            dataset.count,
+            dataset.delete.1 - dataset.delete.0,
+            dataset.insert.1 - dataset.insert.0,
+            dataset.shuffle.1 - dataset.shuffle.0,
+        ));
+        if dataset.count >= LARGE_COUNT {
+            group.sample_size(10);
+        }
+        group.bench_function("DeepDiff-rs", |b| b.iter(|| unsafe { deep_diff_rs::diffWithString(old_ptrs.as_ptr(), old_ptrs.len() as _, new_ptrs.as_ptr(), new_ptrs.len() as _) }));
+        group.bench_function("hackel::diff", |b| b.iter(|| hackel::diff(black_box(&old), black_box(&new)).len()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, swift_datasets);
+criterion_main!(benches);
@@ -0,0 +1,27 @@
+//! Generates the C header of the crate's `extern "C"` functions and a Clang
+//! module map for it into `$OUT_DIR/include`, so Swift can `import
+//! DeepDiffRs` with that directory on its import path.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const MODULE_MAP: &str = "module DeepDiffRs {
+    umbrella header \"DeepDiffRs.h\"
+    link \"deep_diff_rs\"
+    export *
+}
+";
+
+fn main() {
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src");
+
+    let crate_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set"));
+    let include = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set")).join("include");
+    fs::create_dir_all(&include).expect("create include directory");
+
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).expect("read cbindgen.toml");
+    cbindgen::generate_with_config(&crate_dir, config).expect("generate C header").write_to_file(include.join("DeepDiffRs.h"));
+    fs::write(include.join("module.modulemap"), MODULE_MAP).expect("write module map");
+}
@@ -0,0 +1,49 @@
+//! Applies the batch changes of `diff` to `old` and checks the result is `new`.
+//!
+//! `cargo fuzz run diff_apply`
+
+#![no_main]
+
+use deep_diff_rs::hackel::{self, Change};
+use libfuzzer_sys::fuzz_target;
+
+/// Applies changes the way `performBatchUpdates` does: deletes and move
+/// sources address `old`, inserts, replaces and move targets address `new`,
+/// and untouched survivors keep their relative order in the free slots.
+fn apply<T: Copy>(old: &[T], new_len: usize, changes: &[Change<'_, T, u32>]) -> Option<Vec<T>> {
+    let mut slots = vec![None; new_len];
+    let mut consumed = vec![false; old.len()];
+
+    for change in changes {
+        match change {
+            Change::Delete(d) => consumed[d.index as usize] = true,
+            Change::Insert(i) => *slots.get_mut(i.index as usize)? = Some(*i.item),
+            Change::Move(m) => {
+                consumed[m.from_index as usize] = true;
+                *slots.get_mut(m.to_index as usize)? = Some(old[m.from_index as usize]);
+            },
+            Change::Replace(_) => {},
+        }
+    }
+
+    let mut survivors = old.iter().zip(&consumed).filter(|(_, consumed)| !**consumed).map(|(item, _)| *item);
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = Some(survivors.next()?);
+    }
+    if survivors.next().is_some() {
+        return None;
+    }
+
+    for change in changes {
+        if let Change::Replace(r) = change {
+            slots[r.index as usize] = Some(*r.new_item);
+        }
+    }
+    slots.into_iter().collect()
+}
+
+fuzz_target!(|input: (Vec<u8>, Vec<u8>)| {
+    let (old, new) = input;
+    let changes = hackel::diff_indexed::<_, u32>(&old, &new);
+    assert_eq!(apply(&old, new.len(), &changes).as_deref(), Some(&new[..]));
+});
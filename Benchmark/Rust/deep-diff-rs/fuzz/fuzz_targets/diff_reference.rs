@@ -0,0 +1,57 @@
+//! Cross-checks `diff` against a quadratic reference of the same semantics:
+//! the k-th occurrence of an item in new matches its k-th occurrence in old.
+//!
+//! `cargo fuzz run diff_reference`
+
+#![no_main]
+
+use deep_diff_rs::hackel::{self, Change};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Eq, PartialEq, Debug)]
+enum Op {
+    Delete(usize),
+    Insert(usize),
+    Move(usize, usize),
+}
+
+fn reference(old: &[u8], new: &[u8]) -> Vec<Op> {
+    let mut old_match = vec![None; old.len()];
+    let mut new_match = vec![None; new.len()];
+    for (new_idx, item) in new.iter().enumerate() {
+        let occurrence = new[..new_idx].iter().filter(|other| *other == item).count();
+        if let Some((old_idx, _)) = old.iter().enumerate().filter(|(_, other)| *other == item).nth(occurrence) {
+            old_match[old_idx] = Some(new_idx);
+            new_match[new_idx] = Some(old_idx);
+        }
+    }
+
+    let mut ops = (0..old.len()).filter(|idx| old_match[*idx].is_none()).map(Op::Delete).collect::<Vec<_>>();
+    for (new_idx, matched) in new_match.iter().enumerate() {
+        match matched {
+            None => ops.push(Op::Insert(new_idx)),
+            Some(old_idx) => {
+                let deleted_before = old_match[..*old_idx].iter().filter(|m| m.is_none()).count();
+                let inserted_before = new_match[..new_idx].iter().filter(|m| m.is_none()).count();
+                if old_idx - deleted_before + inserted_before != new_idx {
+                    ops.push(Op::Move(*old_idx, new_idx));
+                }
+            },
+        }
+    }
+    ops
+}
+
+fuzz_target!(|input: (Vec<u8>, Vec<u8>)| {
+    let (old, new) = input;
+    let ops = hackel::diff(&old, &new)
+        .iter()
+        .map(|change| match change {
+            Change::Delete(d) => Op::Delete(d.index),
+            Change::Insert(i) => Op::Insert(i.index),
+            Change::Move(m) => Op::Move(m.from_index, m.to_index),
+            Change::Replace(_) => panic!("equal items never replace"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ops, reference(&old, &new));
+});
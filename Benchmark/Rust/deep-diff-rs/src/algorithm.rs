@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::hackel::{self, Change, Index};
+use crate::myers;
+use crate::small::{self, SMALL_THRESHOLD};
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Algorithm {
+    /// Heckel's symbol table algorithm; linear time, reports moves.
+    #[default]
+    Heckel,
+    /// Myers' O(ND) algorithm; minimal delete/insert scripts, no moves.
+    Myers,
+    /// Direct O(n·m) comparison, for tiny inputs.
+    Small,
+    /// Picks one of the above from the shape of the inputs.
+    Auto,
+}
+
+const SAMPLE_SIZE: usize = 64;
+
+impl Algorithm {
+    /// Resolves `Auto` to a concrete algorithm for these inputs.
+    ///
+    /// Tiny inputs go to `Small`. Otherwise the common prefix and suffix give
+    /// an estimate of how much changed, and a strided sample of the old side
+    /// estimates how repetitive it is: few changes over repetitive items is
+    /// where Myers is both fast and clearly better than Heckel's pairing of
+    /// duplicates; everything else goes to Heckel.
+    pub fn select<T: Eq + Hash>(self, old: &[T], new: &[T]) -> Algorithm {
+        if self != Algorithm::Auto {
+            return self;
+        }
+
+        if old.len() <= SMALL_THRESHOLD && new.len() <= SMALL_THRESHOLD {
+            return Algorithm::Small;
+        }
+
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+        let total = old.len() + new.len();
+        let changed = total - 2 * (prefix + suffix);
+
+        let step = (old.len() / SAMPLE_SIZE).max(1);
+        let sample = old.iter().step_by(step).take(SAMPLE_SIZE).collect::<Vec<_>>();
+        let distinct = sample.iter().collect::<HashSet<_>>().len();
+        let repetitive = !sample.is_empty() && distinct * 2 < sample.len();
+
+        if repetitive && changed * 8 <= total {
+            Algorithm::Myers
+        } else {
+            Algorithm::Heckel
+        }
+    }
+}
+
+/// Runs the matching stage of `algorithm`, returning for every position the
+/// matched position on the other side or `usize::NONE`.
+pub(crate) fn matches<T: Eq + Hash>(old: &[T], new: &[T], algorithm: Algorithm) -> (Vec<usize>, Vec<usize>) {
+    match algorithm.select(old, new) {
+        Algorithm::Myers => myers::matches(old, new),
+        Algorithm::Small => {
+            let mut old_match = vec![usize::NONE; old.len()];
+            let mut new_match = vec![usize::NONE; new.len()];
+            small::match_small(old, new, &mut old_match, &mut new_match);
+            (old_match, new_match)
+        },
+        _ => {
+            let symbols = hackel::build(old, new);
+            (symbols.old_match, symbols.new_match)
+        },
+    }
+}
+
+pub fn diff_with_algorithm<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], algorithm: Algorithm) -> Vec<Change<'a, T>> {
+    let mut changes = Vec::new();
+    match algorithm.select(old, new) {
+        Algorithm::Myers => myers::diff_myers_with(old, new, |change| changes.push(change)),
+        Algorithm::Small => small::diff_small_with(old, new, |change| changes.push(change)),
+        _ => hackel::diff_with(old, new, |change| changes.push(change)),
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_choice_is_kept() {
+        let old = vec![1, 2, 3];
+        assert_eq!(Algorithm::Myers.select(&old, &old), Algorithm::Myers);
+        assert_eq!(Algorithm::Heckel.select(&old, &old), Algorithm::Heckel);
+    }
+
+    #[test]
+    fn auto_small() {
+        let old = vec![1, 2, 3];
+        let new = vec![3, 2, 1];
+        assert_eq!(Algorithm::Auto.select(&old, &new), Algorithm::Small);
+        assert_eq!(diff_with_algorithm(&old, &new, Algorithm::Auto).len(), 2);
+    }
+
+    #[test]
+    fn auto_repetitive_few_changes() {
+        let old = (0..1000).map(|i| i % 4).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new.insert(500, 9);
+        assert_eq!(Algorithm::Auto.select(&old, &new), Algorithm::Myers);
+        assert_eq!(diff_with_algorithm(&old, &new, Algorithm::Auto).len(), 1);
+    }
+
+    #[test]
+    fn auto_unique_items() {
+        let old = (0..1000).collect::<Vec<_>>();
+        let new = old.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(Algorithm::Auto.select(&old, &new), Algorithm::Heckel);
+    }
+}
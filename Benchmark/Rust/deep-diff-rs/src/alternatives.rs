@@ -0,0 +1,156 @@
+use std::hash::Hash;
+
+use crate::hackel::{self, Change, Index};
+
+/// Longest common subsequence lengths of every pair of suffixes.
+struct Table {
+    width: usize,
+    lengths: Vec<usize>,
+}
+
+impl Table {
+    fn new<T: Eq>(old: &[T], new: &[T]) -> Self {
+        let width = new.len() + 1;
+        let mut lengths = vec![0; (old.len() + 1) * width];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lengths[i * width + j] = if old[i] == new[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        Table { width, lengths }
+    }
+
+    fn at(&self, i: usize, j: usize) -> usize {
+        self.lengths[i * self.width + j]
+    }
+}
+
+/// Collects up to `k` distinct longest common subsequences as matched
+/// pairs. Each step picks the next pair explicitly, so no set is produced
+/// twice.
+fn enumerate<T: Eq>(old: &[T], new: &[T], table: &Table, start: (usize, usize), pairs: &mut Vec<(usize, usize)>, k: usize, out: &mut Vec<Vec<(usize, usize)>>) {
+    if out.len() == k {
+        return;
+    }
+    let remaining = table.at(start.0, start.1);
+    if remaining == 0 {
+        out.push(pairs.clone());
+        return;
+    }
+
+    for i in start.0..old.len() {
+        for j in start.1..new.len() {
+            if old[i] == new[j] && table.at(i + 1, j + 1) + 1 == remaining {
+                pairs.push((i, j));
+                enumerate(old, new, table, (i + 1, j + 1), pairs, k, out);
+                pairs.pop();
+                if out.len() == k {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Up to `k` distinct edit scripts turning `old` into `new`, shortest first,
+/// for tools that let the user pick an interpretation of an ambiguous edit.
+///
+/// The candidates are every minimal delete/insert script, one per longest
+/// common subsequence, plus the symbol table's script, which reads the edit
+/// as moves instead (e.g. "moved block" rather than "deleted and re-typed").
+///
+/// The table of common subsequence lengths takes quadratic time and memory,
+/// and each script up to `O(L·n·m)` more time, `L` being the length of the
+/// common subsequence; meant for short sequences.
+pub fn alternatives<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], k: usize) -> Vec<Vec<Change<'a, T>>> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    let table = Table::new(old, new);
+    let mut subsequences = Vec::new();
+    enumerate(old, new, &table, (0, 0), &mut Vec::new(), k, &mut subsequences);
+
+    let mut scripts = Vec::new();
+    for pairs in subsequences {
+        let mut old_match = vec![usize::NONE; old.len()];
+        let mut new_match = vec![usize::NONE; new.len()];
+        for (i, j) in pairs {
+            old_match[i] = j;
+            new_match[j] = i;
+        }
+        scripts.push((old_match, new_match));
+    }
+
+    let symbols = hackel::build::<_, usize>(old, new);
+    if !scripts.iter().any(|(old_match, _)| *old_match == symbols.old_match) {
+        scripts.insert(0, (symbols.old_match, symbols.new_match));
+    }
+
+    let mut scripts = scripts.iter().map(|(old_match, new_match)| hackel::changes_from_matches(old, new, old_match, new_match)).collect::<Vec<_>>();
+    scripts.sort_by_key(Vec::len);
+    scripts.truncate(k);
+    scripts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_to_vec;
+    use crate::hackel::ChangeKind;
+
+    fn describe(script: &[Change<'_, u8>]) -> Vec<(ChangeKind, usize, usize)> {
+        script
+            .iter()
+            .map(|change| match change {
+                Change::Insert(insert) => (change.kind(), insert.index, insert.index),
+                Change::Delete(delete) => (change.kind(), delete.index, delete.index),
+                Change::Replace(replace) => (change.kind(), replace.index, replace.index),
+                Change::Move(mv) => (change.kind(), mv.from_index, mv.to_index),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn swap() {
+        let old = vec!["a", "b"];
+        let new = vec!["b", "a"];
+        let scripts = alternatives(&old, &new, 5);
+
+        assert_eq!(scripts.len(), 3);
+        assert!(scripts.iter().any(|script| script.iter().all(|change| matches!(change, Change::Move(_)))));
+        assert_eq!(scripts.iter().filter(|script| script.len() == 2 && !matches!(script[0], Change::Move(_))).count(), 2);
+        for script in &scripts {
+            let mut model = old.clone();
+            apply_to_vec(&mut model, script);
+            assert_eq!(model, new);
+        }
+    }
+
+    #[test]
+    fn distinct_and_bounded() {
+        let old = b"abcabc".to_vec();
+        let new = b"cbacba".to_vec();
+        let scripts = alternatives(&old, &new, 4);
+        assert_eq!(scripts.len(), 4);
+        for (i, a) in scripts.iter().enumerate() {
+            for b in &scripts[i + 1..] {
+                assert_ne!(describe(a), describe(b));
+            }
+        }
+        for window in scripts.windows(2) {
+            assert!(window[0].len() <= window[1].len());
+        }
+    }
+
+    #[test]
+    fn unambiguous() {
+        let old = vec![1, 2, 3];
+        assert_eq!(alternatives(&old, &old, 3).len(), 1);
+        assert!(alternatives(&old, &old, 0).is_empty());
+    }
+}
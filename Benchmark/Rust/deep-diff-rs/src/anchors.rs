@@ -0,0 +1,179 @@
+//! Anchored diffing for huge, mostly similar inputs, such as the lines of
+//! two revisions of a large file. A suffix array over both inputs finds long
+//! blocks that occur exactly once on each side; those are matched outright
+//! and only the gaps between them go through the symbol table.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::hackel::{self, Change, Index};
+use crate::substring::CommonRun;
+
+/// Suffix array by prefix doubling: O(n log² n) on any alphabet.
+fn suffix_array(text: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut sa = (0..n).collect::<Vec<_>>();
+    if n == 0 {
+        return sa;
+    }
+    let mut rank = text.to_vec();
+    let mut next = vec![0; n];
+    let mut k = 1;
+    loop {
+        {
+            let key = |i: usize| (rank[i], if i + k < n { rank[i + k] + 1 } else { 0 });
+            sa.sort_unstable_by_key(|i| key(*i));
+            next[sa[0]] = 0;
+            for w in 1..n {
+                next[sa[w]] = next[sa[w - 1]] + usize::from(key(sa[w - 1]) != key(sa[w]));
+            }
+        }
+        std::mem::swap(&mut rank, &mut next);
+        if rank[sa[n - 1]] == n - 1 {
+            return sa;
+        }
+        k *= 2;
+    }
+}
+
+/// Kasai's algorithm: `lcp[i]` is the common prefix length of the suffixes
+/// at `sa[i - 1]` and `sa[i]`, and `lcp[0]` is 0.
+fn lcp_array(text: &[usize], sa: &[usize]) -> Vec<usize> {
+    let mut rank = vec![0; text.len()];
+    for (idx, suffix) in sa.iter().enumerate() {
+        rank[*suffix] = idx;
+    }
+    let mut lcp = vec![0; text.len()];
+    let mut h = 0;
+    for (pos, idx) in rank.iter().enumerate() {
+        if *idx == 0 {
+            h = 0;
+            continue;
+        }
+        let prev = sa[idx - 1];
+        while pos + h < text.len() && prev + h < text.len() && text[pos + h] == text[prev + h] {
+            h += 1;
+        }
+        lcp[*idx] = h;
+        h = h.saturating_sub(1);
+    }
+    lcp
+}
+
+/// Maximal blocks of at least `min_len` items that occur exactly once in
+/// `old` and once in `new`, reduced to a non-crossing chain (longest blocks
+/// first) and sorted by position.
+pub fn unique_blocks<T: Eq + Hash>(old: &[T], new: &[T], min_len: usize) -> Vec<CommonRun> {
+    let mut ids = HashMap::new();
+    let mut text = Vec::with_capacity(old.len() + new.len() + 1);
+    for item in old {
+        let next = ids.len();
+        text.push(*ids.entry(item).or_insert(next));
+    }
+    // A separator found nowhere else keeps common prefixes on one side.
+    text.push(ids.len());
+    for item in new {
+        let next = ids.len() + 1;
+        text.push(*ids.entry(item).or_insert(next));
+    }
+
+    let sa = suffix_array(&text);
+    let lcp = lcp_array(&text, &sa);
+    let mut candidates = Vec::new();
+    for idx in 1..sa.len() {
+        let len = lcp[idx];
+        let unique = lcp[idx - 1] < len && lcp.get(idx + 1).is_none_or(|next| *next < len);
+        if len < min_len.max(1) || !unique {
+            continue;
+        }
+        let (old_pos, new_pos) = match (sa[idx - 1].min(sa[idx]), sa[idx - 1].max(sa[idx])) {
+            (old_pos, new_pos) if old_pos < old.len() && new_pos > old.len() => (old_pos, new_pos - old.len() - 1),
+            _ => continue,
+        };
+        // Skip blocks that extend to the left; that longer block is found too.
+        if old_pos > 0 && new_pos > 0 && old[old_pos - 1] == new[new_pos - 1] {
+            continue;
+        }
+        candidates.push(CommonRun { old_start: old_pos, new_start: new_pos, len });
+    }
+
+    candidates.sort_by(|a, b| b.len.cmp(&a.len).then(a.old_start.cmp(&b.old_start)));
+    let mut chain = BTreeMap::new();
+    for run in candidates {
+        let fits_before = chain.range(..run.old_start).next_back().is_none_or(|(_, prev): (_, &CommonRun)| {
+            prev.old_range().end <= run.old_start && prev.new_range().end <= run.new_start
+        });
+        let fits_after = chain.range(run.old_start..).next().is_none_or(|(_, next): (_, &CommonRun)| {
+            run.old_range().end <= next.old_start && run.new_range().end <= next.new_start
+        });
+        if fits_before && fits_after {
+            chain.insert(run.old_start, run);
+        }
+    }
+    chain.into_values().collect()
+}
+
+/// Diffs `old` into `new` by first matching `unique_blocks` of at least
+/// `min_len` items, then running the symbol table on each gap between them.
+/// Time is bounded by the suffix array rather than by how items repeat, at
+/// the cost of never pairing items across an anchor: an item that moved past
+/// one is reported as a delete and an insert.
+pub fn diff_anchored<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], min_len: usize) -> Vec<Change<'a, T>> {
+    let mut old_match = vec![usize::NONE; old.len()];
+    let mut new_match = vec![usize::NONE; new.len()];
+    let (mut old_gap, mut new_gap) = (0, 0);
+    let blocks = unique_blocks(old, new, min_len);
+    let ends = blocks.iter().map(|run| (run.old_start, run.new_start, run.len)).chain(Some((old.len(), new.len(), 0)));
+    for (old_start, new_start, len) in ends {
+        let symbols = hackel::build::<_, usize>(&old[old_gap..old_start], &new[new_gap..new_start]);
+        for (offset, matched) in symbols.new_match.iter().enumerate() {
+            if *matched != usize::NONE {
+                new_match[new_gap + offset] = old_gap + matched;
+                old_match[old_gap + matched] = new_gap + offset;
+            }
+        }
+        for offset in 0..len {
+            old_match[old_start + offset] = new_start + offset;
+            new_match[new_start + offset] = old_start + offset;
+        }
+        old_gap = old_start + len;
+        new_gap = new_start + len;
+    }
+
+    hackel::changes_from_matches(old, new, &old_match, &new_match)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_to_vec;
+
+    #[test]
+    fn suffixes_sorted() {
+        let text = b"mississippi".iter().map(|byte| *byte as usize).collect::<Vec<_>>();
+        let sa = suffix_array(&text);
+        let mut naive = (0..text.len()).collect::<Vec<_>>();
+        naive.sort_by_key(|i| &text[*i..]);
+        assert_eq!(sa, naive);
+        assert_eq!(lcp_array(&text, &sa), vec![0, 1, 1, 4, 0, 0, 1, 0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn anchors_and_gaps() {
+        // Repetitive filler around two unique blocks that swapped places.
+        let block_a = (100..140).collect::<Vec<u32>>();
+        let block_b = (200..230).collect::<Vec<u32>>();
+        let filler = vec![0u32, 1, 0, 1, 0];
+        let old = [&filler[..], &block_a, &filler, &block_b, &[7]].concat();
+        let new = [&block_b[..], &filler, &[8], &block_a, &filler[..3]].concat();
+
+        let blocks = unique_blocks(&old, &new, 8);
+        assert_eq!(blocks, vec![CommonRun { old_start: 5, new_start: 36, len: 43 }]);
+
+        let changes = diff_anchored(&old, &new, 8);
+        let mut model = old.clone();
+        apply_to_vec(&mut model, &changes);
+        assert_eq!(model, new);
+        assert_eq!(diff_anchored(&old, &old, 8).len(), 0);
+    }
+}
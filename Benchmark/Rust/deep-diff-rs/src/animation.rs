@@ -0,0 +1,118 @@
+//! Timing for animated list transitions, so every client sequences a batch
+//! update the same way: items leave, then the rest slide into place, then new
+//! items appear.
+
+use std::time::Duration;
+
+use crate::hackel::{Change, ChangeKind};
+
+/// How the changes of a batch are sequenced.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum Grouping {
+    /// Deletes, then moves and replaces, then inserts; each phase starts
+    /// when the previous one has finished.
+    #[default]
+    Phased,
+    /// Every change starts at once, apart from the stagger.
+    Simultaneous,
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AnimationOptions {
+    /// Length of each change's animation.
+    pub duration: Duration,
+    /// Delay between consecutive changes of a group, in index order.
+    pub stagger: Duration,
+    pub grouping: Grouping,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions { duration: Duration::from_millis(300), stagger: Duration::from_millis(20), grouping: Grouping::Phased }
+    }
+}
+
+/// When one change animates, relative to the start of the transition.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Timing {
+    pub delay: Duration,
+    pub duration: Duration,
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Schedule {
+    /// One timing per change, in changeset order.
+    pub timings: Vec<Timing>,
+    /// When the last animation ends.
+    pub total: Duration,
+}
+
+fn phase(kind: ChangeKind, grouping: Grouping) -> usize {
+    match (grouping, kind) {
+        (Grouping::Simultaneous, _) => 0,
+        (Grouping::Phased, ChangeKind::Delete) => 0,
+        (Grouping::Phased, ChangeKind::Move | ChangeKind::Replace) => 1,
+        (Grouping::Phased, ChangeKind::Insert) => 2,
+    }
+}
+
+/// Plans the animation of `changes`. Within a group, changes start
+/// `stagger` apart ordered by index: old index for deletes, new index for
+/// the others.
+pub fn schedule<T>(changes: &[Change<'_, T>], options: &AnimationOptions) -> Schedule {
+    let mut groups: [Vec<(usize, usize)>; 3] = Default::default();
+    for (idx, change) in changes.iter().enumerate() {
+        let index = match change {
+            Change::Insert(insert) => insert.index,
+            Change::Delete(delete) => delete.index,
+            Change::Replace(replace) => replace.index,
+            Change::Move(mv) => mv.to_index,
+        };
+        groups[phase(change.kind(), options.grouping)].push((index, idx));
+    }
+
+    let placeholder = Timing { delay: Duration::ZERO, duration: options.duration };
+    let mut timings = vec![placeholder; changes.len()];
+    let mut start = Duration::ZERO;
+    for group in groups.iter_mut().filter(|group| !group.is_empty()) {
+        group.sort_unstable();
+        let mut end = start;
+        for (order, (_, idx)) in group.iter().enumerate() {
+            let delay = start + options.stagger * order as u32;
+            timings[*idx] = Timing { delay, duration: options.duration };
+            end = end.max(delay + options.duration);
+        }
+        start = end;
+    }
+    Schedule { timings, total: start }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn phases() {
+        let old = vec!["a", "b", "c", "d"];
+        let new = vec!["d", "a", "x", "c"];
+        let changes = hackel::diff(&old, &new);
+        let options = AnimationOptions { duration: Duration::from_millis(100), stagger: Duration::from_millis(10), ..Default::default() };
+        let plan = schedule(&changes, &options);
+
+        let start_of = |kind: ChangeKind| {
+            changes.iter().zip(&plan.timings).filter(|(change, _)| change.kind() == kind).map(|(_, timing)| timing.delay.as_millis()).min()
+        };
+        assert_eq!(start_of(ChangeKind::Delete), Some(0));
+        assert_eq!(start_of(ChangeKind::Move), Some(100));
+        // Three moves staggered 10ms apart end at 220ms.
+        assert_eq!(start_of(ChangeKind::Insert), Some(220));
+        assert_eq!(plan.total, Duration::from_millis(320));
+
+        let together = schedule(&changes, &AnimationOptions { grouping: Grouping::Simultaneous, ..options });
+        let mut delays = together.timings.iter().map(|timing| timing.delay.as_millis()).collect::<Vec<_>>();
+        delays.sort_unstable();
+        assert_eq!(delays, (0..changes.len() as u128).map(|order| order * 10).collect::<Vec<_>>());
+        assert_eq!(schedule::<u8>(&[], &options).total, Duration::ZERO);
+    }
+}
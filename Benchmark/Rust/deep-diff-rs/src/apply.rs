@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use crate::error::ApplyError;
+use crate::hackel::{Change, Delete, Insert, Move, Replace};
+use crate::merge;
+
+/// Applies `changes` to `model`, a mirror of the old items, so it ends up
+/// holding the new items. The result is what a collection view shows after
+/// `performBatchUpdates` with the same changes: deletes and move sources are
+/// removed by old index, inserts and move destinations are placed by new
+/// index, and replaces then reload the item at their new index.
+///
+/// Runs in linear time and moves items instead of cloning them; only
+/// inserted and replaced items are cloned.
+///
+/// # Panics
+///
+/// Panics if `changes` do not describe a batch update of `model`, e.g. an
+/// index is out of bounds. `apply_dry_run` tells which change is at fault.
+pub fn apply_to_vec<T: Clone>(model: &mut Vec<T>, changes: &[Change<'_, T>]) {
+    let mut new_len = model.len();
+    for change in changes {
+        match change {
+            Change::Insert(_) => new_len += 1,
+            Change::Delete(_) => new_len -= 1,
+            Change::Replace(_) | Change::Move(_) => {},
+        }
+    }
+
+    let mut old = model.drain(..).map(Some).collect::<Vec<_>>();
+    let mut slots = (0..new_len).map(|_| None).collect::<Vec<_>>();
+    for change in changes {
+        match change {
+            Change::Delete(delete) => old[delete.index] = None,
+            Change::Insert(insert) => slots[insert.index] = Some(insert.item.clone()),
+            Change::Move(mv) => slots[mv.to_index] = old[mv.from_index].take(),
+            Change::Replace(_) => {},
+        }
+    }
+
+    let mut survivors = old.into_iter().flatten();
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = survivors.next();
+    }
+    assert!(survivors.next().is_none(), "changes leave old items unplaced");
+
+    for change in changes {
+        if let Change::Replace(replace) = change {
+            slots[replace.index] = Some(replace.new_item.clone());
+        }
+    }
+    model.extend(slots.into_iter().map(|slot| slot.expect("changes leave new positions empty")));
+}
+
+/// Checks that `changes` apply to a sequence of `len` items without touching
+/// any data, and returns the new length. Fails on the first change, in
+/// changeset order, whose index is out of bounds or was already used by an
+/// earlier change on the same side.
+pub fn apply_dry_run<T>(len: usize, changes: &[Change<'_, T>]) -> Result<usize, ApplyError> {
+    let (mut inserts, mut deletes) = (0, 0);
+    for change in changes {
+        match change {
+            Change::Insert(_) => inserts += 1,
+            Change::Delete(_) => deletes += 1,
+            Change::Replace(_) | Change::Move(_) => {},
+        }
+    }
+
+    let mut removed = vec![None; len];
+    let old_side = changes.iter().enumerate().try_for_each(|(change, step)| {
+        let index = match step {
+            Change::Delete(delete) => delete.index,
+            Change::Move(mv) => mv.from_index,
+            Change::Insert(_) | Change::Replace(_) => return Ok(()),
+        };
+        take(&mut removed, change, index, len)
+    });
+    // More deletes than items always trips the old side.
+    let new_len = match (len + inserts).checked_sub(deletes) {
+        Some(new_len) => new_len,
+        None => return old_side.map(|_| 0),
+    };
+
+    let (mut placed, mut replaced) = (vec![None; new_len], vec![None; new_len]);
+    let new_side = changes.iter().enumerate().try_for_each(|(change, step)| match step {
+        Change::Insert(insert) => take(&mut placed, change, insert.index, new_len),
+        Change::Move(mv) => take(&mut placed, change, mv.to_index, new_len),
+        Change::Replace(replace) => take(&mut replaced, change, replace.index, new_len),
+        Change::Delete(_) => Ok(()),
+    });
+
+    match (old_side, new_side) {
+        (Err(old), Err(new)) => Err(if old.change() <= new.change() { old } else { new }),
+        (Err(err), _) | (_, Err(err)) => Err(err),
+        (Ok(()), Ok(())) => Ok(new_len),
+    }
+}
+
+/// The changeset undoing `changes`, a batch update of `old_len` items:
+/// inserts become deletes, deletes inserts, moves swap their indices and
+/// replaces swap their items and move to the old index of the item.
+pub fn invert<'a, T>(old_len: usize, changes: &[Change<'a, T>]) -> Vec<Change<'a, T>> {
+    let side = merge::replay(old_len, changes, &mut HashMap::new());
+    let mut deletes = Vec::new();
+    let mut rest = Vec::new();
+    for change in changes {
+        match change {
+            Change::Insert(insert) => deletes.push(Change::Delete(Delete { item: insert.item, index: insert.index })),
+            Change::Delete(delete) => rest.push((delete.index, 0, Change::Insert(Insert { item: delete.item, index: delete.index }))),
+            Change::Replace(replace) => {
+                let index = side.entries[replace.index].base.expect("replaced items have an old index");
+                rest.push((index, 0, Change::Replace(Replace { old_item: replace.new_item, new_item: replace.old_item, index })));
+            },
+            Change::Move(mv) => rest.push((mv.from_index, 1, Change::Move(Move { item: mv.item, from_index: mv.to_index, to_index: mv.from_index }))),
+        }
+    }
+    deletes.sort_by_key(|change| match change {
+        Change::Delete(delete) => delete.index,
+        _ => unreachable!(),
+    });
+    rest.sort_by_key(|(index, rank, _)| (*index, *rank));
+    deletes.extend(rest.into_iter().map(|(_, _, change)| change));
+    deletes
+}
+
+fn take(owners: &mut [Option<usize>], change: usize, index: usize, len: usize) -> Result<(), ApplyError> {
+    match owners.get_mut(index) {
+        None => Err(ApplyError::OutOfBounds { change, index, len }),
+        Some(Some(prior)) => Err(ApplyError::Conflict { change, prior: *prior, index }),
+        Some(owner) => {
+            *owner = Some(change);
+            Ok(())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn mirrors_new() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["a", "b", "c"], &["c", "b", "a"]),
+            (&["a", "b", "c", "d"], &["b", "x", "d", "a"]),
+            (&["a", "a", "b", "a"], &["b", "a", "a", "c", "a"]),
+            (&[], &["a", "b"]),
+            (&["a", "b"], &[]),
+        ];
+        for (old, new) in cases {
+            let mut model = old.to_vec();
+            apply_to_vec(&mut model, &hackel::diff(old, new));
+            assert_eq!(&model, new);
+        }
+    }
+
+    #[test]
+    fn replaced_and_moved() {
+        let old = vec![(1, "a"), (2, "b"), (3, "c")];
+        let new = vec![(3, "c"), (1, "A"), (2, "b")];
+        let changes = hackel::diff_by_key(&old, &new, |row| row.0);
+
+        let mut model = old.clone();
+        apply_to_vec(&mut model, &changes);
+        assert_eq!(model, new);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        let old = vec![1, 2, 3];
+        let changes = hackel::diff(&old, &[1]);
+        apply_to_vec(&mut vec![1, 2], &changes);
+    }
+
+    #[test]
+    fn inverse_restores_old() {
+        let old = vec![(1, "a"), (2, "b"), (3, "c"), (4, "d")];
+        let new = vec![(3, "C"), (5, "e"), (1, "a"), (4, "d")];
+        let changes = hackel::diff_by_key(&old, &new, |row| row.0);
+        let inverse = invert(old.len(), &changes);
+
+        let mut model = new.clone();
+        apply_to_vec(&mut model, &inverse);
+        assert_eq!(model, old);
+        assert_eq!(apply_dry_run(new.len(), &invert(new.len(), &inverse)), Ok(new.len()));
+    }
+
+    #[test]
+    fn dry_run() {
+        let old = vec![1, 2, 3, 4];
+        let new = vec![4, 5, 1, 2];
+        let changes = hackel::diff(&old, &new);
+        assert_eq!(apply_dry_run(old.len(), &changes), Ok(new.len()));
+        assert_eq!(apply_dry_run(2, &changes), Err(ApplyError::OutOfBounds { change: 0, index: 2, len: 2 }));
+
+        let mut doubled = hackel::diff(&old, &[1, 2]);
+        doubled.extend(hackel::diff(&old, &[1, 2]));
+        assert_eq!(apply_dry_run(old.len(), &doubled), Err(ApplyError::Conflict { change: 2, prior: 0, index: 2 }));
+    }
+}
@@ -0,0 +1,204 @@
+//! Reproducible workloads and the timing loop shared by the benchmark binaries.
+
+use std::time::{Duration, Instant};
+
+use self::stats::Summary;
+
+pub mod datasets;
+pub mod export;
+pub mod ffi;
+pub mod report;
+pub mod stats;
+
+/// SplitMix64's finalizer, also used on its own to scatter hashes.
+pub(crate) const fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// SplitMix64, good enough for reproducible workloads without pulling in `rand`.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.0)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn chance(&mut self, ratio: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < ratio
+    }
+
+    pub fn uuid(&mut self) -> String {
+        format!("{:016X}{:016X}", self.next_u64(), self.next_u64())
+    }
+
+    pub fn string(&mut self, length: usize) -> String {
+        const ALPHABET: &[u8] = b"0123456789ABCDEF";
+        (0..length).map(|_| ALPHABET[self.below(ALPHABET.len())] as char).collect()
+    }
+}
+
+/// A random list of strings and a mutated, partly shuffled copy of it.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Workload {
+    pub items: usize,
+    /// Share of `items` removed, replaced or inserted, in `0.0..=1.0`.
+    pub mutations: f64,
+    /// Share of the new list shuffled in place.
+    pub shuffle: f64,
+    /// Chance that an old item repeats an earlier one.
+    pub duplicates: f64,
+    /// Length of each string.
+    pub length: usize,
+    pub seed: u64,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Workload {
+            items: 10_000,
+            mutations: 0.05,
+            shuffle: 0.02,
+            duplicates: 0.0,
+            length: 36,
+            seed: 1,
+        }
+    }
+}
+
+impl Workload {
+    pub fn generate(&self) -> (Vec<String>, Vec<String>) {
+        let mut rng = Rng(self.seed);
+
+        let mut old: Vec<String> = Vec::with_capacity(self.items);
+        for _ in 0..self.items {
+            let item = if !old.is_empty() && rng.chance(self.duplicates) {
+                old[rng.below(old.len())].clone()
+            } else {
+                rng.string(self.length)
+            };
+            old.push(item);
+        }
+
+        let mut new = old.clone();
+        let mutations = (self.items as f64 * self.mutations) as usize;
+        for _ in 0..mutations {
+            match rng.below(3) {
+                0 if !new.is_empty() => {
+                    let index = rng.below(new.len());
+                    new.remove(index);
+                },
+                1 if !new.is_empty() => {
+                    let index = rng.below(new.len());
+                    new[index] = rng.string(self.length);
+                },
+                _ => {
+                    let index = rng.below(new.len() + 1);
+                    let item = rng.string(self.length);
+                    new.insert(index, item);
+                },
+            }
+        }
+
+        let shuffled = ((new.len() as f64 * self.shuffle) as usize).min(new.len());
+        for i in (1..shuffled).rev() {
+            let j = rng.below(i + 1);
+            new.swap(i, j);
+        }
+
+        (old, new)
+    }
+}
+
+/// Parses a percentage such as `5` or `5%` into a ratio.
+pub fn parse_percent(value: &str) -> Option<f64> {
+    value.trim_end_matches('%').parse().ok().filter(|percent| (0.0..=100.0).contains(percent)).map(|percent: f64| percent / 100.0)
+}
+
+/// Nearest-rank percentile of sorted timings; zero if there are none.
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[rank]
+}
+
+/// Times `iterations` runs of `diff`, which returns its number of changes,
+/// and returns the timings in run order with the last run's change count.
+pub fn time_diffs<F: FnMut() -> usize>(iterations: usize, mut diff: F) -> (Vec<Duration>, usize) {
+    let mut timings = Vec::with_capacity(iterations);
+    let mut changes = 0;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        changes = diff();
+        timings.push(start.elapsed());
+    }
+    (timings, changes)
+}
+
+/// The summary the benchmark binaries print: percentiles of `timings`, then
+/// their mean and spread after outlier rejection.
+pub fn format_report(old_len: usize, new_len: usize, changes: usize, timings: &[Duration]) -> String {
+    let mut report = format!("items: {} -> {}, changes: {}, iterations: {}\n", old_len, new_len, changes, timings.len());
+    let mut sorted = timings.to_vec();
+    sorted.sort();
+    if let (Some(min), Some(max)) = (sorted.first(), sorted.last()) {
+        report += &format!("min: {:?}\n", min);
+        report += &format!("p50: {:?}\n", percentile(&sorted, 0.50));
+        report += &format!("p90: {:?}\n", percentile(&sorted, 0.90));
+        report += &format!("p99: {:?}\n", percentile(&sorted, 0.99));
+        report += &format!("max: {:?}\n", max);
+    }
+    if let Some(summary) = Summary::new(&sorted) {
+        report += &format!("mean: {:?} ± {:?}, median: {:?}, p95: {:?} ({} outliers rejected)\n", summary.mean, summary.stddev, summary.median, summary.p95, summary.rejected);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let workload = Workload { items: 500, duplicates: 0.1, ..Workload::default() };
+        assert_eq!(workload.generate(), workload.generate());
+        assert_ne!(workload.generate(), Workload { seed: 2, ..workload }.generate());
+    }
+
+    #[test]
+    fn percents() {
+        assert_eq!(parse_percent("5%"), Some(0.05));
+        assert_eq!(parse_percent("100"), Some(1.0));
+        assert_eq!(parse_percent("101"), None);
+        assert_eq!(parse_percent("x"), None);
+    }
+
+    #[test]
+    fn percentiles() {
+        let timings = [1, 2, 3, 4, 5].map(Duration::from_millis);
+        assert_eq!(percentile(&timings, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&timings, 0.5), Duration::from_millis(3));
+        assert_eq!(percentile(&timings, 1.0), Duration::from_millis(5));
+        assert_eq!(percentile(&[], 0.5), Duration::ZERO);
+    }
+
+    #[test]
+    fn report() {
+        let (timings, changes) = time_diffs(3, || 7);
+        assert_eq!((timings.len(), changes), (3, 7));
+
+        let timings = [Duration::from_millis(3), Duration::from_millis(1), Duration::from_millis(2)];
+        let report = format_report(10, 12, 4, &timings);
+        assert!(report.starts_with("items: 10 -> 12, changes: 4, iterations: 3\nmin: 1ms\np50: 2ms\n"), "{}", report);
+        assert!(report.contains("max: 3ms\n"), "{}", report);
+        assert_eq!(format_report(0, 0, 0, &[]), "items: 0 -> 0, changes: 0, iterations: 0\n");
+    }
+}
@@ -0,0 +1,141 @@
+//! Seeded workloads shared by the benches, the CLI and the FFI harness, so
+//! every harness measures the same inputs.
+
+use super::Rng;
+
+/// A list of UUID-like strings, as the Swift harness generates.
+fn uuids(rng: &mut Rng, count: usize) -> Vec<String> {
+    (0..count).map(|_| rng.uuid()).collect()
+}
+
+/// Shuffles `items[range]` in place with Fisher-Yates.
+fn shuffle_range(rng: &mut Rng, items: &mut [String], start: usize, end: usize) {
+    for i in (start + 1..end).rev() {
+        let j = start + rng.below(i - start + 1);
+        items.swap(i, j);
+    }
+}
+
+/// Mirrors `BenchmarkData` in the Swift harness: deletes, inserts and
+/// shuffles fixed ranges of a list of `count` UUIDs.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct SwiftDataset {
+    pub count: usize,
+    pub delete: (usize, usize),
+    pub insert: (usize, usize),
+    pub shuffle: (usize, usize),
+}
+
+/// The datasets `main.swift` runs.
+pub const SWIFT_DATASETS: &[SwiftDataset] = &[
+    SwiftDataset { count: 5_000, delete: (2_000, 3_000), insert: (3_000, 4_000), shuffle: (0, 200) },
+    SwiftDataset { count: 10_000, delete: (4_000, 6_000), insert: (6_000, 8_000), shuffle: (0, 400) },
+    SwiftDataset { count: 100_000, delete: (20_000, 30_000), insert: (30_000, 40_000), shuffle: (0, 2_000) },
+];
+
+impl SwiftDataset {
+    pub fn generate(&self, rng: &mut Rng) -> (Vec<String>, Vec<String>) {
+        let old = uuids(rng, self.count);
+        let mut new = old.clone();
+
+        new.drain(self.delete.0..self.delete.1);
+        let inserted = uuids(rng, self.insert.1 - self.insert.0);
+        new.splice(self.insert.0..self.insert.0, inserted);
+        shuffle_range(rng, &mut new, self.shuffle.0, self.shuffle.1);
+
+        (old, new)
+    }
+}
+
+/// Update patterns of list UIs.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Scenario {
+    /// 5% of the feed is removed and as many fresh items arrive at random positions.
+    FeedRefresh,
+    /// A page of 50 items is appended at the end.
+    Pagination,
+    /// 20 new items land on top and a handful of bumped items move up with them.
+    PullToRefresh,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 3] = [Scenario::FeedRefresh, Scenario::Pagination, Scenario::PullToRefresh];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scenario::FeedRefresh => "feed-refresh",
+            Scenario::Pagination => "pagination",
+            Scenario::PullToRefresh => "pull-to-refresh",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Scenario> {
+        Scenario::ALL.iter().copied().find(|scenario| scenario.name() == name)
+    }
+
+    pub fn generate(self, items: usize, seed: u64) -> (Vec<String>, Vec<String>) {
+        let mut rng = Rng(seed);
+        let old = uuids(&mut rng, items);
+        let mut new = old.clone();
+
+        match self {
+            Scenario::FeedRefresh => {
+                let churn = items / 20;
+                for _ in 0..churn.min(new.len()) {
+                    let index = rng.below(new.len());
+                    new.remove(index);
+                }
+                for _ in 0..churn {
+                    let index = rng.below(new.len() + 1);
+                    new.insert(index, rng.uuid());
+                }
+            },
+            Scenario::Pagination => new.extend(uuids(&mut rng, 50)),
+            Scenario::PullToRefresh => {
+                let mut top = uuids(&mut rng, 20);
+                for _ in 0..(items / 100).min(new.len()) {
+                    let index = rng.below(new.len());
+                    top.push(new.remove(index));
+                }
+                new.splice(0..0, top);
+            },
+        }
+
+        (old, new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scenarios_are_reproducible() {
+        for scenario in Scenario::ALL.iter() {
+            assert_eq!(scenario.generate(1_000, 7), scenario.generate(1_000, 7));
+            assert_eq!(Scenario::from_name(scenario.name()), Some(*scenario));
+        }
+    }
+
+    #[test]
+    fn scenario_shapes() {
+        let (old, new) = Scenario::FeedRefresh.generate(1_000, 1);
+        assert_eq!(old.len(), new.len());
+
+        let (old, new) = Scenario::Pagination.generate(1_000, 1);
+        assert_eq!(new[..old.len()], old[..]);
+        assert_eq!(new.len(), 1_050);
+
+        let (old, new) = Scenario::PullToRefresh.generate(1_000, 1);
+        assert_eq!(new.len(), 1_020);
+        assert!(!old.contains(&new[0]));
+    }
+
+    #[test]
+    fn swift_dataset() {
+        let dataset = SWIFT_DATASETS[0];
+        let (old, new) = dataset.generate(&mut Rng(1));
+        assert_eq!(old.len(), dataset.count);
+        assert_eq!(new.len(), dataset.count);
+    }
+}
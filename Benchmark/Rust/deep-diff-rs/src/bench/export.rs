@@ -0,0 +1,144 @@
+//! Writes timings in the layout `BenchmarkRunner` in the Swift harness
+//! prints, so Rust and Swift results can be merged by one script.
+//!
+//! The Swift harness only prints Markdown; `write_csv` and `write_json` carry
+//! the same fields one row per measurement.
+
+use std::io::{self, Write};
+
+use super::datasets::SwiftDataset;
+
+/// The best time of one benchmark on one dataset, in seconds as
+/// `CFAbsoluteTime` reports them.
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Measurement {
+    pub count: usize,
+    pub deleted: usize,
+    pub inserted: usize,
+    pub shuffled: usize,
+    pub name: String,
+    pub seconds: f64,
+}
+
+impl Measurement {
+    pub fn new(dataset: &SwiftDataset, name: &str, seconds: f64) -> Self {
+        Measurement {
+            count: dataset.count,
+            deleted: dataset.delete.1 - dataset.delete.0,
+            inserted: dataset.insert.1 - dataset.insert.0,
+            shuffled: dataset.shuffle.1 - dataset.shuffle.0,
+            name: name.to_string(),
+            seconds,
+        }
+    }
+
+    pub(super) fn same_dataset(&self, other: &Measurement) -> bool {
+        (self.count, self.deleted, self.inserted, self.shuffled) == (other.count, other.deleted, other.inserted, other.shuffled)
+    }
+}
+
+/// `String.localizedStringWithFormat("%d", n)` in the en_US locale.
+pub(super) fn grouped(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!("{:<width$}", text, width = width)
+}
+
+/// One table per dataset, in the order the measurements first mention it.
+pub fn write_markdown<W: Write>(out: &mut W, measurements: &[Measurement]) -> io::Result<()> {
+    let mut done = vec![false; measurements.len()];
+    for (idx, first) in measurements.iter().enumerate() {
+        if done[idx] {
+            continue;
+        }
+        let rows = measurements.iter().enumerate().filter(|(_, m)| m.same_dataset(first)).map(|(idx, m)| {
+            done[idx] = true;
+            m
+        }).collect::<Vec<_>>();
+        let width = rows.iter().map(|m| m.name.chars().count()).max().unwrap_or(0);
+
+        writeln!(
+            out,
+            "#### - From {} elements to {} deleted, {} inserted and {} shuffled",
+            grouped(first.count),
+            grouped(first.deleted),
+            grouped(first.inserted),
+            grouped(first.shuffled),
+        )?;
+        writeln!(out)?;
+        writeln!(out, "|{}|{}|", pad("", width), pad("Time(sec)", width))?;
+        writeln!(out, "|:{}|{}:|", "-".repeat(width.saturating_sub(1)), "-".repeat(width.saturating_sub(1)))?;
+        for m in rows {
+            writeln!(out, "|{}|{}|", pad(&m.name, width), pad(&format!("`{:.4}`", m.seconds), width))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn write_csv<W: Write>(out: &mut W, measurements: &[Measurement]) -> io::Result<()> {
+    writeln!(out, "count,deleted,inserted,shuffled,name,seconds")?;
+    for m in measurements {
+        writeln!(out, "{},{},{},{},\"{}\",{}", m.count, m.deleted, m.inserted, m.shuffled, m.name.replace('"', "\"\""), m.seconds)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(out: &mut W, measurements: &[Measurement]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, measurements)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::datasets::SWIFT_DATASETS;
+
+    #[test]
+    fn markdown_matches_swift_layout() {
+        let measurements = vec![
+            Measurement::new(&SWIFT_DATASETS[0], "DeepDiff", 0.0851),
+            Measurement::new(&SWIFT_DATASETS[0], "DeepDiff-rs", 0.00312),
+        ];
+        let mut out = Vec::new();
+        write_markdown(&mut out, &measurements).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+#### - From 5,000 elements to 1,000 deleted, 1,000 inserted and 200 shuffled
+
+|           |Time(sec)  |
+|:----------|----------:|
+|DeepDiff   |`0.0851`   |
+|DeepDiff-rs|`0.0031`   |
+
+");
+    }
+
+    #[test]
+    fn csv_rows() {
+        let measurements = vec![Measurement::new(&SWIFT_DATASETS[2], "DeepDiff-rs", 0.25)];
+        let mut out = Vec::new();
+        write_csv(&mut out, &measurements).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "count,deleted,inserted,shuffled,name,seconds\n100000,10000,10000,2000,\"DeepDiff-rs\",0.25\n");
+    }
+
+    #[test]
+    fn grouping() {
+        assert_eq!(grouped(0), "0");
+        assert_eq!(grouped(999), "999");
+        assert_eq!(grouped(100_000), "100,000");
+        assert_eq!(grouped(1_234_567), "1,234,567");
+    }
+}
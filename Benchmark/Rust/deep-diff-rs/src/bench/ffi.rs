@@ -0,0 +1,187 @@
+//! Measurement entry points for the iOS benchmark app: timings under one
+//! warmup and iteration policy shared with the Swift implementations, and
+//! the memory a diff takes.
+
+use std::hint::black_box;
+use std::os::raw::{c_char, c_int};
+use std::slice;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::stats::Summary;
+use crate::hackel;
+
+static WARMUP_ITERS: AtomicU32 = AtomicU32::new(1);
+static MEASURED_ITERS: AtomicU32 = AtomicU32::new(3);
+static MIN_TIME_NS: AtomicU64 = AtomicU64::new(0);
+
+/// How `deep_diff_bench_run` measures.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Policy {
+    /// Untimed runs before measuring.
+    pub warmup_iters: u32,
+    /// Timed runs at least.
+    pub measured_iters: u32,
+    /// Keeps timing past `measured_iters` until this much wall-clock time
+    /// passed since the first timed run, so runs too fast for the clock to
+    /// register still end.
+    pub min_time: Duration,
+}
+
+impl Policy {
+    pub fn current() -> Policy {
+        Policy {
+            warmup_iters: WARMUP_ITERS.load(Ordering::Relaxed),
+            measured_iters: MEASURED_ITERS.load(Ordering::Relaxed),
+            min_time: Duration::from_nanos(MIN_TIME_NS.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Runs `f` under this policy and returns the timing of every measured run.
+    pub fn measure<F: FnMut()>(&self, mut f: F) -> Vec<Duration> {
+        for _ in 0..self.warmup_iters {
+            f();
+        }
+        let mut timings = Vec::with_capacity(self.measured_iters as usize);
+        let began = Instant::now();
+        while timings.len() < self.measured_iters as usize || began.elapsed() < self.min_time {
+            let start = Instant::now();
+            f();
+            timings.push(start.elapsed());
+        }
+        timings
+    }
+}
+
+/// Timings of one `deep_diff_bench_run`, in nanoseconds after outlier
+/// rejection; all zero if nothing was measured.
+#[repr(C)]
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct DeepDiffBenchResult {
+    pub iterations: u64,
+    pub rejected: u64,
+    pub mean_ns: u64,
+    pub median_ns: u64,
+    pub p95_ns: u64,
+    pub stddev_ns: u64,
+}
+
+impl From<Summary> for DeepDiffBenchResult {
+    fn from(summary: Summary) -> Self {
+        DeepDiffBenchResult {
+            iterations: summary.samples as u64,
+            rejected: summary.rejected as u64,
+            mean_ns: summary.mean.as_nanos() as u64,
+            median_ns: summary.median.as_nanos() as u64,
+            p95_ns: summary.p95.as_nanos() as u64,
+            stddev_ns: summary.stddev.as_nanos() as u64,
+        }
+    }
+}
+
+/// Sets the policy later `deep_diff_bench_run` calls measure with. A
+/// `measured_iters` of zero with `min_time_ns` zero measures nothing.
+#[no_mangle]
+pub extern "C" fn deep_diff_bench_configure(warmup_iters: u32, measured_iters: u32, min_time_ns: u64) {
+    WARMUP_ITERS.store(warmup_iters, Ordering::Relaxed);
+    MEASURED_ITERS.store(measured_iters, Ordering::Relaxed);
+    MIN_TIME_NS.store(min_time_ns, Ordering::Relaxed);
+}
+
+/// Measures the diff `diffWithString` runs under the configured policy.
+///
+/// # Safety
+///
+/// `old` and `new` must point to `old_len` and `new_len` valid C string pointers.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_bench_run(old: *const *const c_char, old_len: c_int, new: *const *const c_char, new_len: c_int) -> DeepDiffBenchResult {
+    let old = slice::from_raw_parts(old, old_len as usize);
+    let new = slice::from_raw_parts(new, new_len as usize);
+    let timings = Policy::current().measure(|| {
+        black_box(hackel::diff_indexed::<_, u32>(black_box(old), black_box(new)));
+    });
+    Summary::new(&timings).map(DeepDiffBenchResult::from).unwrap_or_default()
+}
+
+/// Memory one diff took on the calling thread.
+#[repr(C)]
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct DeepDiffMemoryResult {
+    /// Most bytes live at once beyond those live before the diff.
+    pub peak_bytes: u64,
+    pub allocations: u64,
+}
+
+/// Runs the diff `diffWithString` runs once under the counting allocator.
+/// The result is all zero unless the library was built with the
+/// `counting-allocator` feature.
+///
+/// # Safety
+///
+/// `old` and `new` must point to `old_len` and `new_len` valid C string pointers.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_bench_memory(old: *const *const c_char, old_len: c_int, new: *const *const c_char, new_len: c_int) -> DeepDiffMemoryResult {
+    let old = slice::from_raw_parts(old, old_len as usize);
+    let new = slice::from_raw_parts(new, new_len as usize);
+    #[cfg(feature = "stats")]
+    {
+        let (_, allocations) = crate::stats::measure(|| hackel::diff_indexed::<_, u32>(old, new));
+        DeepDiffMemoryResult { peak_bytes: allocations.peak_bytes as u64, allocations: allocations.allocations as u64 }
+    }
+    #[cfg(not(feature = "stats"))]
+    {
+        black_box(hackel::diff_indexed::<_, u32>(old, new));
+        DeepDiffMemoryResult::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn policy_counts() {
+        let mut runs = 0;
+        let timings = Policy { warmup_iters: 2, measured_iters: 5, min_time: Duration::ZERO }.measure(|| runs += 1);
+        assert_eq!((runs, timings.len()), (7, 5));
+
+        let start = Instant::now();
+        let timings = Policy { warmup_iters: 0, measured_iters: 1, min_time: Duration::from_millis(5) }.measure(|| std::thread::sleep(Duration::from_millis(1)));
+        assert!(timings.len() > 1 && start.elapsed() >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn policy_ends_on_instant_runs() {
+        let timings = Policy { warmup_iters: 0, measured_iters: 1, min_time: Duration::from_millis(5) }.measure(|| {});
+        assert!(!timings.is_empty());
+    }
+
+    fn c_strings(items: &[&str]) -> Vec<CString> {
+        items.iter().map(|item| CString::new(*item).unwrap()).collect()
+    }
+
+    #[test]
+    fn run_over_ffi() {
+        let (old, new) = (c_strings(&["a", "b", "c"]), c_strings(&["c", "a"]));
+        let old_ptrs = old.iter().map(|item| item.as_ptr()).collect::<Vec<_>>();
+        let new_ptrs = new.iter().map(|item| item.as_ptr()).collect::<Vec<_>>();
+
+        deep_diff_bench_configure(1, 4, 0);
+        let result = unsafe { deep_diff_bench_run(old_ptrs.as_ptr(), 3, new_ptrs.as_ptr(), 2) };
+        assert_eq!(result.iterations + result.rejected, 4);
+        assert!(result.median_ns <= result.p95_ns);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn memory_over_ffi() {
+        let (old, new) = (c_strings(&["a", "b", "c"]), c_strings(&["c", "x", "a"]));
+        let old_ptrs = old.iter().map(|item| item.as_ptr()).collect::<Vec<_>>();
+        let new_ptrs = new.iter().map(|item| item.as_ptr()).collect::<Vec<_>>();
+
+        let result = unsafe { deep_diff_bench_memory(old_ptrs.as_ptr(), 3, new_ptrs.as_ptr(), 3) };
+        assert!(result.allocations > 0);
+        assert!(result.peak_bytes > 0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Merges Swift harness and Rust exporter timings into one table per
+//! dataset, with each implementation's speedup over a reference one.
+
+use std::io::{self, Write};
+
+use super::export::{grouped, Measurement};
+
+/// Parses the Markdown `BenchmarkRunner` prints, and `export::write_markdown`
+/// writes, skipping any other console output around the tables.
+pub fn parse_markdown(text: &str) -> Result<Vec<Measurement>, String> {
+    const PREFIX: &str = "#### - From ";
+    let mut measurements = Vec::new();
+    let mut dataset = None;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix(PREFIX) {
+            let numbers = header
+                .split(' ')
+                .filter_map(|word| word.replace(',', "").parse::<usize>().ok())
+                .collect::<Vec<_>>();
+            match numbers.as_slice() {
+                [count, deleted, inserted, shuffled] => dataset = Some((*count, *deleted, *inserted, *shuffled)),
+                _ => return Err(format!("line {}: malformed dataset header", idx + 1)),
+            }
+            continue;
+        }
+
+        let cells = match line.strip_prefix('|').and_then(|line| line.strip_suffix('|')) {
+            Some(row) => row.split('|').map(str::trim).collect::<Vec<_>>(),
+            None => continue,
+        };
+        match cells.as_slice() {
+            [name, time] if !name.is_empty() && !name.starts_with(':') => {
+                let (count, deleted, inserted, shuffled) = dataset.ok_or_else(|| format!("line {}: row before any dataset header", idx + 1))?;
+                let seconds = time.trim_matches('`').parse().map_err(|_| format!("line {}: invalid time {}", idx + 1, time))?;
+                measurements.push(Measurement { count, deleted, inserted, shuffled, name: name.to_string(), seconds });
+            },
+            _ => {},
+        }
+    }
+    Ok(measurements)
+}
+
+struct Row<'a> {
+    source: &'a str,
+    measurement: &'a Measurement,
+    /// Reference time over this time.
+    speedup: Option<f64>,
+}
+
+/// Groups every source's measurements by dataset, in the order datasets
+/// first appear. The reference is looked up in the first source naming it.
+fn tables<'a>(sources: &[(&'a str, &'a [Measurement])], reference: &str) -> Vec<Vec<Row<'a>>> {
+    let all = sources.iter().flat_map(|(source, measurements)| measurements.iter().map(move |m| (*source, m))).collect::<Vec<_>>();
+    let mut tables: Vec<Vec<Row<'a>>> = Vec::new();
+    for (_, measurement) in &all {
+        if tables.iter().any(|table| table[0].measurement.same_dataset(measurement)) {
+            continue;
+        }
+        let rows = all.iter().filter(|(_, m)| m.same_dataset(measurement)).collect::<Vec<_>>();
+        let reference = rows.iter().find(|(_, m)| m.name == reference).map(|(_, m)| m.seconds);
+        tables.push(
+            rows.into_iter()
+                .map(|(source, measurement)| Row { source, measurement, speedup: reference.map(|seconds| seconds / measurement.seconds) })
+                .collect(),
+        );
+    }
+    tables
+}
+
+fn header(m: &Measurement) -> String {
+    format!("From {} elements to {} deleted, {} inserted and {} shuffled", grouped(m.count), grouped(m.deleted), grouped(m.inserted), grouped(m.shuffled))
+}
+
+fn speedup(row: &Row<'_>) -> String {
+    row.speedup.map_or_else(|| "-".to_string(), |speedup| format!("{:.2}x", speedup))
+}
+
+/// Writes one Markdown table per dataset over all `(source, measurements)`.
+pub fn write_markdown<W: Write>(out: &mut W, sources: &[(&str, &[Measurement])], reference: &str) -> io::Result<()> {
+    for rows in tables(sources, reference) {
+        writeln!(out, "#### - {}", header(rows[0].measurement))?;
+        writeln!(out)?;
+        writeln!(out, "|Implementation|Source|Time(sec)|Speedup vs {}|", reference)?;
+        writeln!(out, "|:--|:--|--:|--:|")?;
+        for row in &rows {
+            writeln!(out, "|{}|{}|`{:.4}`|{}|", row.measurement.name, row.source, row.measurement.seconds, speedup(row))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Writes the same tables as `write_markdown` as an HTML fragment.
+pub fn write_html<W: Write>(out: &mut W, sources: &[(&str, &[Measurement])], reference: &str) -> io::Result<()> {
+    for rows in tables(sources, reference) {
+        writeln!(out, "<h4>{}</h4>", header(rows[0].measurement))?;
+        writeln!(out, "<table>")?;
+        writeln!(out, "<tr><th>Implementation</th><th>Source</th><th>Time(sec)</th><th>Speedup vs {}</th></tr>", escape(reference))?;
+        for row in &rows {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td align=\"right\">{:.4}</td><td align=\"right\">{}</td></tr>",
+                escape(&row.measurement.name),
+                escape(row.source),
+                row.measurement.seconds,
+                speedup(row),
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::datasets::SWIFT_DATASETS;
+    use crate::bench::export;
+
+    const SWIFT_OUTPUT: &str = "\
+Running on iPhone
+#### - From 5,000 elements to 1,000 deleted, 1,000 inserted and 200 shuffled
+
+|              |Time(sec)     |
+|:-------------|-------------:|
+|DeepDiff      |`0.0800`      |
+|DeepDiff-rs   |`0.0040`      |
+
+";
+
+    #[test]
+    fn parses_swift_console() {
+        let measurements = parse_markdown(SWIFT_OUTPUT).unwrap();
+        assert_eq!(measurements, vec![Measurement::new(&SWIFT_DATASETS[0], "DeepDiff", 0.08), Measurement::new(&SWIFT_DATASETS[0], "DeepDiff-rs", 0.004)]);
+
+        let mut exported = Vec::new();
+        export::write_markdown(&mut exported, &measurements).unwrap();
+        assert_eq!(parse_markdown(std::str::from_utf8(&exported).unwrap()).unwrap(), measurements);
+        assert!(parse_markdown("|DeepDiff|`0.1`|").is_err());
+    }
+
+    #[test]
+    fn speedups() {
+        let swift = parse_markdown(SWIFT_OUTPUT).unwrap();
+        let rust = vec![Measurement::new(&SWIFT_DATASETS[0], "hackel::diff", 0.002), Measurement::new(&SWIFT_DATASETS[1], "hackel::diff", 0.005)];
+        let mut out = Vec::new();
+        write_markdown(&mut out, &[("Swift", &swift), ("Rust", &rust)], "DeepDiff").unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+#### - From 5,000 elements to 1,000 deleted, 1,000 inserted and 200 shuffled
+
+|Implementation|Source|Time(sec)|Speedup vs DeepDiff|
+|:--|:--|--:|--:|
+|DeepDiff|Swift|`0.0800`|1.00x|
+|DeepDiff-rs|Swift|`0.0040`|20.00x|
+|hackel::diff|Rust|`0.0020`|40.00x|
+
+#### - From 10,000 elements to 2,000 deleted, 2,000 inserted and 400 shuffled
+
+|Implementation|Source|Time(sec)|Speedup vs DeepDiff|
+|:--|:--|--:|--:|
+|hackel::diff|Rust|`0.0050`|-|
+
+");
+    }
+}
@@ -0,0 +1,89 @@
+//! Summary statistics over raw iteration timings, so harnesses report more
+//! than a single run.
+
+use std::time::Duration;
+
+use super::percentile;
+
+/// Tukey's fence factor: timings further than this many interquartile
+/// ranges outside the quartiles count as outliers.
+const FENCE: f64 = 1.5;
+
+/// Statistics of the timings left after outlier rejection.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Summary {
+    /// Timings kept.
+    pub samples: usize,
+    /// Timings dropped as outliers.
+    pub rejected: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    /// Sample standard deviation; zero for a single timing.
+    pub stddev: Duration,
+}
+
+/// The sorted timings inside Tukey's fences. Outliers sit at both ends of a
+/// sorted slice, so the rest is a subslice.
+pub fn without_outliers(sorted: &[Duration]) -> &[Duration] {
+    if sorted.len() < 4 {
+        return sorted;
+    }
+    let q1 = percentile(sorted, 0.25).as_secs_f64();
+    let q3 = percentile(sorted, 0.75).as_secs_f64();
+    let (low, high) = (q1 - FENCE * (q3 - q1), q3 + FENCE * (q3 - q1));
+    let start = sorted.iter().position(|timing| timing.as_secs_f64() >= low).unwrap_or(sorted.len());
+    let end = sorted.iter().rposition(|timing| timing.as_secs_f64() <= high).map_or(start, |idx| idx + 1);
+    &sorted[start..end.max(start)]
+}
+
+impl Summary {
+    /// Summarizes `timings` in any order, or `None` if there are none.
+    pub fn new(timings: &[Duration]) -> Option<Summary> {
+        let mut sorted = timings.to_vec();
+        sorted.sort();
+        let kept = without_outliers(&sorted);
+        if kept.is_empty() {
+            return None;
+        }
+
+        let n = kept.len() as f64;
+        let mean = kept.iter().map(Duration::as_secs_f64).sum::<f64>() / n;
+        let variance = if kept.len() > 1 { kept.iter().map(|timing| (timing.as_secs_f64() - mean).powi(2)).sum::<f64>() / (n - 1.0) } else { 0.0 };
+        Some(Summary {
+            samples: kept.len(),
+            rejected: sorted.len() - kept.len(),
+            mean: Duration::from_secs_f64(mean),
+            median: percentile(kept, 0.50),
+            p95: percentile(kept, 0.95),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|value| Duration::from_millis(*value)).collect()
+    }
+
+    #[test]
+    fn rejects_spikes() {
+        let summary = Summary::new(&millis(&[12, 10, 11, 250, 10, 11, 12, 1, 11, 10])).unwrap();
+        assert_eq!(summary.samples, 8);
+        assert_eq!(summary.rejected, 2);
+        assert_eq!(summary.median, Duration::from_millis(11));
+        assert_eq!(summary.p95, Duration::from_millis(12));
+        assert!((summary.mean.as_secs_f64() - 0.010_875).abs() < 1e-9);
+        assert!((summary.stddev.as_secs_f64() - 0.000_834_5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn small_and_empty() {
+        assert_eq!(Summary::new(&[]), None);
+        let summary = Summary::new(&millis(&[5])).unwrap();
+        assert_eq!((summary.samples, summary.mean, summary.stddev), (1, Duration::from_millis(5), Duration::ZERO));
+    }
+}
@@ -0,0 +1,166 @@
+//! Fails when the criterion scenarios got slower than the committed baseline.
+//!
+//! Runs `cargo bench --bench scenarios`, reads the median of every benchmark
+//! from criterion's output directory and compares it with
+//! `benches/baseline.json`, a map from criterion's full benchmark id to the
+//! median in nanoseconds. `--update` rewrites the baseline instead.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use deep_diff_rs::bench::parse_percent;
+use serde_json::Value;
+
+const USAGE: &str = "usage: bench-gate [--baseline PATH] [--threshold PCT] [--criterion-dir PATH] [--no-run] [--update]";
+
+struct Config {
+    baseline: PathBuf,
+    threshold: f64,
+    criterion_dir: PathBuf,
+    run: bool,
+    update: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        // `cargo bench` runs in the manifest directory, so a relative
+        // CARGO_TARGET_DIR is relative to it too.
+        let target_dir = env::var_os("CARGO_TARGET_DIR").map_or_else(|| manifest_dir.join("target"), |dir| manifest_dir.join(dir));
+        Config {
+            baseline: manifest_dir.join("benches/baseline.json"),
+            threshold: 0.10,
+            criterion_dir: target_dir.join("criterion"),
+            run: true,
+            update: false,
+        }
+    }
+}
+
+fn parse_args() -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--no-run" => config.run = false,
+            "--update" => config.update = true,
+            _ => {
+                let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+                match flag.as_str() {
+                    "--baseline" => config.baseline = PathBuf::from(value),
+                    "--threshold" => config.threshold = parse_percent(&value).ok_or_else(|| format!("{} must be between 0 and 100", flag))?,
+                    "--criterion-dir" => config.criterion_dir = PathBuf::from(value),
+                    _ => return Err(format!("unknown flag {}", flag)),
+                }
+            },
+        }
+    }
+    Ok(config)
+}
+
+fn run_scenarios() -> Result<(), String> {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["bench", "--bench", "scenarios"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .map_err(|error| format!("cannot run cargo bench: {}", error))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("cargo bench failed: {}", status))
+    }
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    serde_json::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Medians in nanoseconds of the latest run, keyed by full benchmark id.
+fn read_medians(dir: &Path) -> Result<BTreeMap<String, f64>, String> {
+    let mut medians = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|error| format!("{}: {}", dir.display(), error))?;
+        for entry in entries {
+            let path = entry.map_err(|error: io::Error| error.to_string())?.path();
+            if path.file_name().is_some_and(|name| name == "new") {
+                let benchmark = read_json(&path.join("benchmark.json"))?;
+                let estimates = read_json(&path.join("estimates.json"))?;
+                let id = benchmark["full_id"].as_str().ok_or_else(|| format!("{}: no full_id", path.display()))?;
+                let median = estimates["median"]["point_estimate"].as_f64().ok_or_else(|| format!("{}: no median", path.display()))?;
+                medians.insert(id.to_string(), median);
+            } else if path.is_dir() && path.file_name().is_some_and(|name| name != "report") {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(medians)
+}
+
+/// Prints one line per benchmark and returns whether any regressed or went
+/// missing.
+fn compare(baseline: &BTreeMap<String, f64>, current: &BTreeMap<String, f64>, threshold: f64) -> bool {
+    let mut failed = false;
+    for (id, before) in baseline {
+        match current.get(id) {
+            Some(after) => {
+                let change = after / before - 1.0;
+                let regressed = change > threshold;
+                failed |= regressed;
+                println!("{} {}: {:.0} ns -> {:.0} ns ({:+.1}%)", if regressed { "FAIL" } else { "ok  " }, id, before, after, change * 100.0);
+            },
+            None => {
+                failed = true;
+                println!("FAIL {}: not measured", id);
+            },
+        }
+    }
+    for id in current.keys().filter(|id| !baseline.contains_key(*id)) {
+        println!("new  {}: not in the baseline", id);
+    }
+    failed
+}
+
+fn gate(config: &Config) -> Result<bool, String> {
+    if config.run {
+        run_scenarios()?;
+    }
+    let current = read_medians(&config.criterion_dir)?;
+    if current.is_empty() {
+        return Err(format!("no criterion results in {}", config.criterion_dir.display()));
+    }
+
+    if config.update {
+        let json = serde_json::to_string_pretty(&current).map_err(|error| error.to_string())?;
+        fs::write(&config.baseline, json + "\n").map_err(|error| format!("{}: {}", config.baseline.display(), error))?;
+        println!("wrote {} benchmarks to {}", current.len(), config.baseline.display());
+        return Ok(false);
+    }
+
+    let baseline = serde_json::from_value(read_json(&config.baseline)?).map_err(|error| format!("{}: {}", config.baseline.display(), error))?;
+    Ok(compare(&baseline, &current, config.threshold))
+}
+
+/// Exits 0 when every benchmark is within the threshold, 1 on a regression,
+/// 2 on trouble.
+fn main() {
+    let config = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2);
+    });
+
+    match gate(&config) {
+        Ok(failed) => process::exit(if failed { 1 } else { 0 }),
+        Err(message) => {
+            eprintln!("bench-gate: {}", message);
+            process::exit(2);
+        },
+    }
+}
@@ -0,0 +1,67 @@
+use std::env;
+use std::process;
+
+use deep_diff_rs::bench::{format_report, parse_percent, time_diffs, Workload};
+use deep_diff_rs::hackel;
+
+#[cfg(all(feature = "stats", not(feature = "counting-allocator")))]
+#[global_allocator]
+static ALLOCATOR: deep_diff_rs::stats::CountingAllocator = deep_diff_rs::stats::CountingAllocator;
+
+struct Config {
+    workload: Workload,
+    iterations: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { workload: Workload::default(), iterations: 100 }
+    }
+}
+
+const USAGE: &str = "usage: bench [--items N] [--mutations PCT] [--shuffle PCT] [--duplicates PCT] [--length N] [--iterations N] [--seed N]";
+
+fn parse_args() -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        let workload = &mut config.workload;
+        match flag.as_str() {
+            "--items" => workload.items = parse(&flag, &value)?,
+            "--mutations" => workload.mutations = parse_ratio(&flag, &value)?,
+            "--shuffle" => workload.shuffle = parse_ratio(&flag, &value)?,
+            "--duplicates" => workload.duplicates = parse_ratio(&flag, &value)?,
+            "--length" => workload.length = parse(&flag, &value)?,
+            "--iterations" => config.iterations = parse(&flag, &value)?,
+            "--seed" => workload.seed = parse(&flag, &value)?,
+            _ => return Err(format!("unknown flag {}", flag)),
+        }
+    }
+
+    if config.iterations == 0 {
+        return Err("--iterations must be positive".to_string());
+    }
+    Ok(config)
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_ratio(flag: &str, value: &str) -> Result<f64, String> {
+    parse_percent(value).ok_or_else(|| format!("{} must be between 0 and 100", flag))
+}
+
+fn main() {
+    let config = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2);
+    });
+
+    let (old, new) = config.workload.generate();
+
+    let (timings, changes) = time_diffs(config.iterations, || hackel::diff(&old, &new).len());
+    print!("{}", format_report(old.len(), new.len(), changes, &timings));
+}
@@ -0,0 +1,146 @@
+//! Compares changesets recorded by the Swift benchmark with this crate's.
+//!
+//! The Swift file is an array of cases in DeepDiff's `Change` shape, as in
+//! `tests/fixtures/deepdiff_heckel.json`. The Rust changesets either come
+//! from a second file of `{ "name", "changes" }` cases as serialized by the
+//! `serde` feature, or are computed from each case's inputs.
+//!
+//! Both sides are compared in the form `swift::normalize` leaves them in.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process;
+
+use deep_diff_rs::algorithm::{diff_with_algorithm, Algorithm};
+use deep_diff_rs::changeset::CowChange;
+use deep_diff_rs::swift::{normalize, Normalized, SwiftChange};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct SwiftCase {
+    name: String,
+    old: Vec<String>,
+    new: Vec<String>,
+    changes: Vec<SwiftChange<String>>,
+}
+
+#[derive(Deserialize)]
+struct RustCase {
+    name: String,
+    changes: Vec<CowChange<'static, String>>,
+}
+
+/// Changes of `a` missing from `b`, as multisets; both must be sorted.
+fn missing(a: &[Normalized<String>], b: &[Normalized<String>]) -> Vec<Normalized<String>> {
+    let mut b = b.iter().peekable();
+    let mut missing = Vec::new();
+    for change in a {
+        while b.peek().is_some_and(|other| *other < change) {
+            b.next();
+        }
+        if b.peek() == Some(&change) {
+            b.next();
+        } else {
+            missing.push(change.clone());
+        }
+    }
+    missing
+}
+
+struct Config {
+    algorithm: Algorithm,
+    swift: String,
+    rust: Option<String>,
+}
+
+const USAGE: &str = "usage: compare [--algorithm heckel|myers|small|auto] SWIFT.json [RUST.json]";
+
+fn parse_args() -> Result<Config, String> {
+    let mut algorithm = Algorithm::default();
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => {
+                algorithm = match args.next().ok_or("missing value for --algorithm")?.as_str() {
+                    "heckel" => Algorithm::Heckel,
+                    "myers" => Algorithm::Myers,
+                    "small" => Algorithm::Small,
+                    "auto" => Algorithm::Auto,
+                    value => return Err(format!("unknown algorithm {}", value)),
+                };
+            },
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {}", flag)),
+            _ => paths.push(arg),
+        }
+    }
+
+    let mut paths = paths.into_iter();
+    let swift = paths.next().ok_or("expected a Swift changeset file")?;
+    let rust = paths.next();
+    if paths.next().is_some() {
+        return Err("expected at most two paths".to_string());
+    }
+    Ok(Config { algorithm, swift, rust })
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &str) -> Result<T, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    serde_json::from_str(&text).map_err(|error| format!("{}: {}", path, error))
+}
+
+/// Prints every case that differs and returns whether any did.
+fn compare(config: &Config) -> Result<bool, String> {
+    let swift: Vec<SwiftCase> = read_json(&config.swift)?;
+    let mut recorded = match &config.rust {
+        Some(path) => Some(read_json::<Vec<RustCase>>(path)?.into_iter().map(|case| (case.name, case.changes)).collect::<HashMap<_, _>>()),
+        None => None,
+    };
+
+    let total = swift.len();
+    let mut differing = 0;
+    for case in swift {
+        let rust = match recorded.as_mut() {
+            Some(recorded) => recorded.remove(&case.name).ok_or_else(|| format!("no Rust changeset for {}", case.name))?,
+            None => diff_with_algorithm(&case.old, &case.new, config.algorithm).into_iter().map(CowChange::from).collect(),
+        };
+        let swift_changes = normalize(case.changes);
+        let rust_changes = normalize(rust.into_iter().map(SwiftChange::from));
+
+        let only_swift = missing(&swift_changes, &rust_changes);
+        let only_rust = missing(&rust_changes, &swift_changes);
+        if only_swift.is_empty() && only_rust.is_empty() {
+            continue;
+        }
+
+        differing += 1;
+        println!("{}:", case.name);
+        for change in only_swift {
+            println!("  only in Swift: {}", change);
+        }
+        for change in only_rust {
+            println!("  only in Rust:  {}", change);
+        }
+    }
+
+    println!("{} of {} cases differ", differing, total);
+    Ok(differing > 0)
+}
+
+/// Exits 0 when every case matches, 1 when some differ, 2 on trouble.
+fn main() {
+    let config = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2);
+    });
+
+    match compare(&config) {
+        Ok(differ) => process::exit(differ as i32),
+        Err(message) => {
+            eprintln!("compare: {}", message);
+            process::exit(2);
+        },
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use deep_diff_rs::algorithm::{diff_with_algorithm, Algorithm};
+use deep_diff_rs::bench::datasets::Scenario;
+use deep_diff_rs::bench::{format_report, parse_percent, time_diffs, Workload};
+use deep_diff_rs::hackel::Change;
+
+#[cfg(all(feature = "stats", not(feature = "counting-allocator")))]
+#[global_allocator]
+static ALLOCATOR: deep_diff_rs::stats::CountingAllocator = deep_diff_rs::stats::CountingAllocator;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum Format {
+    Text,
+    /// The changeset as serialized by the `serde` feature.
+    Json,
+    /// The changeset in the JSON shape of DeepDiff's Swift `Change`.
+    Swift,
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum Command {
+    Files,
+    /// Pairs files of two trees by relative path and summarizes each pair.
+    Dirs,
+}
+
+struct Config {
+    command: Command,
+    algorithm: Algorithm,
+    format: Format,
+    old: String,
+    new: String,
+}
+
+const USAGE: &str = "usage: deep-diff [dir] [--algorithm heckel|myers|small|auto] [--format text|json|swift] [--old-from PATH] [--new-from PATH] [OLD] [NEW]
+       deep-diff bench [--algorithm A] [--items N] [--mutations PCT] [--shuffle PCT] [--duplicates PCT] [--length N] [--iterations N] [--seed N] [--scenario feed-refresh|pagination|pull-to-refresh] [--csv PATH]
+OLD or NEW may be - to read newline-delimited items from stdin";
+
+fn parse_args() -> Result<Config, String> {
+    let mut algorithm = Algorithm::default();
+    let mut format = Format::Text;
+    let mut old_from = None;
+    let mut new_from = None;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1).peekable();
+    let command = match args.peek().map(String::as_str) {
+        Some("dir") => {
+            args.next();
+            Command::Dirs
+        },
+        _ => Command::Files,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => {
+                let value = args.next().ok_or("missing value for --algorithm")?;
+                algorithm = parse_algorithm(&value)?;
+            },
+            "--format" => {
+                let value = args.next().ok_or("missing value for --format")?;
+                format = parse_format(&value)?;
+            },
+            "--old-from" => old_from = Some(args.next().ok_or("missing value for --old-from")?),
+            "--new-from" => new_from = Some(args.next().ok_or("missing value for --new-from")?),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {}", flag)),
+            _ => paths.push(arg),
+        }
+    }
+
+    // Positional paths fill whichever sides the flags left open, old first.
+    let mut paths = paths.into_iter();
+    let old = old_from.or_else(|| paths.next()).ok_or("expected two paths")?;
+    let new = new_from.or_else(|| paths.next()).ok_or("expected two paths")?;
+    if paths.next().is_some() {
+        return Err("expected two paths".to_string());
+    }
+    if old == "-" && new == "-" {
+        return Err("only one side can read stdin".to_string());
+    }
+    if command == Command::Dirs && (old == "-" || new == "-") {
+        return Err("dir needs two directories".to_string());
+    }
+    if command == Command::Dirs && format == Format::Swift {
+        return Err("dir summaries have no swift format".to_string());
+    }
+    Ok(Config { command, algorithm, format, old, new })
+}
+
+struct BenchConfig {
+    algorithm: Algorithm,
+    workload: Workload,
+    /// Replaces the random mutations of `workload`, keeping its size and seed.
+    scenario: Option<Scenario>,
+    iterations: usize,
+    csv: Option<PathBuf>,
+}
+
+fn parse_bench_args(args: impl Iterator<Item = String>) -> Result<BenchConfig, String> {
+    let mut config = BenchConfig { algorithm: Algorithm::default(), workload: Workload::default(), scenario: None, iterations: 100, csv: None };
+    let mut args = args;
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        let workload = &mut config.workload;
+        match flag.as_str() {
+            "--algorithm" => config.algorithm = parse_algorithm(&value)?,
+            "--items" => workload.items = parse(&flag, &value)?,
+            "--mutations" => workload.mutations = parse_ratio(&flag, &value)?,
+            "--shuffle" => workload.shuffle = parse_ratio(&flag, &value)?,
+            "--duplicates" => workload.duplicates = parse_ratio(&flag, &value)?,
+            "--length" => workload.length = parse(&flag, &value)?,
+            "--iterations" => config.iterations = parse(&flag, &value)?,
+            "--seed" => workload.seed = parse(&flag, &value)?,
+            "--scenario" => config.scenario = Some(Scenario::from_name(&value).ok_or_else(|| format!("unknown scenario {}", value))?),
+            "--csv" => config.csv = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown flag {}", flag)),
+        }
+    }
+
+    if config.iterations == 0 {
+        return Err("--iterations must be positive".to_string());
+    }
+    Ok(config)
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_ratio(flag: &str, value: &str) -> Result<f64, String> {
+    parse_percent(value).ok_or_else(|| format!("{} must be between 0 and 100", flag))
+}
+
+fn parse_algorithm(value: &str) -> Result<Algorithm, String> {
+    match value {
+        "heckel" => Ok(Algorithm::Heckel),
+        "myers" => Ok(Algorithm::Myers),
+        "small" => Ok(Algorithm::Small),
+        "auto" => Ok(Algorithm::Auto),
+        _ => Err(format!("unknown algorithm {}", value)),
+    }
+}
+
+fn parse_format(value: &str) -> Result<Format, String> {
+    match value {
+        "text" => Ok(Format::Text),
+        "json" if cfg!(feature = "serde") => Ok(Format::Json),
+        "swift" if cfg!(feature = "serde") => Ok(Format::Swift),
+        "json" | "swift" => Err(format!("{} output needs the serde feature", value)),
+        _ => Err(format!("unknown format {}", value)),
+    }
+}
+
+/// Reads a whole file, FIFO, or stdin when `path` is `-`.
+fn read(path: &Path) -> Result<String, String> {
+    let bytes = if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+    } else {
+        fs::read(path)
+    };
+    bytes
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Prints one change per line with 1-based line numbers: deletes use old
+/// numbers, inserts and moves new ones.
+fn print(change: &Change<'_, &str>) {
+    match change {
+        Change::Delete(d) => println!("-{}\t{}", d.index + 1, d.item),
+        Change::Insert(i) => println!("+{}\t{}", i.index + 1, i.item),
+        Change::Replace(r) => println!("~{}\t{}", r.index + 1, r.new_item),
+        Change::Move(m) => println!(">{}:{}\t{}", m.from_index + 1, m.to_index + 1, m.item),
+    }
+}
+
+/// Returns whether the files differ.
+fn diff_files(config: &Config) -> Result<bool, String> {
+    let old = read(Path::new(&config.old))?;
+    let new = read(Path::new(&config.new))?;
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+
+    let changes = diff_with_algorithm(&old_lines, &new_lines, config.algorithm);
+    match config.format {
+        Format::Text => changes.iter().for_each(print),
+        #[cfg(feature = "serde")]
+        Format::Json => println!("{}", serde_json::to_string(&changes).expect("changes serialize")),
+        #[cfg(feature = "serde")]
+        Format::Swift => println!("{}", deep_diff_rs::swift::to_swift_json(&changes).expect("changes serialize")),
+        #[cfg(not(feature = "serde"))]
+        Format::Json | Format::Swift => unreachable!("rejected while parsing arguments"),
+    }
+    Ok(!changes.is_empty())
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "lowercase"))]
+enum Status {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct FileSummary {
+    path: String,
+    status: Status,
+    deleted: usize,
+    inserted: usize,
+    moved: usize,
+}
+
+/// Collects the files under `dir` relative to `root`.
+fn walk(root: &Path, dir: &Path, files: &mut BTreeSet<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, files)?;
+        } else {
+            files.insert(path.strip_prefix(root).expect("walked paths are under the root").to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+fn summarize(config: &Config, path: &Path) -> Result<Option<FileSummary>, String> {
+    let old_path = Path::new(&config.old).join(path);
+    let new_path = Path::new(&config.new).join(path);
+    let (status, old, new) = match (old_path.is_file(), new_path.is_file()) {
+        (true, true) => (Status::Modified, read(&old_path)?, read(&new_path)?),
+        (true, false) => (Status::Removed, read(&old_path)?, String::new()),
+        _ => (Status::Added, String::new(), read(&new_path)?),
+    };
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+
+    let mut summary = FileSummary { path: path.display().to_string(), status, deleted: 0, inserted: 0, moved: 0 };
+    for change in diff_with_algorithm(&old_lines, &new_lines, config.algorithm) {
+        match change {
+            Change::Delete(_) => summary.deleted += 1,
+            Change::Insert(_) => summary.inserted += 1,
+            Change::Replace(_) => {
+                summary.deleted += 1;
+                summary.inserted += 1;
+            },
+            Change::Move(_) => summary.moved += 1,
+        }
+    }
+
+    let unchanged = status == Status::Modified && summary.deleted + summary.inserted + summary.moved == 0;
+    Ok(if unchanged { None } else { Some(summary) })
+}
+
+/// Returns whether the trees differ.
+fn diff_dirs(config: &Config) -> Result<bool, String> {
+    let mut paths = BTreeSet::new();
+    for root in [&config.old, &config.new] {
+        let root = Path::new(root);
+        walk(root, root, &mut paths).map_err(|error| format!("{}: {}", root.display(), error))?;
+    }
+
+    let mut summaries = Vec::new();
+    for path in &paths {
+        summaries.extend(summarize(config, path)?);
+    }
+
+    match config.format {
+        Format::Text => {
+            for summary in &summaries {
+                let status = match summary.status {
+                    Status::Added => "added",
+                    Status::Removed => "removed",
+                    Status::Modified => "modified",
+                };
+                println!("{:<8} {} (-{} +{} >{})", status, summary.path, summary.deleted, summary.inserted, summary.moved);
+            }
+        },
+        #[cfg(feature = "serde")]
+        Format::Json => println!("{}", serde_json::to_string(&summaries).expect("summaries serialize")),
+        #[cfg(not(feature = "serde"))]
+        Format::Json => unreachable!("rejected while parsing arguments"),
+        Format::Swift => unreachable!("rejected while parsing arguments"),
+    }
+    Ok(!summaries.is_empty())
+}
+
+/// Times `iterations` diffs of one generated workload, printing a summary
+/// and optionally writing every iteration to a CSV file.
+fn bench(config: &BenchConfig) -> Result<(), String> {
+    let (old, new) = match config.scenario {
+        Some(scenario) => scenario.generate(config.workload.items, config.workload.seed),
+        None => config.workload.generate(),
+    };
+
+    let (timings, changes) = time_diffs(config.iterations, || diff_with_algorithm(&old, &new, config.algorithm).len());
+
+    if let Some(path) = &config.csv {
+        write_csv(path, &timings, changes).map_err(|error| format!("{}: {}", path.display(), error))?;
+    }
+
+    print!("{}", format_report(old.len(), new.len(), changes, &timings));
+    Ok(())
+}
+
+/// Timings in run order, one row per iteration.
+fn write_csv(path: &Path, timings: &[Duration], changes: usize) -> io::Result<()> {
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(out, "iteration,nanoseconds,changes")?;
+    for (iteration, timing) in timings.iter().enumerate() {
+        writeln!(out, "{},{},{}", iteration, timing.as_nanos(), changes)?;
+    }
+    out.flush()
+}
+
+/// Exits like `diff`: 0 when the inputs match, 1 when they differ, 2 on trouble.
+fn main() {
+    if env::args().nth(1).as_deref() == Some("bench") {
+        let result = parse_bench_args(env::args().skip(2)).map_err(|message| format!("{}\n{}", message, USAGE)).and_then(|config| bench(&config));
+        if let Err(message) = result {
+            eprintln!("deep-diff: {}", message);
+            process::exit(2);
+        }
+        return;
+    }
+
+    let config = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2);
+    });
+
+    let result = match config.command {
+        Command::Files => diff_files(&config),
+        Command::Dirs => diff_dirs(&config),
+    };
+    match result {
+        Ok(differ) => process::exit(differ as i32),
+        Err(message) => {
+            eprintln!("deep-diff: {}", message);
+            process::exit(2);
+        },
+    }
+}
@@ -0,0 +1,81 @@
+//! Renders Swift and Rust benchmark timings as one table per dataset with
+//! speedups over a reference implementation.
+//!
+//! `--swift` takes the Swift harness's console output. `--rust` takes what
+//! `bench::export` wrote: JSON when the path ends in `.json`, Markdown
+//! otherwise. Either may be repeated or left out.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+
+use deep_diff_rs::bench::export::Measurement;
+use deep_diff_rs::bench::report::{parse_markdown, write_html, write_markdown};
+
+const USAGE: &str = "usage: report [--swift PATH]... [--rust PATH]... [--reference NAME] [--html]";
+
+struct Config {
+    swift: Vec<String>,
+    rust: Vec<String>,
+    reference: String,
+    html: bool,
+}
+
+fn parse_args() -> Result<Config, String> {
+    let mut config = Config { swift: Vec::new(), rust: Vec::new(), reference: "DeepDiff".to_string(), html: false };
+    let mut args = env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        if flag == "--html" {
+            config.html = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--swift" => config.swift.push(value),
+            "--rust" => config.rust.push(value),
+            "--reference" => config.reference = value,
+            _ => return Err(format!("unknown flag {}", flag)),
+        }
+    }
+
+    if config.swift.is_empty() && config.rust.is_empty() {
+        return Err("nothing to report".to_string());
+    }
+    Ok(config)
+}
+
+fn read(path: &str) -> Result<Vec<Measurement>, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    let measurements = if Path::new(path).extension().is_some_and(|extension| extension == "json") {
+        serde_json::from_str(&text).map_err(|error| error.to_string())
+    } else {
+        parse_markdown(&text)
+    };
+    measurements.map_err(|message| format!("{}: {}", path, message))
+}
+
+fn report(config: &Config) -> Result<(), String> {
+    let swift = config.swift.iter().map(|path| read(path)).collect::<Result<Vec<_>, _>>()?.concat();
+    let rust = config.rust.iter().map(|path| read(path)).collect::<Result<Vec<_>, _>>()?.concat();
+    let sources: [(&str, &[Measurement]); 2] = [("Swift", &swift), ("Rust", &rust)];
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let written = if config.html { write_html(&mut out, &sources, &config.reference) } else { write_markdown(&mut out, &sources, &config.reference) };
+    written.and_then(|_| out.flush()).map_err(|error| error.to_string())
+}
+
+fn main() {
+    let config = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2);
+    });
+
+    if let Err(message) = report(&config) {
+        eprintln!("report: {}", message);
+        process::exit(2);
+    }
+}
@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+/// Positions where two flag arrays differ.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct BitDiff {
+    /// Maximal runs of changed positions, ascending.
+    pub runs: Vec<Range<usize>>,
+    /// Number of changed positions.
+    pub flipped: usize,
+}
+
+/// Packs flags into words, bit `i % 64` of word `i / 64` holding flag `i`.
+pub fn pack(flags: &[bool]) -> Vec<u64> {
+    flags
+        .chunks(64)
+        .map(|chunk| chunk.iter().enumerate().fold(0, |word, (bit, flag)| word | (*flag as u64) << bit))
+        .collect()
+}
+
+/// Diffs two packed bit arrays position by position, a word at a time: XOR
+/// finds the changed bits, popcount counts them and trailing zero counts
+/// skip to the edges of each run. Bits past the end of the shorter array
+/// count as unset, so growing an occupancy map reports only the set bits.
+pub fn diff_words(old: &[u64], new: &[u64]) -> BitDiff {
+    let mut diff = BitDiff::default();
+    let mut open = None;
+
+    for idx in 0..old.len().max(new.len()) {
+        let changed = old.get(idx).copied().unwrap_or(0) ^ new.get(idx).copied().unwrap_or(0);
+        diff.flipped += changed.count_ones() as usize;
+
+        let base = idx * 64;
+        let mut bit = 0;
+        while bit < 64 {
+            // Looks for the next changed bit outside a run, the next unchanged one inside.
+            let rest = if open.is_none() { changed >> bit } else { !changed >> bit };
+            if rest == 0 {
+                break;
+            }
+            bit += rest.trailing_zeros();
+            match open.take() {
+                None => open = Some(base + bit as usize),
+                Some(start) => diff.runs.push(start..base + bit as usize),
+            }
+        }
+    }
+
+    if let Some(start) = open {
+        diff.runs.push(start..old.len().max(new.len()) * 64);
+    }
+    diff
+}
+
+/// Same as `diff_words` for unpacked flags, e.g. `&[bool]` occupancy maps.
+pub fn diff_bools(old: &[bool], new: &[bool]) -> BitDiff {
+    diff_words(&pack(old), &pack(new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Rng;
+
+    fn naive(old: &[bool], new: &[bool]) -> BitDiff {
+        let mut diff = BitDiff::default();
+        for idx in 0..old.len().max(new.len()) {
+            if old.get(idx).copied().unwrap_or(false) == new.get(idx).copied().unwrap_or(false) {
+                continue;
+            }
+            diff.flipped += 1;
+            match diff.runs.last_mut() {
+                Some(run) if run.end == idx => run.end += 1,
+                _ => diff.runs.push(idx..idx + 1),
+            }
+        }
+        diff
+    }
+
+    fn flags(len: usize, seed: u64) -> Vec<bool> {
+        let mut rng = Rng(seed);
+        (0..len).map(|_| rng.chance(0.2)).collect()
+    }
+
+    #[test]
+    fn matches_naive() {
+        for (old_len, new_len) in [(0, 0), (1, 0), (63, 64), (64, 64), (200, 130), (1000, 1000)] {
+            for seed in 1..5 {
+                let old = flags(old_len, seed);
+                let new = flags(new_len, seed * 31);
+                assert_eq!(diff_bools(&old, &new), naive(&old, &new), "{} -> {}", old_len, new_len);
+            }
+        }
+    }
+
+    #[test]
+    fn runs_across_words() {
+        let old = vec![false; 200];
+        let mut new = old.clone();
+        new[60..140].iter_mut().for_each(|flag| *flag = true);
+        new[199] = true;
+
+        let diff = diff_bools(&old, &new);
+        assert_eq!(diff.runs, vec![60..140, 199..200]);
+        assert_eq!(diff.flipped, 81);
+    }
+
+    #[test]
+    fn identical() {
+        let old = flags(500, 9);
+        assert_eq!(diff_bools(&old, &old), BitDiff::default());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use crate::hackel::{self, Change, Symbols};
+
+/// Memoizes diffs keyed by `(hash(old), hash(new))`.
+///
+/// The matching computed by the symbol table passes is cached, so a hit
+/// skips all hashing into the table and only re-emits the changes. Keys are
+/// 64-bit content hashes; before a hit is used, the input lengths and a
+/// second, differently seeded hash of each input must match too.
+pub struct DiffCache {
+    entries: HashMap<(u64, u64), Entry>,
+    order: VecDeque<(u64, u64)>,
+    capacity: usize,
+    hits: usize,
+    misses: usize,
+}
+
+struct Entry {
+    /// `(hash(old), hash(new))` under `CHECK_SEED`.
+    check: (u64, u64),
+    symbols: Symbols<usize>,
+}
+
+const KEY_SEED: u64 = 0;
+const CHECK_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+impl DiffCache {
+    /// Creates a cache holding at most `capacity` pairs, evicting the oldest first.
+    pub fn new(capacity: usize) -> Self {
+        DiffCache {
+            entries: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn diff<'a, T: Eq + Hash>(&mut self, old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+        let key = (content_hash(old, KEY_SEED), content_hash(new, KEY_SEED));
+        let check = (content_hash(old, CHECK_SEED), content_hash(new, CHECK_SEED));
+        let mut changes = Vec::new();
+
+        match self.entries.get(&key) {
+            Some(entry) if entry.check == check && entry.symbols.old_match.len() == old.len() && entry.symbols.new_match.len() == new.len() => {
+                self.hits += 1;
+                hackel::walk(&entry.symbols, |step| hackel::emit(old, new, step, &mut |change| changes.push(change)));
+            },
+            _ => {
+                self.misses += 1;
+                let symbols = hackel::build(old, new);
+                hackel::walk(&symbols, |step| hackel::emit(old, new, step, &mut |change| changes.push(change)));
+                self.insert(key, Entry { check, symbols });
+            },
+        }
+
+        changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn insert(&mut self, key: (u64, u64), entry: Entry) {
+        if self.capacity == 0 {
+            return;
+        }
+        // Replacing an entry, as after a collision on `key`, frees no room.
+        if let Some(slot) = self.entries.get_mut(&key) {
+            *slot = entry;
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, entry);
+        self.order.push_back(key);
+    }
+}
+
+fn content_hash<T: Hash>(items: &[T], seed: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(seed);
+    items.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_pair_hits() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "b", "a"];
+        let mut cache = DiffCache::new(4);
+
+        let first = cache.diff(&old, &new);
+        let second = cache.diff(&old, &new);
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 2);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+    }
+
+    #[test]
+    fn evicts_oldest() {
+        let pairs = [(vec![1], vec![2]), (vec![3], vec![4]), (vec![5], vec![6])];
+        let mut cache = DiffCache::new(2);
+        for (old, new) in &pairs {
+            cache.diff(old, new);
+        }
+        assert_eq!(cache.len(), 2);
+
+        cache.diff(&pairs[0].0, &pairs[0].1);
+        assert_eq!(cache.hits(), 0);
+        cache.diff(&pairs[2].0, &pairs[2].1);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn colliding_key_misses() {
+        let (old, new) = (vec!["a", "b"], vec!["b", "a"]);
+        let (other_old, other_new) = (vec!["x", "y"], vec!["y", "z"]);
+        let mut cache = DiffCache::new(4);
+        cache.diff(&old, &new);
+
+        // Plant the first pair's entry under the second pair's key, as a
+        // collision of the 64-bit key hashes would.
+        let key = (content_hash(&old, KEY_SEED), content_hash(&new, KEY_SEED));
+        let entry = cache.entries.remove(&key).unwrap();
+        cache.entries.insert((content_hash(&other_old, KEY_SEED), content_hash(&other_new, KEY_SEED)), entry);
+
+        let changes = cache.diff(&other_old, &other_new);
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(changes.len(), hackel::diff(&other_old, &other_new).len());
+    }
+
+    #[test]
+    fn replacing_keeps_others() {
+        let pairs = [(vec![1], vec![2]), (vec![3], vec![4])];
+        let mut cache = DiffCache::new(2);
+        for (old, new) in &pairs {
+            cache.diff(old, new);
+        }
+
+        // A failed check on the newest pair re-diffs it in place.
+        let key = (content_hash(&pairs[1].0, KEY_SEED), content_hash(&pairs[1].1, KEY_SEED));
+        cache.entries.get_mut(&key).unwrap().check = (0, 0);
+        cache.diff(&pairs[1].0, &pairs[1].1);
+        assert_eq!(cache.len(), 2);
+        cache.diff(&pairs[0].0, &pairs[0].1);
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+    }
+}
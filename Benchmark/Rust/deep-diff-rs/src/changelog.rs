@@ -0,0 +1,67 @@
+use crate::hackel::Change;
+
+/// Pushes the pending run of deletes or inserts, if any.
+fn flush<T, F: FnMut(&T) -> String>(lines: &mut Vec<String>, run: &mut Option<(&str, usize, Vec<&T>)>, item: &mut F) {
+    if let Some((verb, start, items)) = run.take() {
+        lines.push(match items.as_slice() {
+            [single] => format!("{} '{}' at {}", verb, item(single), start),
+            _ => format!("{} {} items at {}..{}", verb, items.len(), start, start + items.len()),
+        });
+    }
+}
+
+/// Renders `changes` as one line per change for logs, e.g. `moved
+/// 'Settings' from 4 to 1`. Runs of deletes or inserts at consecutive
+/// indices collapse into one line such as `removed 3 items at 10..13`.
+/// `item` formats the quoted items.
+pub fn render<T, F: FnMut(&T) -> String>(changes: &[Change<'_, T>], mut item: F) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut run: Option<(&str, usize, Vec<&T>)> = None;
+    for change in changes {
+        let (verb, index, run_item) = match change {
+            Change::Delete(delete) => ("removed", delete.index, delete.item),
+            Change::Insert(insert) => ("inserted", insert.index, insert.item),
+            Change::Replace(replace) => {
+                flush(&mut lines, &mut run, &mut item);
+                lines.push(format!("replaced '{}' with '{}' at {}", item(replace.old_item), item(replace.new_item), replace.index));
+                continue;
+            },
+            Change::Move(mv) => {
+                flush(&mut lines, &mut run, &mut item);
+                lines.push(format!("moved '{}' from {} to {}", item(mv.item), mv.from_index, mv.to_index));
+                continue;
+            },
+        };
+        match &mut run {
+            Some((run_verb, start, items)) if *run_verb == verb && *start + items.len() == index => items.push(run_item),
+            _ => {
+                flush(&mut lines, &mut run, &mut item);
+                run = Some((verb, index, vec![run_item]));
+            },
+        }
+    }
+    flush(&mut lines, &mut run, &mut item);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn runs_and_moves() {
+        let old = vec!["Home", "a", "b", "c", "Settings"];
+        let new = vec!["Settings", "Home", "x"];
+        let lines = render(&hackel::diff(&old, &new), |item| item.to_string());
+        assert_eq!(lines, vec!["removed 3 items at 1..4", "moved 'Settings' from 4 to 0", "moved 'Home' from 0 to 1", "inserted 'x' at 2"]);
+    }
+
+    #[test]
+    fn replace_uses_formatter() {
+        let old = vec![(1, "draft")];
+        let new = vec![(1, "final")];
+        let lines = render(&hackel::diff_by_key(&old, &new, |row| row.0), |row| row.1.to_uppercase());
+        assert_eq!(lines, vec!["replaced 'DRAFT' with 'FINAL' at 0"]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::borrow::Cow;
+use std::hash::Hash;
+use std::ops::{Deref, Range};
+
+use crate::hackel::{self, Change, ChangeKind, Delete, Index, Insert, Move, Replace};
+#[cfg(feature = "rayon")]
+use crate::parallel::PARALLEL_THRESHOLD;
+
+/// Changes turning one sequence into another, in the order `diff` emits them.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Changeset<'a, T, I = usize>(Vec<Change<'a, T, I>>);
+
+impl<'a, T, I> Changeset<'a, T, I> {
+    /// `true` when applying the changeset leaves the sequence as it was.
+    pub fn is_identity(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_vec(self) -> Vec<Change<'a, T, I>> {
+        self.0
+    }
+
+    /// Maps every change independently, e.g. to render or group it. With the
+    /// `rayon` feature, large changesets are spread over the rayon pool; the
+    /// result order is the same either way.
+    pub fn map<R, F>(&self, f: F) -> Vec<R>
+    where
+        T: Sync,
+        I: Sync,
+        R: Send,
+        F: Fn(&Change<'a, T, I>) -> R + Sync + Send,
+    {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            if self.0.len() >= PARALLEL_THRESHOLD {
+                return self.0.par_iter().map(f).collect();
+            }
+        }
+        self.0.iter().map(f).collect()
+    }
+}
+
+/// Filtered views, in changeset order. Indices are those the changes carry:
+/// old for deletes and move sources, new for the rest, so a range of screen
+/// rows matches changes leaving or reaching those rows.
+impl<'a, T, I: Index> Changeset<'a, T, I> {
+    pub fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &Change<'a, T, I>> {
+        self.0.iter().filter(move |change| change.kind() == kind)
+    }
+
+    /// Changes with an index in `range`; a move matches on either end.
+    pub fn in_range(&self, range: Range<usize>) -> impl Iterator<Item = &Change<'a, T, I>> {
+        self.0.iter().filter(move |change| {
+            let (first, second) = match change {
+                Change::Insert(insert) => (insert.index, None),
+                Change::Delete(delete) => (delete.index, None),
+                Change::Replace(replace) => (replace.index, None),
+                Change::Move(mv) => (mv.from_index, Some(mv.to_index)),
+            };
+            range.contains(&first.to_usize()) || second.is_some_and(|index| range.contains(&index.to_usize()))
+        })
+    }
+
+    pub fn affecting_index(&self, index: usize) -> impl Iterator<Item = &Change<'a, T, I>> {
+        self.in_range(index..index + 1)
+    }
+}
+
+impl<'a, T, I> Deref for Changeset<'a, T, I> {
+    type Target = [Change<'a, T, I>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, T, I> From<Vec<Change<'a, T, I>>> for Changeset<'a, T, I> {
+    fn from(changes: Vec<Change<'a, T, I>>) -> Self {
+        Changeset(changes)
+    }
+}
+
+impl<'a, T, I> IntoIterator for Changeset<'a, T, I> {
+    type Item = Change<'a, T, I>;
+    type IntoIter = std::vec::IntoIter<Change<'a, T, I>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T: Clone, I> Changeset<'a, T, I> {
+    /// Clones the referenced items so the changeset outlives its inputs.
+    pub fn into_owned(self) -> Vec<CowChange<'static, T, I>> {
+        self.0.into_iter().map(|change| CowChange::from(change).into_owned()).collect()
+    }
+}
+
+/// A `Change` whose items are either borrowed from the inputs or owned, so
+/// it can be detached (e.g. sent to another thread) only when needed.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CowChange<'a, T: Clone, I = usize> {
+    Insert { item: Cow<'a, T>, index: I },
+    Delete { item: Cow<'a, T>, index: I },
+    Replace { old_item: Cow<'a, T>, new_item: Cow<'a, T>, index: I },
+    Move { item: Cow<'a, T>, from_index: I, to_index: I },
+}
+
+impl<'a, T: Clone, I> CowChange<'a, T, I> {
+    pub fn into_owned(self) -> CowChange<'static, T, I> {
+        let owned = |item: Cow<'a, T>| Cow::Owned(item.into_owned());
+        match self {
+            CowChange::Insert { item, index } => CowChange::Insert { item: owned(item), index },
+            CowChange::Delete { item, index } => CowChange::Delete { item: owned(item), index },
+            CowChange::Replace { old_item, new_item, index } => CowChange::Replace { old_item: owned(old_item), new_item: owned(new_item), index },
+            CowChange::Move { item, from_index, to_index } => CowChange::Move { item: owned(item), from_index, to_index },
+        }
+    }
+
+    /// Borrows the change as a `Change`, e.g. to apply a deserialized one.
+    pub fn as_change(&self) -> Change<'_, T, I>
+    where
+        I: Copy,
+    {
+        match self {
+            CowChange::Insert { item, index } => Change::Insert(Insert { item, index: *index }),
+            CowChange::Delete { item, index } => Change::Delete(Delete { item, index: *index }),
+            CowChange::Replace { old_item, new_item, index } => Change::Replace(Replace { old_item, new_item, index: *index }),
+            CowChange::Move { item, from_index, to_index } => Change::Move(Move { item, from_index: *from_index, to_index: *to_index }),
+        }
+    }
+}
+
+impl<'a, T: Clone, I> From<Change<'a, T, I>> for CowChange<'a, T, I> {
+    fn from(change: Change<'a, T, I>) -> Self {
+        match change {
+            Change::Insert(i) => CowChange::Insert { item: Cow::Borrowed(i.item), index: i.index },
+            Change::Delete(d) => CowChange::Delete { item: Cow::Borrowed(d.item), index: d.index },
+            Change::Replace(r) => CowChange::Replace { old_item: Cow::Borrowed(r.old_item), new_item: Cow::Borrowed(r.new_item), index: r.index },
+            Change::Move(m) => CowChange::Move { item: Cow::Borrowed(m.item), from_index: m.from_index, to_index: m.to_index },
+        }
+    }
+}
+
+pub enum DiffResult<'a, T> {
+    /// Old and new are equal element by element; there is nothing to update.
+    Unchanged,
+    Changed(Changeset<'a, T>),
+}
+
+impl<'a, T> DiffResult<'a, T> {
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, DiffResult::Unchanged)
+    }
+
+    pub fn changeset(&self) -> Option<&Changeset<'a, T>> {
+        match self {
+            DiffResult::Unchanged => None,
+            DiffResult::Changed(changeset) => Some(changeset),
+        }
+    }
+
+    /// The changeset, empty when the inputs were unchanged.
+    pub fn into_changeset(self) -> Changeset<'a, T> {
+        match self {
+            DiffResult::Unchanged => Changeset(Vec::new()),
+            DiffResult::Changed(changeset) => changeset,
+        }
+    }
+}
+
+/// Same as `diff`, first comparing the inputs element by element so equal
+/// sequences return `Unchanged` without building the symbol table.
+pub fn diff_result<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> DiffResult<'a, T> {
+    if old == new {
+        return DiffResult::Unchanged;
+    }
+    DiffResult::Changed(Changeset(hackel::diff(old, new)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged() {
+        let old = (0..1000).collect::<Vec<_>>();
+        let new = old.clone();
+        let result = diff_result(&old, &new);
+        assert!(result.is_unchanged());
+        assert!(result.changeset().is_none());
+        assert!(result.into_changeset().is_identity());
+    }
+
+    #[test]
+    fn changed() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["a", "c"];
+        let result = diff_result(&old, &new);
+        assert!(!result.is_unchanged());
+
+        let changeset = result.into_changeset();
+        assert!(!changeset.is_identity());
+        assert_eq!(changeset.len(), 1);
+        assert!(matches!(changeset[0], Change::Delete(_)));
+    }
+
+    #[test]
+    fn borrowed_then_owned() {
+        let old = vec!["a".to_string(), "b".to_string()];
+        let new = vec!["b".to_string(), "c".to_string()];
+        let borrowed = hackel::diff(&old, &new).into_iter().map(CowChange::from).collect::<Vec<_>>();
+        assert!(borrowed.iter().all(|change| matches!(change, CowChange::Delete { item: Cow::Borrowed(_), .. } | CowChange::Insert { item: Cow::Borrowed(_), .. })));
+
+        let changeset = diff_result(&old, &new).into_changeset();
+        let owned = changeset.into_owned();
+        let detached = std::thread::spawn(move || owned).join().unwrap();
+        assert_eq!(detached, vec![
+            CowChange::Delete { item: Cow::Owned("a".to_string()), index: 0 },
+            CowChange::Insert { item: Cow::Owned("c".to_string()), index: 1 },
+        ]);
+        assert_eq!(borrowed, detached);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip() {
+        let old = vec!["a".to_string(), "b".to_string()];
+        let new = vec!["b".to_string(), "a".to_string(), "c".to_string()];
+        let changeset = diff_result(&old, &new).into_changeset();
+
+        let json = serde_json::to_string(&changeset).unwrap();
+        let parsed: Vec<CowChange<'_, String>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, changeset.into_owned());
+    }
+
+    #[test]
+    fn map_keeps_order() {
+        let old = (0..40_000).collect::<Vec<u32>>();
+        let new = old.iter().map(|i| i + 20_000).collect::<Vec<_>>();
+        let changeset = diff_result(&old, &new).into_changeset();
+
+        let rendered = changeset.map(|change| match change {
+            Change::Delete(d) => format!("-{}", d.index),
+            Change::Insert(i) => format!("+{}", i.index),
+            _ => String::new(),
+        });
+        assert_eq!(rendered.len(), changeset.len());
+        assert_eq!(rendered[0], "-0");
+        assert_eq!(rendered[rendered.len() - 1], format!("+{}", new.len() - 1));
+    }
+
+    #[test]
+    fn queries() {
+        let old = vec!["a", "b", "c", "d", "e"];
+        let new = vec!["e", "a", "x", "c", "d"];
+        let changeset = diff_result(&old, &new).into_changeset();
+
+        let kinds = |changes: Vec<&Change<'_, &str>>| changes.iter().map(|change| change.kind()).collect::<Vec<_>>();
+        assert_eq!(changeset.of_kind(ChangeKind::Move).count(), 4);
+        assert_eq!(changeset.of_kind(ChangeKind::Replace).count(), 0);
+        // Deletes `b` at old 1 and moves `a` to new 1.
+        assert_eq!(kinds(changeset.affecting_index(1).collect()), vec![ChangeKind::Delete, ChangeKind::Move]);
+        assert_eq!(kinds(changeset.in_range(0..3).collect()), vec![ChangeKind::Delete, ChangeKind::Move, ChangeKind::Move, ChangeKind::Insert, ChangeKind::Move]);
+        assert_eq!(changeset.in_range(5..9).count(), 0);
+    }
+
+    #[test]
+    fn reordered_is_changed() {
+        let old = vec![1, 2];
+        let new = vec![2, 1];
+        assert!(!diff_result(&old, &new).is_unchanged());
+    }
+}
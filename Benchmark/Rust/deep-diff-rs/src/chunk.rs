@@ -0,0 +1,202 @@
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use std::ops::Range;
+
+use crate::bench::mix;
+use crate::hackel::{self, Index};
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        table[i] = mix(state);
+        i += 1;
+    }
+    table
+}
+
+static GEAR: [u64; 256] = gear_table();
+
+/// Gear-hash content-defined chunker.
+///
+/// Boundaries depend only on nearby bytes, so an edit only changes the chunks
+/// around it and the rest of the blob keeps its chunking.
+#[derive(Clone, Copy, Debug)]
+pub struct Chunker {
+    pub min_size: usize,
+    pub max_size: usize,
+    /// A boundary is cut where the low `mask_bits` bits of the rolling hash
+    /// are zero. Values above 63 count as 63.
+    pub mask_bits: u32,
+}
+
+impl Default for Chunker {
+    fn default() -> Self {
+        Chunker { min_size: 2 * 1024, max_size: 64 * 1024, mask_bits: 13 }
+    }
+}
+
+impl Chunker {
+    pub fn chunks(&self, data: &[u8]) -> Vec<Range<usize>> {
+        let mask = u64::MAX.checked_shr(64 - self.mask_bits.min(63)).unwrap_or(0);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut hash = 0u64;
+
+        for (idx, byte) in data.iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+            let len = idx + 1 - start;
+            if (len >= self.min_size && hash & mask == 0) || len >= self.max_size {
+                chunks.push(start..idx + 1);
+                start = idx + 1;
+                hash = 0;
+            }
+        }
+        if start < data.len() {
+            chunks.push(start..data.len());
+        }
+        chunks
+    }
+}
+
+/// Instruction for rebuilding the new blob.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum DeltaOp {
+    /// Copy this byte range of the old blob.
+    Copy(Range<usize>),
+    /// Transfer this byte range of the new blob.
+    Literal(Range<usize>),
+}
+
+/// Chunk bytes keyed by a precomputed hash, so the table hashes each chunk once.
+struct ChunkKey<'a> {
+    hash: u64,
+    bytes: &'a [u8],
+}
+
+impl<'a> ChunkKey<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        ChunkKey { hash: hasher.finish(), bytes }
+    }
+}
+
+impl<'a> PartialEq for ChunkKey<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.bytes == other.bytes
+    }
+}
+
+impl<'a> Eq for ChunkKey<'a> {}
+
+impl<'a> Hash for ChunkKey<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// Computes an rsync-style delta: chunks of `new` that the sequence differ
+/// matches in `old` are copied, the rest are sent as literals.
+pub fn delta(old: &[u8], new: &[u8], chunker: &Chunker) -> Vec<DeltaOp> {
+    let old_chunks = chunker.chunks(old);
+    let new_chunks = chunker.chunks(new);
+    let symbols = hackel::build::<_, usize>(
+        old_chunks.iter().map(|r| ChunkKey::new(&old[r.clone()])),
+        new_chunks.iter().map(|r| ChunkKey::new(&new[r.clone()])),
+    );
+
+    let mut ops: Vec<DeltaOp> = Vec::new();
+    for (new_idx, old_idx) in symbols.new_match.iter().enumerate() {
+        let op = if *old_idx == usize::NONE {
+            DeltaOp::Literal(new_chunks[new_idx].clone())
+        } else {
+            DeltaOp::Copy(old_chunks[*old_idx].clone())
+        };
+
+        match (ops.last_mut(), op) {
+            (Some(DeltaOp::Copy(last)), DeltaOp::Copy(next)) if last.end == next.start => last.end = next.end,
+            (Some(DeltaOp::Literal(last)), DeltaOp::Literal(next)) if last.end == next.start => last.end = next.end,
+            (_, op) => ops.push(op),
+        }
+    }
+    ops
+}
+
+/// Rebuilds the new blob from the old one and the literal bytes of the new one.
+pub fn apply_delta(old: &[u8], new: &[u8], ops: &[DeltaOp]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for op in ops {
+        match op {
+            DeltaOp::Copy(range) => out.extend_from_slice(&old[range.clone()]),
+            DeltaOp::Literal(range) => out.extend_from_slice(&new[range.clone()]),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Rng;
+
+    fn blob(len: usize, seed: u64) -> Vec<u8> {
+        let mut rng = Rng(seed);
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    fn literal_bytes(ops: &[DeltaOp]) -> usize {
+        ops.iter().map(|op| match op {
+            DeltaOp::Literal(range) => range.len(),
+            DeltaOp::Copy(_) => 0,
+        }).sum()
+    }
+
+    #[test]
+    fn chunk_bounds() {
+        let chunker = Chunker::default();
+        let data = blob(300_000, 1);
+        let chunks = chunker.chunks(&data);
+
+        assert_eq!(chunks.first().unwrap().start, 0);
+        assert_eq!(chunks.last().unwrap().end, data.len());
+        for window in chunks.windows(2) {
+            assert_eq!(window[0].end, window[1].start);
+        }
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.len() >= chunker.min_size && chunk.len() <= chunker.max_size);
+        }
+    }
+
+    #[test]
+    fn small_edit_sends_little() {
+        let chunker = Chunker::default();
+        let old = blob(500_000, 7);
+        let mut new = old.clone();
+        new.splice(250_000..250_010, b"inserted bytes".iter().copied());
+
+        let ops = delta(&old, &new, &chunker);
+        assert_eq!(apply_delta(&old, &new, &ops), new);
+        assert!(literal_bytes(&ops) < 3 * chunker.max_size);
+    }
+
+    #[test]
+    fn unrelated_blobs() {
+        let chunker = Chunker::default();
+        let old = blob(50_000, 3);
+        let new = blob(40_000, 4);
+        let ops = delta(&old, &new, &chunker);
+
+        assert_eq!(ops, vec![DeltaOp::Literal(0..new.len())]);
+    }
+
+    #[test]
+    fn wide_mask() {
+        let chunker = Chunker { min_size: 16, max_size: 1024, mask_bits: 64 };
+        let chunks = chunker.chunks(&blob(4096, 3));
+        assert!(chunks.len() <= 8, "{:?}", chunks);
+        assert_eq!(chunks.last().unwrap().end, 4096);
+    }
+}
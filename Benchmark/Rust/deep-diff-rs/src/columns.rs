@@ -0,0 +1,85 @@
+use std::hash::Hash;
+
+use crate::hackel::{self, IndexChange, Step};
+
+/// One array stored in parallel with the keys, such as titles or thumbnails
+/// kept apart from ids. Implemented for `(old, new)` slice pairs.
+pub trait Column {
+    /// Lengths of the old and new arrays.
+    fn lens(&self) -> (usize, usize);
+
+    /// Whether the row at `old_idx` changed on its way to `new_idx`.
+    fn changed(&self, old_idx: usize, new_idx: usize) -> bool;
+}
+
+impl<'a, T: PartialEq> Column for (&'a [T], &'a [T]) {
+    fn lens(&self) -> (usize, usize) {
+        (self.0.len(), self.1.len())
+    }
+
+    fn changed(&self, old_idx: usize, new_idx: usize) -> bool {
+        self.0[old_idx] != self.1[new_idx]
+    }
+}
+
+/// Diffs rows spread over parallel arrays as one changeset: rows are matched
+/// by the key arrays, and a matched row whose value changed in any of
+/// `columns` is reported as a `Replace` at its new index, followed by a
+/// `Move` if it also moved, as `diff_by_key` does.
+///
+/// # Panics
+///
+/// Panics if a column's lengths differ from the keys'.
+pub fn diff_columns<K: Eq + Hash>(old_keys: &[K], new_keys: &[K], columns: &[&dyn Column]) -> Vec<IndexChange> {
+    for column in columns {
+        assert_eq!(column.lens(), (old_keys.len(), new_keys.len()), "column length differs from the keys");
+    }
+
+    let symbols = hackel::build::<_, usize>(old_keys, new_keys);
+    let mut changes = Vec::new();
+    hackel::walk(&symbols, |step| match step {
+        Step::Delete(index) => changes.push(IndexChange::Delete { index }),
+        Step::Insert(index) => changes.push(IndexChange::Insert { index }),
+        Step::Match { old_idx, new_idx, moved } => {
+            if columns.iter().any(|column| column.changed(old_idx, new_idx)) {
+                changes.push(IndexChange::Replace { index: new_idx });
+            }
+            if moved {
+                changes.push(IndexChange::Move { from_index: old_idx, to_index: new_idx });
+            }
+        },
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_changeset_for_all_columns() {
+        let old_ids = [1, 2, 3];
+        let old_titles = ["a", "b", "c"];
+        let old_thumbs = [10u8, 20, 30];
+        let new_ids = [3, 1, 4];
+        let new_titles = ["c", "A", "d"];
+        let new_thumbs = [31u8, 10, 40];
+
+        let changes = diff_columns(&old_ids, &new_ids, &[&(&old_titles[..], &new_titles[..]), &(&old_thumbs[..], &new_thumbs[..])]);
+        assert_eq!(changes, vec![
+            IndexChange::Delete { index: 1 },
+            IndexChange::Replace { index: 0 },
+            IndexChange::Move { from_index: 2, to_index: 0 },
+            IndexChange::Replace { index: 1 },
+            IndexChange::Move { from_index: 0, to_index: 1 },
+            IndexChange::Insert { index: 2 },
+        ]);
+        assert_eq!(diff_columns(&old_ids, &old_ids, &[&(&old_titles[..], &old_titles[..])]), vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "column length")]
+    fn mismatched_column() {
+        diff_columns(&[1, 2], &[1, 2], &[&(&["a"][..], &["a", "b"][..])]);
+    }
+}
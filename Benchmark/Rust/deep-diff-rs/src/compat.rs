@@ -0,0 +1,170 @@
+//! This crate's backends behind `similar`'s `DiffOp` and a `TextDiff`-like
+//! type, so code written against `similar` can switch backends in place.
+//!
+//! `DiffOp` has no moves: a moved item comes out as a delete plus an insert.
+//! Enabled by the `similar` feature.
+
+use std::hash::Hash;
+
+use similar::DiffOp;
+
+use crate::algorithm::{self, Algorithm};
+use crate::hackel::Index;
+use crate::merge;
+
+/// Same as `similar::capture_diff_slices`, running `algorithm` from this crate.
+pub fn capture_diff_slices<T: Eq + Hash>(algorithm: Algorithm, old: &[T], new: &[T]) -> Vec<DiffOp> {
+    let (_, new_match) = algorithm::matches(old, new, algorithm);
+    ops_from_matches(&new_match, old.len())
+}
+
+/// Keeps the longest run of matches that preserve order (the other, moved
+/// items count as unmatched) and turns the alignment into ops, pairing
+/// adjacent deletes and inserts into replaces as `similar` does.
+fn ops_from_matches(new_match: &[usize], old_len: usize) -> Vec<DiffOp> {
+    let placed = new_match
+        .iter()
+        .enumerate()
+        .filter(|(_, old_idx)| **old_idx != usize::NONE)
+        .map(|(new_idx, old_idx)| (*old_idx, new_idx))
+        .collect::<Vec<_>>();
+    let mut pairs = merge::longest_increasing(&placed).into_iter().rev().map(|new_idx| (new_match[new_idx], new_idx)).collect::<Vec<_>>();
+    pairs.push((old_len, new_match.len()));
+
+    let mut ops = Vec::new();
+    let (mut old_idx, mut new_idx) = (0, 0);
+    for (old_next, new_next) in pairs {
+        match (old_next - old_idx, new_next - new_idx) {
+            (0, 0) => {},
+            (deleted, 0) => ops.push(DiffOp::Delete { old_index: old_idx, old_len: deleted, new_index: new_idx }),
+            (0, inserted) => ops.push(DiffOp::Insert { old_index: old_idx, new_index: new_idx, new_len: inserted }),
+            (deleted, inserted) => ops.push(DiffOp::Replace { old_index: old_idx, old_len: deleted, new_index: new_idx, new_len: inserted }),
+        }
+        if old_next == old_len {
+            break;
+        }
+        match ops.last_mut() {
+            Some(DiffOp::Equal { len, .. }) if old_next == old_idx && new_next == new_idx => *len += 1,
+            _ => ops.push(DiffOp::Equal { old_index: old_next, new_index: new_next, len: 1 }),
+        }
+        old_idx = old_next + 1;
+        new_idx = new_next + 1;
+    }
+    ops
+}
+
+/// Line diff with the surface of `similar::TextDiff` that call sites use most.
+pub struct TextDiff<'a> {
+    old: Vec<&'a str>,
+    new: Vec<&'a str>,
+    ops: Vec<DiffOp>,
+}
+
+impl<'a> TextDiff<'a> {
+    pub fn from_lines(old: &'a str, new: &'a str) -> Self {
+        TextDiff::from_lines_with(Algorithm::default(), old, new)
+    }
+
+    /// Splits after every newline, keeping it, like `similar` does.
+    pub fn from_lines_with(algorithm: Algorithm, old: &'a str, new: &'a str) -> Self {
+        let old = old.split_inclusive('\n').collect::<Vec<_>>();
+        let new = new.split_inclusive('\n').collect::<Vec<_>>();
+        let ops = capture_diff_slices(algorithm, &old, &new);
+        TextDiff { old, new, ops }
+    }
+
+    pub fn ops(&self) -> &[DiffOp] {
+        &self.ops
+    }
+
+    pub fn old_slices(&self) -> &[&'a str] {
+        &self.old
+    }
+
+    pub fn new_slices(&self) -> &[&'a str] {
+        &self.new
+    }
+
+    /// Similarity in `0.0..=1.0`, as `similar::TextDiff::ratio`.
+    pub fn ratio(&self) -> f32 {
+        similar::get_diff_ratio(&self.ops, self.old.len(), self.new.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar::ChangeTag;
+
+    /// Rebuilds new from old and the ops, checking equal ranges really are equal.
+    fn replay<T: Clone + PartialEq + std::fmt::Debug>(old: &[T], new: &[T], ops: &[DiffOp]) -> Vec<T> {
+        let mut out = Vec::new();
+        for op in ops {
+            for change in op.iter_changes(old, new) {
+                match change.tag() {
+                    ChangeTag::Equal | ChangeTag::Insert => out.push(change.value()),
+                    ChangeTag::Delete => {},
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn ops_rebuild_new() {
+        let cases: &[(&[u8], &[u8])] = &[
+            (b"", b""),
+            (b"abc", b""),
+            (b"", b"abc"),
+            (b"abcdef", b"abXdef"),
+            (b"abcdef", b"fedcba"),
+            (b"sitting", b"kitten"),
+            (b"aaabbb", b"bbbaaa"),
+        ];
+        for (old, new) in cases {
+            for algorithm in [Algorithm::Heckel, Algorithm::Myers, Algorithm::Small] {
+                let ops = capture_diff_slices(algorithm, old, new);
+                assert_eq!(replay(old, new, &ops), new.to_vec(), "{:?} -> {:?} with {:?}", old, new, algorithm);
+                for window in ops.windows(2) {
+                    assert_eq!(window[0].as_tag_tuple().1.end, window[1].as_tag_tuple().1.start);
+                    assert_eq!(window[0].as_tag_tuple().2.end, window[1].as_tag_tuple().2.start);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_similar_on_edits() {
+        let old = (0..200).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new.drain(50..60);
+        new.splice(100..100, 1000..1005);
+
+        let ours = capture_diff_slices(Algorithm::Myers, &old, &new);
+        let theirs = similar::capture_diff_slices(similar::Algorithm::Myers, &old, &new);
+        assert_eq!(ours, theirs);
+    }
+
+    #[test]
+    fn moved_line_keeps_the_rest_equal() {
+        let old = (0..100).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new.rotate_right(1);
+
+        let ops = capture_diff_slices(Algorithm::Heckel, &old, &new);
+        assert_eq!(ops, vec![
+            DiffOp::Insert { old_index: 0, new_index: 0, new_len: 1 },
+            DiffOp::Equal { old_index: 0, new_index: 1, len: 99 },
+            DiffOp::Delete { old_index: 99, old_len: 1, new_index: 100 },
+        ]);
+        assert!(similar::get_diff_ratio(&ops, old.len(), new.len()) > 0.98);
+    }
+
+    #[test]
+    fn text_diff() {
+        let diff = TextDiff::from_lines("a\nb\nc\n", "a\nc\nd\n");
+        assert_eq!(diff.old_slices(), &["a\n", "b\n", "c\n"]);
+        assert_eq!(diff.ops().len(), 4);
+        assert!((diff.ratio() - 2.0 / 3.0).abs() < 1e-6);
+    }
+}
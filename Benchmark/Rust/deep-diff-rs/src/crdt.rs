@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::hackel::Change;
+use crate::merge;
+
+/// Operation on an RGA/LSEQ-style list CRDT, addressing elements by id.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CrdtOp<'a, T, K> {
+    /// Inserts `item` right after the element `after`, or at the head.
+    InsertAfter { after: Option<K>, id: K, item: &'a T },
+    /// Marks the element as deleted; it keeps anchoring later inserts.
+    Tombstone { id: K },
+}
+
+/// Converts a changeset between `old` and `new` into CRDT operations, with
+/// `id` giving every item its stable element id.
+///
+/// Tombstones come first, then inserts in new order, each anchored to the
+/// element that precedes it in `new`, so replaying them in order on a
+/// replica holding `old` yields `new`. List CRDTs cannot move, so moved and
+/// replaced items are tombstoned and inserted again under the id `id`
+/// returns for them; replicas that never reuse ids should make it include a
+/// version. Items `diff` only reports as moved because others shifted around
+/// them stay in place.
+///
+/// # Panics
+///
+/// Panics if `changes` do not describe a batch update of `old` into `new`.
+pub fn to_crdt_ops<'a, T, K, F>(old: &'a [T], new: &'a [T], changes: &[Change<'a, T>], mut id: F) -> Vec<CrdtOp<'a, T, K>>
+where
+    F: FnMut(&T) -> K,
+{
+    let side = merge::replay(old.len(), changes, &mut HashMap::new());
+    assert_eq!(side.entries.len(), new.len(), "changes do not produce new");
+
+    let mut kept = vec![false; old.len()];
+    for entry in &side.entries {
+        if let (Some(base), None, false) = (entry.base, entry.item, entry.moved) {
+            kept[base] = true;
+        }
+    }
+
+    let mut ops = (0..old.len()).filter(|idx| !kept[*idx]).map(|idx| CrdtOp::Tombstone { id: id(&old[idx]) }).collect::<Vec<_>>();
+    for (new_idx, entry) in side.entries.iter().enumerate() {
+        if entry.base.is_some() && entry.item.is_none() && !entry.moved {
+            continue;
+        }
+        let after = new_idx.checked_sub(1).map(|prev| id(&new[prev]));
+        ops.push(CrdtOp::InsertAfter { after, id: id(&new[new_idx]), item: &new[new_idx] });
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    type Row = (u32, &'static str);
+
+    /// Minimal sequential RGA replica: elements in order with tombstone flags.
+    fn replay_ops(old: &[Row], ops: &[CrdtOp<'_, Row, (u32, u32)>]) -> Vec<&'static str> {
+        let mut elements = old.iter().map(|item| ((item.0, 0), item.1, false)).collect::<Vec<_>>();
+        for op in ops {
+            match op {
+                CrdtOp::Tombstone { id } => elements.iter_mut().filter(|e| e.0 == *id && !e.2).for_each(|e| e.2 = true),
+                CrdtOp::InsertAfter { after, id, item } => {
+                    let at = match after {
+                        None => 0,
+                        Some(after) => elements.iter().rposition(|e| e.0 == *after && !e.2).expect("anchor exists") + 1,
+                    };
+                    elements.insert(at, (*id, item.1, false));
+                },
+            }
+        }
+        elements.into_iter().filter(|e| !e.2).map(|e| e.1).collect()
+    }
+
+    #[test]
+    fn replica_converges() {
+        let old = vec![(1, "a"), (2, "b"), (3, "c"), (4, "d")];
+        let new = vec![(4, "d"), (2, "B"), (5, "e"), (1, "a")];
+        let changes = hackel::diff_by_key(&old, &new, |row| row.0);
+        // Re-inserted elements get a fresh version so ids stay unique.
+        let ops = to_crdt_ops(&old, &new, &changes, |row| (row.0, if old.contains(row) { 0 } else { 1 }));
+
+        assert_eq!(replay_ops(&old, &ops), vec!["d", "B", "e", "a"]);
+    }
+
+    #[test]
+    fn head_and_tail() {
+        let old = vec![(1, "a")];
+        let new = vec![(0, "z"), (1, "a"), (2, "b")];
+        let changes = hackel::diff(&old, &new);
+        let ops = to_crdt_ops(&old, &new, &changes, |row| row.0);
+
+        assert_eq!(ops, vec![CrdtOp::InsertAfter { after: None, id: 0, item: &new[0] }, CrdtOp::InsertAfter { after: Some(1), id: 2, item: &new[2] }]);
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use crate::hackel::Change;
+use crate::merge;
+
+/// Maps offsets between the units of an old text (bytes or chars, as
+/// diffed) to the matching offsets in the new text, so editors can keep
+/// cursors and annotations in place across a diff. See `map_position`.
+pub fn map_positions<T>(changes: &[Change<'_, T>], old_offsets: &[usize]) -> Vec<usize> {
+    // The old length only matters up to the last offset or change; the
+    // untouched tail shifts uniformly.
+    let (mut deletes, mut inserts, mut old_end, mut new_end) = (0, 0, 0, 0);
+    for change in changes {
+        match change {
+            Change::Delete(delete) => {
+                deletes += 1;
+                old_end = old_end.max(delete.index + 1);
+            },
+            Change::Insert(insert) => {
+                inserts += 1;
+                new_end = new_end.max(insert.index + 1);
+            },
+            Change::Replace(replace) => new_end = new_end.max(replace.index + 1),
+            Change::Move(mv) => {
+                old_end = old_end.max(mv.from_index + 1);
+                new_end = new_end.max(mv.to_index + 1);
+            },
+        }
+    }
+    let old_len = old_offsets.iter().copied().max().unwrap_or(0).max(old_end).max((new_end + deletes).saturating_sub(inserts));
+    let side = merge::replay(old_len, changes, &mut HashMap::new());
+
+    // `anchors[offset]` is the new offset right after the last item before
+    // `offset` that kept its place.
+    let mut after = vec![None; old_len];
+    for (new_idx, entry) in side.entries.iter().enumerate() {
+        if let (Some(base), false) = (entry.base, entry.moved) {
+            after[base] = Some(new_idx + 1);
+        }
+    }
+    let mut anchors = Vec::with_capacity(old_len + 1);
+    anchors.push(0);
+    for new_offset in after {
+        let last = anchors[anchors.len() - 1];
+        anchors.push(new_offset.unwrap_or(last));
+    }
+    old_offsets.iter().map(|offset| anchors[*offset]).collect()
+}
+
+/// Translates `old_offset`, a position between units of the old text, into
+/// the new text. The position follows the last unit before it that kept its
+/// place: deleted or moved-away units before it collapse, and text inserted
+/// exactly at it ends up after it. Replaced units count as kept.
+pub fn map_position<T>(changes: &[Change<'_, T>], old_offset: usize) -> usize {
+    map_positions(changes, &[old_offset])[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn follows_edits() {
+        let old = "abcdefg".chars().collect::<Vec<_>>();
+        let new = "abXYcdfgZ".chars().collect::<Vec<_>>();
+        let changes = hackel::diff(&old, &new);
+
+        assert_eq!(map_position(&changes, 0), 0);
+        assert_eq!(map_position(&changes, 2), 2);
+        assert_eq!(map_position(&changes, 3), 5);
+        assert_eq!(map_positions(&changes, &[5, 6, 7]), vec![6, 7, 8]);
+    }
+
+    #[test]
+    fn deletions_collapse() {
+        let old = "abcdef".chars().collect::<Vec<_>>();
+        let new = "abf".chars().collect::<Vec<_>>();
+        let changes = hackel::diff(&old, &new);
+        assert_eq!(map_positions(&changes, &[2, 4, 5, 6]), vec![2, 2, 2, 3]);
+    }
+
+    #[test]
+    fn moved_units_do_not_anchor() {
+        let old = "abcd".chars().collect::<Vec<_>>();
+        let new = "bcda".chars().collect::<Vec<_>>();
+        let changes = hackel::diff(&old, &new);
+        assert_eq!(map_positions(&changes, &[1, 2, 4]), vec![0, 1, 3]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Diffs of heterogeneous lists, such as a feed mixing header, post and ad
+//! cells, held as trait objects instead of one enum wrapping every type.
+
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::hackel::{self, Change};
+
+/// An item of a heterogeneous list. Every `Eq + Hash` type is one already;
+/// implement it by hand for types compared some other way. Being `Eq +
+/// Hash`, `Box<dyn DiffItem>` is one too: call the methods on the
+/// `dyn DiffItem` inside.
+pub trait DiffItem {
+    /// Equal items must return equal hashes.
+    fn diff_hash(&self) -> u64;
+    /// Whether `other` is the same item; items of different types usually
+    /// are not.
+    fn diff_eq(&self, other: &dyn DiffItem) -> bool;
+    /// `self`, for `diff_eq` to downcast `other` with.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Eq + Hash + Any> DiffItem for T {
+    fn diff_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn diff_eq(&self, other: &dyn DiffItem) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl PartialEq for dyn DiffItem + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.diff_eq(other)
+    }
+}
+
+impl Eq for dyn DiffItem + '_ {}
+
+impl Hash for dyn DiffItem + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.diff_hash());
+    }
+}
+
+/// Same as `diff`, for items of any types implementing `DiffItem`.
+pub fn diff_dyn<'a>(old: &'a [Box<dyn DiffItem>], new: &'a [Box<dyn DiffItem>]) -> Vec<Change<'a, Box<dyn DiffItem>>> {
+    hackel::diff(old, new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel::ChangeKind;
+
+    /// A cell compared by id only.
+    struct Post {
+        id: u32,
+        likes: u32,
+    }
+
+    impl DiffItem for Post {
+        fn diff_hash(&self) -> u64 {
+            u64::from(self.id)
+        }
+
+        fn diff_eq(&self, other: &dyn DiffItem) -> bool {
+            other.as_any().downcast_ref::<Post>().is_some_and(|post| post.id == self.id)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn mixed_cells() {
+        let old: Vec<Box<dyn DiffItem>> = vec![Box::new("header"), Box::new(Post { id: 1, likes: 0 }), Box::new(7u32), Box::new(Post { id: 2, likes: 0 })];
+        let new: Vec<Box<dyn DiffItem>> = vec![Box::new("header"), Box::new(Post { id: 2, likes: 5 }), Box::new(Post { id: 1, likes: 3 })];
+        let changes = diff_dyn(&old, &new);
+
+        // The number 7 is deleted; the posts match by id despite new likes.
+        let kinds = changes.iter().map(|change| change.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds.iter().filter(|kind| **kind == ChangeKind::Delete).count(), 1);
+        assert!(!kinds.contains(&ChangeKind::Insert));
+        assert!(Box::new(7u32) as Box<dyn DiffItem> != Box::new(7u64) as Box<dyn DiffItem>);
+        assert_eq!(new.iter().map(|item| (**item).as_any().downcast_ref::<Post>().map_or(0, |post| post.likes)).sum::<u32>(), 8);
+    }
+}
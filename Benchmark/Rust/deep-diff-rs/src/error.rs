@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fmt;
+
+/// Why a fallible diff gave up.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum DiffError {
+    /// A new position points at an old position that is out of range or
+    /// that points back somewhere else.
+    InconsistentMatch { old_idx: usize, new_idx: usize },
+    /// The pass 6 position of a matched item fell outside `usize`.
+    OffsetOverflow { old_idx: usize, new_idx: usize },
+    /// An input is longer than `Limits::max_len`.
+    TooLong { len: usize, limit: usize },
+    /// The inputs hold more distinct items than `Limits::max_unique`.
+    TooManyUnique { limit: usize },
+    /// The changes do not turn the old sequence into the new one.
+    NotReproduced,
+    /// The changes hold a different number of deletes and inserts than the
+    /// reference diff implies.
+    EditCountMismatch { edits: usize, expected: usize },
+    /// Retains and deletes consume a different number of items than the old
+    /// sequence holds.
+    OperationsMismatch { old_len: usize, consumed: usize },
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::InconsistentMatch { old_idx, new_idx } => write!(f, "new item {} is matched to old item {}, which does not match it back", new_idx, old_idx),
+            DiffError::OffsetOverflow { old_idx, new_idx } => write!(f, "move offset of old item {} at new item {} overflowed", old_idx, new_idx),
+            DiffError::TooLong { len, limit } => write!(f, "input of {} items exceeds the limit of {}", len, limit),
+            DiffError::TooManyUnique { limit } => write!(f, "inputs hold more than {} distinct items", limit),
+            DiffError::NotReproduced => write!(f, "changes do not turn old into new"),
+            DiffError::EditCountMismatch { edits, expected } => write!(f, "{} deletes and inserts where the reference implies {}", edits, expected),
+            DiffError::OperationsMismatch { old_len, consumed } => write!(f, "operations consume {} of {} old items", consumed, old_len),
+        }
+    }
+}
+
+impl Error for DiffError {}
+
+/// Why a changeset cannot be applied to a sequence; see `apply_dry_run`.
+/// `change` is the position of the offending change in the changeset.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum ApplyError {
+    /// An index is past the end of the old sequence (deletes and move
+    /// sources) or of the new one (inserts, move destinations and replaces).
+    OutOfBounds { change: usize, index: usize, len: usize },
+    /// An index was already deleted, moved or filled by the change at
+    /// `prior`.
+    Conflict { change: usize, prior: usize, index: usize },
+}
+
+impl ApplyError {
+    /// Position of the offending change.
+    pub fn change(&self) -> usize {
+        match self {
+            ApplyError::OutOfBounds { change, .. } | ApplyError::Conflict { change, .. } => *change,
+        }
+    }
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::OutOfBounds { change, index, len } => write!(f, "change {} uses index {}, out of bounds for length {}", change, index, len),
+            ApplyError::Conflict { change, prior, index } => write!(f, "change {} reuses index {}, already taken by change {}", change, index, prior),
+        }
+    }
+}
+
+impl Error for ApplyError {}
+
+/// Why a checked changeset was not applied; see `integrity`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum IntegrityError {
+    /// The target is not the sequence the changeset was made from.
+    PreMismatch { expected: u64, actual: u64 },
+    /// The changes do not fit the target.
+    Apply(ApplyError),
+    /// Applying the changes did not produce the sequence they were made for.
+    PostMismatch { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::PreMismatch { expected, actual } => write!(f, "target checksum {:016x} does not match the expected {:016x}", actual, expected),
+            IntegrityError::Apply(err) => write!(f, "changes do not apply: {}", err),
+            IntegrityError::PostMismatch { expected, actual } => write!(f, "result checksum {:016x} does not match the expected {:016x}", actual, expected),
+        }
+    }
+}
+
+impl Error for IntegrityError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IntegrityError::Apply(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ApplyError> for IntegrityError {
+    fn from(err: ApplyError) -> Self {
+        IntegrityError::Apply(err)
+    }
+}
+
+/// Why `diff_pinned` cannot keep the pinned items in place.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum PinError {
+    /// The pinned old item has no counterpart in new.
+    Deleted { index: usize },
+    /// The pinned old item changed order with an earlier pinned one.
+    Reordered { index: usize },
+}
+
+impl fmt::Display for PinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinError::Deleted { index } => write!(f, "pinned item {} is deleted", index),
+            PinError::Reordered { index } => write!(f, "pinned item {} moves past another pinned item", index),
+        }
+    }
+}
+
+impl Error for PinError {}
@@ -0,0 +1,67 @@
+use std::hash::Hash;
+
+use crate::hackel::{self, Change};
+
+/// `old.diff(&new)` on slices and `Vec`s, like DeepDiff's `extension Array`.
+pub trait SliceDiffExt<T> {
+    fn diff<'a>(&'a self, new: &'a [T]) -> Vec<Change<'a, T>>
+    where
+        T: Eq + Hash;
+
+    /// See `hackel::diff_by_key`.
+    fn diff_by_key<'a, K, F>(&'a self, new: &'a [T], key: F) -> Vec<Change<'a, T>>
+    where
+        T: PartialEq,
+        K: Eq + Hash,
+        F: FnMut(&T) -> K;
+}
+
+impl<T> SliceDiffExt<T> for [T] {
+    fn diff<'a>(&'a self, new: &'a [T]) -> Vec<Change<'a, T>>
+    where
+        T: Eq + Hash,
+    {
+        hackel::diff(self, new)
+    }
+
+    fn diff_by_key<'a, K, F>(&'a self, new: &'a [T], key: F) -> Vec<Change<'a, T>>
+    where
+        T: PartialEq,
+        K: Eq + Hash,
+        F: FnMut(&T) -> K,
+    {
+        hackel::diff_by_key(self, new, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Debug)]
+    struct Cell {
+        id: u32,
+        title: String,
+    }
+
+    #[test]
+    fn on_vec_and_array() {
+        let old = ["a", "b", "c"].to_vec();
+        let new = ["a", "c"];
+        assert_eq!(old.diff(&new).len(), 1);
+        assert_eq!(new.diff(&old).len(), 1);
+    }
+
+    #[test]
+    fn by_key_without_hash() {
+        let old = [Cell { id: 1, title: "one".into() }, Cell { id: 2, title: "two".into() }];
+        let new = [Cell { id: 1, title: "uno".into() }, Cell { id: 2, title: "two".into() }];
+        let changes = old.diff_by_key(&new, |cell| cell.id);
+
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            Change::Replace(r) => assert_eq!((r.old_item.title.as_str(), r.new_item.title.as_str(), r.index), ("one", "uno", 0)),
+            _ => panic!("expected a replace"),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! C entry points: diffing opaque host objects (Swift or Objective-C
+//! instances) through caller-provided hash and equality callbacks, and a
+//! two-phase string diff whose results the caller copies into its own
+//! buffers.
+
+use std::ffi::{c_void, CStr};
+use std::hash::{Hash, Hasher};
+use std::os::raw::{c_char, c_int};
+use std::{ptr, slice};
+
+use crate::hackel::{self, Change};
+
+/// Hashes one item; equal items must hash equally.
+pub type HashCallback = unsafe extern "C" fn(item: *const c_void) -> u64;
+/// Tells whether two items are equal.
+pub type EqualsCallback = unsafe extern "C" fn(a: *const c_void, b: *const c_void) -> bool;
+
+/// An item with its hash computed once, compared through the callback.
+struct Opaque {
+    item: *const c_void,
+    hash: u64,
+    equals: EqualsCallback,
+}
+
+impl PartialEq for Opaque {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && unsafe { (self.equals)(self.item, other.item) }
+    }
+}
+
+impl Eq for Opaque {}
+
+impl Hash for Opaque {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+#[repr(C)]
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum DeepDiffChangeKind {
+    Insert,
+    Delete,
+    Replace,
+    Move,
+}
+
+/// One change with `diff`'s indexing: the old index for deletes and move
+/// sources, the new index otherwise. `to_index` is a move's new index and
+/// equals `index` for the other kinds.
+#[repr(C)]
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct DeepDiffChange {
+    pub kind: DeepDiffChangeKind,
+    pub index: c_int,
+    pub to_index: c_int,
+}
+
+impl<T> From<&Change<'_, T>> for DeepDiffChange {
+    fn from(change: &Change<'_, T>) -> Self {
+        let (kind, index, to_index) = match change {
+            Change::Insert(insert) => (DeepDiffChangeKind::Insert, insert.index, insert.index),
+            Change::Delete(delete) => (DeepDiffChangeKind::Delete, delete.index, delete.index),
+            Change::Replace(replace) => (DeepDiffChangeKind::Replace, replace.index, replace.index),
+            Change::Move(mv) => (DeepDiffChangeKind::Move, mv.from_index, mv.to_index),
+        };
+        DeepDiffChange { kind, index: index as c_int, to_index: to_index as c_int }
+    }
+}
+
+/// Changes owned by the library; release them with `deep_diff_changes_free`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DeepDiffChanges {
+    pub changes: *mut DeepDiffChange,
+    pub len: usize,
+}
+
+/// Diffs `old` against `new`, hashing each item once with `hash` and
+/// comparing items of equal hash with `equals`.
+///
+/// # Safety
+///
+/// `old` and `new` must point to `old_len` and `new_len` item pointers that
+/// stay valid for the call, and both callbacks must accept them.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_opaque(
+    old: *const *const c_void,
+    old_len: c_int,
+    new: *const *const c_void,
+    new_len: c_int,
+    hash: HashCallback,
+    equals: EqualsCallback,
+) -> DeepDiffChanges {
+    let wrap = |items: *const *const c_void, len: c_int| {
+        if len <= 0 {
+            return Vec::new();
+        }
+        slice::from_raw_parts(items, len as usize).iter().map(|item| Opaque { item: *item, hash: hash(*item), equals }).collect::<Vec<_>>()
+    };
+    let (old, new) = (wrap(old, old_len), wrap(new, new_len));
+
+    let changes = hackel::diff(&old, &new).iter().map(DeepDiffChange::from).collect::<Box<[_]>>();
+    let len = changes.len();
+    DeepDiffChanges { changes: Box::into_raw(changes).cast(), len }
+}
+
+/// Frees changes returned by `deep_diff_opaque`.
+///
+/// # Safety
+///
+/// `changes` must come from `deep_diff_opaque` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_changes_free(changes: DeepDiffChanges) {
+    if !changes.changes.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(changes.changes, changes.len)));
+    }
+}
+
+/// The changes of one `deep_diff_compute`, behind an opaque handle.
+pub struct DeepDiffResult {
+    changes: Vec<DeepDiffChange>,
+}
+
+/// Diffs two arrays of C strings by content and keeps the changes for
+/// `deep_diff_copy_changes`. Release the handle with `deep_diff_result_free`.
+///
+/// # Safety
+///
+/// `old` and `new` must point to `old_len` and `new_len` valid C string pointers.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_compute(old: *const *const c_char, old_len: c_int, new: *const *const c_char, new_len: c_int) -> *mut DeepDiffResult {
+    let strings = |items: *const *const c_char, len: c_int| {
+        if len <= 0 {
+            return Vec::new();
+        }
+        slice::from_raw_parts(items, len as usize).iter().map(|item| CStr::from_ptr(*item)).collect::<Vec<_>>()
+    };
+    let (old, new) = (strings(old, old_len), strings(new, new_len));
+    let changes = hackel::diff(&old, &new).iter().map(DeepDiffChange::from).collect();
+    Box::into_raw(Box::new(DeepDiffResult { changes }))
+}
+
+/// Number of changes behind `handle`; zero for a null handle.
+///
+/// # Safety
+///
+/// `handle` must be null or come from `deep_diff_compute` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_result_count(handle: *const DeepDiffResult) -> usize {
+    handle.as_ref().map_or(0, |result| result.changes.len())
+}
+
+/// Copies up to `cap` changes into `out` and returns how many were copied,
+/// so a buffer sized by `deep_diff_result_count` can be reused across calls.
+///
+/// # Safety
+///
+/// `handle` must be null or come from `deep_diff_compute` and not have been
+/// freed, and `out` must be valid for writing `cap` changes.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_copy_changes(handle: *const DeepDiffResult, out: *mut DeepDiffChange, cap: usize) -> usize {
+    let Some(result) = handle.as_ref() else {
+        return 0;
+    };
+    let count = result.changes.len().min(cap);
+    if count > 0 {
+        ptr::copy_nonoverlapping(result.changes.as_ptr(), out, count);
+    }
+    count
+}
+
+/// Frees a handle returned by `deep_diff_compute`; null is ignored.
+///
+/// # Safety
+///
+/// `handle` must be null or come from `deep_diff_compute` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_result_free(handle: *mut DeepDiffResult) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe extern "C" fn hash_word(item: *const c_void) -> u64 {
+        // Deliberately collides, so equality has to decide.
+        u64::from(*item.cast::<u32>() % 2)
+    }
+
+    unsafe extern "C" fn equal_words(a: *const c_void, b: *const c_void) -> bool {
+        *a.cast::<u32>() == *b.cast::<u32>()
+    }
+
+    #[test]
+    fn diffs_through_callbacks() {
+        // Distinct allocations: identity never decides equality.
+        let old = vec![Box::new(1u32), Box::new(2), Box::new(3)];
+        let new = vec![Box::new(3u32), Box::new(1), Box::new(4)];
+        let pointers = |items: &[Box<u32>]| items.iter().map(|item| (&**item as *const u32).cast::<c_void>()).collect::<Vec<_>>();
+        let (old_ptrs, new_ptrs) = (pointers(&old), pointers(&new));
+
+        let result = unsafe { deep_diff_opaque(old_ptrs.as_ptr(), 3, new_ptrs.as_ptr(), 3, hash_word, equal_words) };
+        let changes = unsafe { slice::from_raw_parts(result.changes, result.len) }.to_vec();
+        unsafe { deep_diff_changes_free(result) };
+
+        let expected = hackel::diff(&[1, 2, 3], &[3, 1, 4]);
+        assert_eq!(changes.len(), expected.len());
+        assert!(changes.contains(&DeepDiffChange { kind: DeepDiffChangeKind::Delete, index: 1, to_index: 1 }));
+        assert!(changes.contains(&DeepDiffChange { kind: DeepDiffChangeKind::Insert, index: 2, to_index: 2 }));
+
+        let empty = unsafe { deep_diff_opaque(ptr::null(), 0, ptr::null(), 0, hash_word, equal_words) };
+        assert_eq!(empty.len, 0);
+        unsafe { deep_diff_changes_free(empty) };
+    }
+
+    #[test]
+    fn copies_into_caller_buffer() {
+        let (old, new) = (["a\0", "b\0", "c\0"], ["c\0", "a\0"]);
+        let pointers = |items: &[&str]| items.iter().map(|item| CStr::from_bytes_with_nul(item.as_bytes()).unwrap().as_ptr()).collect::<Vec<_>>();
+        let (old_ptrs, new_ptrs) = (pointers(&old), pointers(&new));
+
+        let handle = unsafe { deep_diff_compute(old_ptrs.as_ptr(), 3, new_ptrs.as_ptr(), 2) };
+        let count = unsafe { deep_diff_result_count(handle) };
+        assert_eq!(count, hackel::diff(&["a", "b", "c"], &["c", "a"]).len());
+
+        let mut buffer = vec![DeepDiffChange { kind: DeepDiffChangeKind::Insert, index: -1, to_index: -1 }; count + 1];
+        assert_eq!(unsafe { deep_diff_copy_changes(handle, buffer.as_mut_ptr(), 1) }, 1);
+        assert_eq!(unsafe { deep_diff_copy_changes(handle, buffer.as_mut_ptr(), buffer.len()) }, count);
+        assert!(buffer[..count].contains(&DeepDiffChange { kind: DeepDiffChangeKind::Delete, index: 1, to_index: 1 }));
+        assert_eq!(buffer[count].index, -1);
+        unsafe { deep_diff_result_free(handle) };
+
+        assert_eq!(unsafe { deep_diff_result_count(ptr::null()) }, 0);
+        assert_eq!(unsafe { deep_diff_copy_changes(ptr::null(), buffer.as_mut_ptr(), buffer.len()) }, 0);
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::hackel::{self, Change};
+
+/// A line change addressed by byte ranges into the mapped files.
+///
+/// Ranges exclude the line terminator.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum LineChange {
+    Insert { index: usize, new_range: Range<usize> },
+    Delete { index: usize, old_range: Range<usize> },
+    Move { from_index: usize, to_index: usize, old_range: Range<usize>, new_range: Range<usize> },
+}
+
+fn map(path: &Path) -> io::Result<Option<Mmap>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(None);
+    }
+    // Safety: the map only lives for the duration of `diff_files`. Truncating
+    // the file from another process during that window is undefined behavior,
+    // as with any memory-mapped read.
+    unsafe { Mmap::map(&file) }.map(Some)
+}
+
+fn line_ranges(bytes: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for (idx, byte) in bytes.iter().enumerate() {
+        if *byte == b'\n' {
+            ranges.push(start..idx);
+            start = idx + 1;
+        }
+    }
+    if start < bytes.len() {
+        ranges.push(start..bytes.len());
+    }
+    ranges
+}
+
+/// Line-diffs two files by memory-mapping them and hashing lines in place.
+pub fn diff_files(a: &Path, b: &Path) -> io::Result<Vec<LineChange>> {
+    let old_map = map(a)?;
+    let new_map = map(b)?;
+    let old_bytes = old_map.as_deref().unwrap_or(&[]);
+    let new_bytes = new_map.as_deref().unwrap_or(&[]);
+
+    let old_ranges = line_ranges(old_bytes);
+    let new_ranges = line_ranges(new_bytes);
+    let old_lines = old_ranges.iter().map(|r| &old_bytes[r.clone()]).collect::<Vec<_>>();
+    let new_lines = new_ranges.iter().map(|r| &new_bytes[r.clone()]).collect::<Vec<_>>();
+
+    let mut changes = Vec::new();
+    hackel::diff_with(&old_lines, &new_lines, |change| match change {
+        Change::Insert(i) => changes.push(LineChange::Insert { index: i.index, new_range: new_ranges[i.index].clone() }),
+        Change::Delete(d) => changes.push(LineChange::Delete { index: d.index, old_range: old_ranges[d.index].clone() }),
+        Change::Replace(_) => unreachable!("equal lines always hash equally"),
+        Change::Move(m) => changes.push(LineChange::Move {
+            from_index: m.from_index,
+            to_index: m.to_index,
+            old_range: old_ranges[m.from_index].clone(),
+            new_range: new_ranges[m.to_index].clone(),
+        }),
+    });
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("deep-diff-rs-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(line_ranges(b"a\nbc\n"), vec![0..1, 2..4]);
+        assert_eq!(line_ranges(b"a\n\nbc"), vec![0..1, 2..2, 3..5]);
+        assert!(line_ranges(b"").is_empty());
+    }
+
+    #[test]
+    fn diff_two_files() {
+        let a = write("a", "one\ntwo\nthree\n");
+        let b = write("b", "one\nthree\nfour\n");
+        let changes = diff_files(&a, &b).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+
+        assert_eq!(changes, vec![
+            LineChange::Delete { index: 1, old_range: 4..7 },
+            LineChange::Insert { index: 2, new_range: 10..14 },
+        ]);
+    }
+
+    #[test]
+    fn empty_file() {
+        let a = write("empty", "");
+        let b = write("nonempty", "x");
+        let changes = diff_files(&a, &b).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+
+        assert_eq!(changes, vec![LineChange::Insert { index: 0, new_range: 0..1 }]);
+    }
+}
@@ -0,0 +1,94 @@
+//! FlatBuffers encoding of changesets, matching `fbs/changeset.fbs`. Changes
+//! are fixed-size structs laid out back to back, so C++ and Swift consumers
+//! read them straight from the buffer without a deserialization pass.
+
+use flatbuffers::{FlatBufferBuilder, Push, PushAlignment};
+
+use crate::hackel::{Change, ChangeKind};
+
+/// The schema's `Change` struct: a kind byte, three bytes of padding, then
+/// the little-endian `from_index` and `to_index`.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct FlatChange([u8; 12]);
+
+impl FlatChange {
+    fn new(kind: ChangeKind, from_index: u32, to_index: u32) -> Self {
+        let mut bytes = [0; 12];
+        bytes[0] = kind as u8;
+        bytes[4..8].copy_from_slice(&from_index.to_le_bytes());
+        bytes[8..12].copy_from_slice(&to_index.to_le_bytes());
+        FlatChange(bytes)
+    }
+}
+
+impl Push for FlatChange {
+    type Output = FlatChange;
+
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        dst[..12].copy_from_slice(&self.0);
+    }
+
+    fn alignment() -> PushAlignment {
+        PushAlignment::new(4)
+    }
+}
+
+/// Writes `changes` as a finished `Changeset` buffer. Items are left out:
+/// consumers look them up by index in the arrays they diffed, which is what
+/// the FFI entry points hand over anyway.
+pub fn to_flatbuffer<T>(changes: &[Change<'_, T, u32>]) -> Vec<u8> {
+    let changes = changes
+        .iter()
+        .map(|change| match change {
+            Change::Insert(insert) => FlatChange::new(ChangeKind::Insert, insert.index, insert.index),
+            Change::Delete(delete) => FlatChange::new(ChangeKind::Delete, delete.index, delete.index),
+            Change::Replace(replace) => FlatChange::new(ChangeKind::Replace, replace.index, replace.index),
+            Change::Move(mv) => FlatChange::new(ChangeKind::Move, mv.from_index, mv.to_index),
+        })
+        .collect::<Vec<_>>();
+
+    let mut builder = FlatBufferBuilder::with_capacity(changes.len() * 12 + 32);
+    let vector = builder.create_vector(&changes);
+    let table = builder.start_table();
+    builder.push_slot_always(4, vector);
+    let root = builder.end_table(table);
+    builder.finish_minimal(root);
+    builder.finished_data().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    fn u32_at(buf: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+    }
+
+    /// Follows root, vtable and vector offsets by hand, as a consumer would.
+    fn read(buf: &[u8]) -> Vec<(u8, u32, u32)> {
+        let table = u32_at(buf, 0) as usize;
+        let vtable = (table as i64 - u32_at(buf, table) as i32 as i64) as usize;
+        let field = u16::from_le_bytes([buf[vtable + 4], buf[vtable + 5]]) as usize;
+        let vector = table + field + u32_at(buf, table + field) as usize;
+        // Structs start 4-byte aligned so consumers can map them in place.
+        assert_eq!((vector + 4) % 4, 0);
+        (0..u32_at(buf, vector) as usize).map(|idx| vector + 4 + idx * 12).map(|at| (buf[at], u32_at(buf, at + 4), u32_at(buf, at + 8))).collect()
+    }
+
+    #[test]
+    fn layout() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "x", "a"];
+        let buf = to_flatbuffer(&hackel::diff_indexed::<_, u32>(&old, &new));
+
+        assert_eq!(read(&buf), vec![(1, 1, 1), (3, 2, 0), (0, 1, 1), (3, 0, 2)]);
+    }
+
+    #[test]
+    fn empty() {
+        let buf = to_flatbuffer::<u8>(&[]);
+        assert!(read(&buf).is_empty());
+    }
+}
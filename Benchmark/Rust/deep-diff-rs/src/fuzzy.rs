@@ -0,0 +1,112 @@
+//! Fuzzy matching of text items: items left unmatched by equality are paired
+//! by the character n-grams (shingles) they share, so an edited line that
+//! also moved shows up as an update rather than a delete and an insert.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::hackel::{self, Change, Index};
+
+/// Hashes of the character `n`-grams of `text`; text shorter than `n` is
+/// one gram.
+fn shingles(text: &str, n: usize) -> HashSet<u64> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let hash = |gram: &[char]| {
+        let mut hasher = DefaultHasher::new();
+        gram.hash(&mut hasher);
+        hasher.finish()
+    };
+    if chars.len() < n.max(1) {
+        return std::iter::once(hash(&chars)).collect();
+    }
+    chars.windows(n.max(1)).map(hash).collect()
+}
+
+/// Pairs of old and new items whose `n`-gram sets have a Jaccard
+/// similarity of at least `threshold`, as `(old_idx, new_idx, similarity)`.
+/// Every item is in at most one pair; the most similar pairs win, then the
+/// earliest. Only the indices in `old_idx` and `new_idx` take part.
+pub fn similar_pairs<S: AsRef<str>>(old: &[S], new: &[S], old_idx: &[usize], new_idx: &[usize], n: usize, threshold: f64) -> Vec<(usize, usize, f64)> {
+    let old_shingles = old_idx.iter().map(|idx| (*idx, shingles(old[*idx].as_ref(), n))).collect::<HashMap<_, _>>();
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for idx in old_idx {
+        for shingle in &old_shingles[idx] {
+            index.entry(*shingle).or_default().push(*idx);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for new_idx in new_idx {
+        let grams = shingles(new[*new_idx].as_ref(), n);
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for shingle in &grams {
+            for old_idx in index.get(shingle).into_iter().flatten() {
+                *shared.entry(*old_idx).or_default() += 1;
+            }
+        }
+        for (old_idx, shared) in shared {
+            let similarity = shared as f64 / (old_shingles[&old_idx].len() + grams.len() - shared) as f64;
+            if similarity >= threshold {
+                candidates.push((old_idx, *new_idx, similarity));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.1.cmp(&b.1)).then(a.0.cmp(&b.0)));
+
+    let (mut old_taken, mut new_taken) = (HashSet::new(), HashSet::new());
+    candidates.retain(|(old_idx, new_idx, _)| {
+        if old_taken.contains(old_idx) || new_taken.contains(new_idx) {
+            return false;
+        }
+        old_taken.insert(*old_idx);
+        new_taken.insert(*new_idx);
+        true
+    });
+    candidates
+}
+
+/// Same as `diff`, then pairs the remaining items with `similar_pairs` and
+/// reports each pair as a `Replace` at its new index, followed by a `Move` if
+/// it also moved.
+pub fn diff_fuzzy<'a, S: AsRef<str> + Eq + Hash>(old: &'a [S], new: &'a [S], n: usize, threshold: f64) -> Vec<Change<'a, S>> {
+    let symbols = hackel::build::<_, usize>(old, new);
+    let (mut old_match, mut new_match) = (symbols.old_match, symbols.new_match);
+    let unmatched = |matches: &[usize]| matches.iter().enumerate().filter(|(_, matched)| **matched == usize::NONE).map(|(idx, _)| idx).collect::<Vec<_>>();
+    let (old_left, new_left) = (unmatched(&old_match), unmatched(&new_match));
+    for (old_idx, new_idx, _) in similar_pairs(old, new, &old_left, &new_left, n, threshold) {
+        old_match[old_idx] = new_idx;
+        new_match[new_idx] = old_idx;
+    }
+
+    hackel::changes_from_matches(old, new, &old_match, &new_match)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel::ChangeKind;
+
+    #[test]
+    fn edited_and_moved() {
+        let old = vec!["fn main() {", "    let total = 0;", "    println!(\"done\");", "}"];
+        let new = vec!["fn main() {", "    println!(\"done!\");", "    let total = 1;", "}"];
+        let changes = diff_fuzzy(&old, &new, 3, 0.5);
+
+        let kinds = changes.iter().map(|change| change.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![ChangeKind::Replace, ChangeKind::Move, ChangeKind::Replace, ChangeKind::Move]);
+        match &changes[0] {
+            Change::Replace(replace) => assert_eq!((*replace.old_item, replace.index), ("    println!(\"done\");", 1)),
+            _ => unreachable!(),
+        }
+        assert_eq!(diff_fuzzy(&old, &new, 3, 1.0).len(), hackel::diff(&old, &new).len());
+    }
+
+    #[test]
+    fn most_similar_wins() {
+        let old = vec!["abcdef", "abcxyz"];
+        let new = vec!["abcdeg"];
+        assert_eq!(similar_pairs(&old, &new, &[0, 1], &[0], 2, 0.1).iter().map(|pair| (pair.0, pair.1)).collect::<Vec<_>>(), vec![(0, 0)]);
+        assert!(similar_pairs(&old, &new, &[1], &[0], 2, 0.5).is_empty());
+    }
+}
@@ -1,15 +1,65 @@
 use std::hash::Hash;
-use std::collections::HashMap;
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::changeset::Changeset;
+use crate::error::DiffError;
+use crate::small::{self, SMALL_THRESHOLD};
+use crate::stats::DiffStats;
+use crate::trace;
+
+/// Integer type used to store positions, both internally and in `Change`.
+///
+/// `u32` halves the memory of the index arrays for inputs below 2^32 items.
+pub trait Index: Copy + Eq + Ord + Hash + Debug {
+    /// Sentinel marking an unmatched position; never a valid index.
+    const NONE: Self;
+
+    fn from_usize(index: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+impl Index for usize {
+    const NONE: Self = usize::MAX;
+
+    #[inline]
+    fn from_usize(index: usize) -> Self {
+        index
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+impl Index for u32 {
+    const NONE: Self = u32::MAX;
+
+    #[inline]
+    fn from_usize(index: usize) -> Self {
+        u32::try_from(index)
+            .ok()
+            .filter(|index| *index != u32::MAX)
+            .expect("index does not fit in u32")
+    }
 
-#[derive(Eq, PartialEq, Debug)]
-enum Counter {
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub(crate) enum Counter {
     Zero, One, Many
 }
 
 impl Counter {
-    fn increment(&self) -> Counter {
+    pub(crate) fn increment(&self) -> Counter {
         match self {
             Counter::Zero => Counter::One,
             Counter::One => Counter::Many,
@@ -18,176 +68,860 @@ impl Counter {
     }
 }
 
-#[derive(Eq, PartialEq, Debug)]
-struct TableEntry {
-    old_counter: Counter,
-    new_counter: Counter,
-    indexes_in_old: Vec<usize>,
+/// Per-entry state of the symbol table, one slot per distinct item.
+///
+/// Old positions of an entry form a queue threaded through `next_in_old`.
+struct Table<I> {
+    old_counter: Vec<Counter>,
+    new_counter: Vec<Counter>,
+    head_in_old: Vec<I>,
+    tail_in_old: Vec<I>,
 }
 
-impl TableEntry {
-    fn new() -> Self {
-        TableEntry{
-            old_counter: Counter::Zero,
-            new_counter: Counter::Zero,
-            indexes_in_old: Vec::new()
+impl<I: Index> Table<I> {
+    fn with_capacity(capacity: usize) -> Self {
+        Table {
+            old_counter: Vec::with_capacity(capacity),
+            new_counter: Vec::with_capacity(capacity),
+            head_in_old: Vec::with_capacity(capacity),
+            tail_in_old: Vec::with_capacity(capacity),
         }
     }
+
+    fn push(&mut self) -> I {
+        self.old_counter.push(Counter::Zero);
+        self.new_counter.push(Counter::Zero);
+        self.head_in_old.push(I::NONE);
+        self.tail_in_old.push(I::NONE);
+        I::from_usize(self.old_counter.len() - 1)
+    }
 }
 
-#[derive(Eq, PartialEq, Clone, Debug)]
-enum ArrayEntry {
-    TableEntry(Rc<RefCell<TableEntry>>),
-    IndexInOther(usize),
+/// Result of passes 1-4: for every position, the matched position in the
+/// other sequence or `I::NONE`.
+pub(crate) struct Symbols<I> {
+    pub(crate) old_match: Vec<I>,
+    pub(crate) new_match: Vec<I>,
+    pub(crate) table_entries: usize,
 }
 
-pub enum Change<'a, T> {
-    Insert(Insert<'a, T>),
-    Delete(Delete<'a, T>),
-    Replace(Replace<'a, T>),
-    Move(Move<'a, T>)
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Change<'a, T, I = usize> {
+    Insert(Insert<'a, T, I>),
+    Delete(Delete<'a, T, I>),
+    Replace(Replace<'a, T, I>),
+    Move(Move<'a, T, I>)
 }
-pub struct Insert<'a, T> {
-    item: &'a T,
-    index: usize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Insert<'a, T, I = usize> {
+    pub item: &'a T,
+    pub index: I,
 }
 
-pub struct Delete<'a, T> {
-    item: &'a T,
-    index: usize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Delete<'a, T, I = usize> {
+    pub item: &'a T,
+    pub index: I,
 }
 
-pub struct Replace<'a, T> {
-    old_item: &'a T,
-    new_item: &'a T,
-    index: usize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Replace<'a, T, I = usize> {
+    pub old_item: &'a T,
+    pub new_item: &'a T,
+    pub index: I,
 }
 
-pub struct Move<'a, T> {
-    item: &'a T,
-    from_index: usize,
-    to_index: usize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Move<'a, T, I = usize> {
+    pub item: &'a T,
+    pub from_index: I,
+    pub to_index: I,
 }
 
-#[inline]
-pub fn diff<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
-    let mut table = HashMap::new();
-    let mut old_array = Vec::new();
-    let mut new_array = Vec::new();
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum IndexChange<I = usize> {
+    Insert { index: I },
+    Delete { index: I },
+    Replace { index: I },
+    Move { from_index: I, to_index: I },
+}
 
-    for item in new {
-        let entry = table
-            .entry(item)
-            .or_insert(Rc::new(RefCell::new(TableEntry::new())));
-        let mut e = entry.borrow_mut();
-        e.new_counter = e.new_counter.increment();
-        new_array.push(ArrayEntry::TableEntry(Rc::clone(entry)));
+/// What a change does, without its items or indices.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ChangeKind {
+    Insert,
+    Delete,
+    Replace,
+    Move,
+}
+
+impl<'a, T, I> Change<'a, T, I> {
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            Change::Insert(_) => ChangeKind::Insert,
+            Change::Delete(_) => ChangeKind::Delete,
+            Change::Replace(_) => ChangeKind::Replace,
+            Change::Move(_) => ChangeKind::Move,
+        }
+    }
+}
+
+impl<I> IndexChange<I> {
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            IndexChange::Insert { .. } => ChangeKind::Insert,
+            IndexChange::Delete { .. } => ChangeKind::Delete,
+            IndexChange::Replace { .. } => ChangeKind::Replace,
+            IndexChange::Move { .. } => ChangeKind::Move,
+        }
+    }
+}
+
+pub(crate) enum Step<I> {
+    Delete(I),
+    Insert(I),
+    Match { old_idx: I, new_idx: I, moved: bool },
+}
+
+/// Hooks into the passes. The unit observer compiles away entirely.
+pub(crate) trait Observer {
+    fn lap(&mut self, _pass: usize) {}
+
+    /// Polled every `CHECK_INTERVAL` items of passes 1-4; `true` aborts the build.
+    fn expired(&mut self) -> bool {
+        false
+    }
+
+    /// Polled after every item of passes 1-2 with the current number of table
+    /// entries; `true` aborts the build.
+    fn exceeds(&mut self, _entries: usize) -> bool {
+        false
+    }
+}
+
+impl Observer for () {}
+
+const CHECK_INTERVAL: usize = 1024;
+
+struct PassTimer<'s> {
+    stats: &'s mut DiffStats,
+    start: Instant,
+}
+
+impl<'s> Observer for PassTimer<'s> {
+    fn lap(&mut self, pass: usize) {
+        self.stats.passes_ns[pass] = self.start.elapsed().as_nanos() as u64;
+        self.start = Instant::now();
+    }
+}
+
+struct Deadline(Instant);
+
+impl Observer for Deadline {
+    fn expired(&mut self) -> bool {
+        Instant::now() >= self.0
+    }
+}
+
+/// Caps on what `try_diff` accepts before allocating anything large.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Limits {
+    /// Longest accepted `old` or `new`.
+    pub max_len: usize,
+    /// Most distinct items accepted across both sides.
+    pub max_unique: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { max_len: usize::MAX, max_unique: usize::MAX }
+    }
+}
+
+impl Observer for Limits {
+    fn exceeds(&mut self, entries: usize) -> bool {
+        entries > self.max_unique
+    }
+}
+
+pub(crate) fn build<K: Eq + Hash, I: Index>(old: impl IntoIterator<Item = K>, new: impl IntoIterator<Item = K>) -> Symbols<I> {
+    build_observed(old, new, &mut ()).expect("unobserved builds never expire")
+}
+
+/// Symbol table under construction. Passes 1 and 2 take one item at a time,
+/// so a caller can interleave them with other work, such as yielding to an
+/// executor, and still match exactly what `build` does.
+pub(crate) struct Builder<K, I> {
+    ids: HashMap<K, I>,
+    table: Table<I>,
+    new_entry: Vec<I>,
+    next_in_old: Vec<I>,
+}
+
+impl<K: Eq + Hash, I: Index> Builder<K, I> {
+    pub(crate) fn with_capacity(old_len: usize, new_len: usize) -> Self {
+        trace::alloc::<(Counter, Counter, I, I)>("table", new_len);
+        Builder {
+            ids: HashMap::new(),
+            table: Table::with_capacity(new_len),
+            new_entry: Vec::with_capacity(new_len),
+            next_in_old: Vec::with_capacity(old_len),
+        }
+    }
+
+    /// Number of distinct items seen so far.
+    pub(crate) fn entries(&self) -> usize {
+        self.table.old_counter.len()
+    }
+
+    /// Pass 1 for the next new item. All new items come before any old one.
+    pub(crate) fn push_new(&mut self, item: K) {
+        let table = &mut self.table;
+        let entry = *self.ids.entry(item).or_insert_with(|| table.push());
+        self.new_entry.push(entry);
+        let entry = entry.to_usize();
+        table.new_counter[entry] = table.new_counter[entry].increment();
+    }
+
+    /// Pass 2 for the next old item.
+    pub(crate) fn push_old(&mut self, item: K) {
+        let table = &mut self.table;
+        let entry = self.ids.entry(item).or_insert_with(|| table.push()).to_usize();
+        let idx = I::from_usize(self.next_in_old.len());
+        table.old_counter[entry] = table.old_counter[entry].increment();
+        match table.tail_in_old[entry] {
+            tail if tail == I::NONE => table.head_in_old[entry] = idx,
+            tail => self.next_in_old[tail.to_usize()] = idx,
+        }
+        table.tail_in_old[entry] = idx;
+        self.next_in_old.push(I::NONE);
+    }
+
+    /// Passes 3 and 4, fused into one sweep over the new side, or `None`
+    /// once the observer reports expiry.
+    pub(crate) fn finish<O: Observer>(mut self, observer: &mut O) -> Option<Symbols<I>> {
+        let mut symbols = Symbols {
+            old_match: vec![I::NONE; self.next_in_old.len()],
+            new_match: vec![I::NONE; self.new_entry.len()],
+            table_entries: self.entries(),
+        };
+        trace::alloc::<I>("symbols", self.next_in_old.len() + self.new_entry.len());
+
+        let table = &mut self.table;
+        for (new_idx, entry) in self.new_entry.iter().enumerate() {
+            if new_idx % CHECK_INTERVAL == 0 && observer.expired() {
+                return None;
+            }
+            let entry = entry.to_usize();
+            let old_idx = table.head_in_old[entry];
+            if old_idx == I::NONE {
+                continue;
+            }
+            table.head_in_old[entry] = self.next_in_old[old_idx.to_usize()];
+
+            let new_counter = table.new_counter[entry];
+            let old_counter = table.old_counter[entry];
+            let is_observation1 = new_counter == Counter::One && old_counter == Counter::One;
+            let is_observation2 = new_counter != Counter::Zero && old_counter != Counter::Zero && symbols.old_match[old_idx.to_usize()] == I::NONE;
+
+            if is_observation1 || is_observation2 {
+                symbols.new_match[new_idx] = old_idx;
+                symbols.old_match[old_idx.to_usize()] = I::from_usize(new_idx);
+            }
+        }
+        Some(symbols)
+    }
+}
+
+/// Runs passes 1-4, or returns `None` once the observer reports expiry.
+fn build_observed<K, I, O>(old: impl IntoIterator<Item = K>, new: impl IntoIterator<Item = K>, observer: &mut O) -> Option<Symbols<I>>
+where
+    K: Eq + Hash,
+    I: Index,
+    O: Observer,
+{
+    let new = new.into_iter();
+    let old = old.into_iter();
+    let mut builder = Builder::<K, I>::with_capacity(old.size_hint().0, new.size_hint().0);
+
+    let pass = trace::Pass::enter(1, new.size_hint().0);
+    for (idx, item) in new.enumerate() {
+        if idx % CHECK_INTERVAL == 0 && observer.expired() {
+            return None;
+        }
+        builder.push_new(item);
+        if observer.exceeds(builder.entries()) {
+            return None;
+        }
     }
+    observer.lap(0);
+    pass.end();
 
+    let pass = trace::Pass::enter(2, old.size_hint().0);
+    for (idx, item) in old.enumerate() {
+        if idx % CHECK_INTERVAL == 0 && observer.expired() {
+            return None;
+        }
+        builder.push_old(item);
+        if observer.exceeds(builder.entries()) {
+            return None;
+        }
+    }
+    observer.lap(1);
+    pass.end();
+
+    let pass = trace::Pass::enter(3, builder.new_entry.len());
+    let symbols = builder.finish(observer)?;
+    observer.lap(2);
+    pass.end();
+
+    Some(symbols)
+}
+
+/// Heckel's original passes 4 and 5: items unique on both sides anchor the
+/// matching, and runs of equal items are grown outward from each anchor and
+/// from both ends. Duplicates that no anchor reaches stay unmatched instead of
+/// being paired by occurrence count.
+pub(crate) fn build_anchored<T: Eq + Hash>(old: &[T], new: &[T]) -> Symbols<usize> {
+    let mut entries: HashMap<&T, (Counter, Counter, usize)> = HashMap::new();
+    for item in new {
+        let entry = entries.entry(item).or_insert((Counter::Zero, Counter::Zero, usize::NONE));
+        entry.1 = entry.1.increment();
+    }
     for (idx, item) in old.iter().enumerate() {
-        let entry = table
-            .entry(item)
-            .or_insert(Rc::new(RefCell::new(TableEntry::new())));
-        let mut e = entry.borrow_mut();
-        e.old_counter = e.old_counter.increment();
-        e.indexes_in_old.push(idx);
-        old_array.push(ArrayEntry::TableEntry(Rc::clone(entry)));
-    }
-
-    for (new_idx, item) in new_array.iter_mut().enumerate() {
-        match item.clone() {
-            ArrayEntry::TableEntry(ref entry) => {
-                let mut entry = entry.borrow_mut();
-
-                if entry.indexes_in_old.is_empty() {
-                    continue;
-                }
+        let entry = entries.entry(item).or_insert((Counter::Zero, Counter::Zero, usize::NONE));
+        entry.0 = entry.0.increment();
+        entry.2 = idx;
+    }
 
-                let old_idx = entry.indexes_in_old.remove(0);
-                let is_observation1 = entry.new_counter == Counter::One && entry.old_counter == Counter::One;
-                let is_observation2 = entry.new_counter != Counter::Zero && entry.old_counter != Counter::Zero && item == &mut old_array[old_idx];
+    let mut symbols = Symbols {
+        old_match: vec![usize::NONE; old.len()],
+        new_match: vec![usize::NONE; new.len()],
+        table_entries: entries.len(),
+    };
 
-                if is_observation1 || is_observation2 {
-                    *item = ArrayEntry::IndexInOther(old_idx);
-                    old_array[old_idx] = ArrayEntry::IndexInOther(new_idx);
-                }
-            },
-            _ => continue
+    for (new_idx, item) in new.iter().enumerate() {
+        if let Some((Counter::One, Counter::One, old_idx)) = entries.get(item) {
+            symbols.new_match[new_idx] = *old_idx;
+            symbols.old_match[*old_idx] = new_idx;
+        }
+    }
+
+    let pair = |symbols: &mut Symbols<usize>, old_idx: usize, new_idx: usize| {
+        let unmatched = symbols.old_match[old_idx] == usize::NONE && symbols.new_match[new_idx] == usize::NONE;
+        if unmatched && old[old_idx] == new[new_idx] {
+            symbols.new_match[new_idx] = old_idx;
+            symbols.old_match[old_idx] = new_idx;
+        }
+    };
+
+    // Ascending, seeded by a virtual anchor before both sequences.
+    let mut anchor = Some(0);
+    for new_idx in 0..new.len() {
+        if let Some(old_idx) = anchor.filter(|old_idx| *old_idx < old.len()) {
+            pair(&mut symbols, old_idx, new_idx);
+        }
+        anchor = match symbols.new_match[new_idx] {
+            old_idx if old_idx == usize::NONE => None,
+            old_idx => Some(old_idx + 1),
+        };
+    }
+
+    // Descending, seeded by a virtual anchor after both sequences.
+    let mut anchor = Some(old.len());
+    for new_idx in (0..new.len()).rev() {
+        if let Some(old_idx) = anchor.filter(|old_idx| *old_idx > 0) {
+            pair(&mut symbols, old_idx - 1, new_idx);
         }
+        anchor = match symbols.new_match[new_idx] {
+            old_idx if old_idx == usize::NONE => None,
+            old_idx => Some(old_idx),
+        };
     }
 
+    symbols
+}
+
+pub(crate) fn walk<I: Index, F: FnMut(Step<I>)>(symbols: &Symbols<I>, f: F) {
+    walk_observed(symbols, f, &mut ())
+}
+
+/// Runs passes 5 and 6. Emission is never aborted once it has started.
+fn walk_observed<I, F, O>(symbols: &Symbols<I>, f: F, observer: &mut O)
+where
+    I: Index,
+    F: FnMut(Step<I>),
+    O: Observer,
+{
+    let mut delete_offsets = vec![I::from_usize(0); symbols.old_match.len()];
+    trace::alloc::<I>("delete_offsets", delete_offsets.len());
+    walk_matches(&symbols.old_match, &symbols.new_match, &mut delete_offsets, f, observer)
+}
+
+/// Passes 5 and 6 over bare match slices; `delete_offsets` is scratch space
+/// as long as `old_match`.
+pub(crate) fn walk_matches<I, F, O>(old_match: &[I], new_match: &[I], delete_offsets: &mut [I], f: F, observer: &mut O)
+where
+    I: Index,
+    F: FnMut(Step<I>),
+    O: Observer,
+{
+    try_walk_matches(old_match, new_match, delete_offsets, f, observer).expect("matches are consistent")
+}
+
+/// The changes of a matching computed outside the symbol table, such as by
+/// a DP table or from anchors.
+pub(crate) fn changes_from_matches<'a, T: PartialEq>(old: &'a [T], new: &'a [T], old_match: &[usize], new_match: &[usize]) -> Vec<Change<'a, T>> {
     let mut changes = Vec::new();
     let mut delete_offsets = vec![0; old.len()];
+    walk_matches(old_match, new_match, &mut delete_offsets, |step| emit(old, new, step, &mut |change| changes.push(change)), &mut ());
+    changes
+}
 
+/// Same as `walk_matches`, reporting a malformed matching instead of
+/// panicking. Steps before the offending position have already been emitted.
+pub(crate) fn try_walk_matches<I, F, O>(old_match: &[I], new_match: &[I], delete_offsets: &mut [I], mut f: F, observer: &mut O) -> Result<(), DiffError>
+where
+    I: Index,
+    F: FnMut(Step<I>),
+    O: Observer,
+{
+    let pass = trace::Pass::enter(5, old_match.len());
     let mut running_offset = 0;
-    for (old_offset, entry) in old_array.iter().enumerate() {
-        delete_offsets[old_offset] = running_offset;
+    for (old_offset, matched) in old_match.iter().enumerate() {
+        delete_offsets[old_offset] = I::from_usize(running_offset);
 
-        match entry {
-            ArrayEntry::TableEntry(_te) => {
-                let delete = Delete { item: &old[old_offset], index: old_offset };
-                changes.push(Change::Delete(delete));
+        if *matched == I::NONE {
+            f(Step::Delete(I::from_usize(old_offset)));
 
-                running_offset += 1;
-            },
-            _ => continue
+            running_offset += 1;
         }
     }
+    observer.lap(3);
+    pass.end();
 
+    let pass = trace::Pass::enter(6, new_match.len());
     running_offset = 0;
-    for (new_offset, entry) in new_array.iter().enumerate() {
-        match entry {
-            ArrayEntry::TableEntry(_te) => {
-                running_offset += 1;
-
-                let insert = Insert { item: &new[new_offset], index: new_offset };
-                changes.push(Change::Insert(insert));
-            },
-            ArrayEntry::IndexInOther(old_idx) => {
-                if old[*old_idx] != new[new_offset] {
-                    let replace = Replace { old_item: &old[*old_idx], new_item: &new[new_offset], index: new_offset };
-                    changes.push(Change::Replace(replace));
-                }
+    for (new_offset, old_idx) in new_match.iter().enumerate() {
+        if *old_idx == I::NONE {
+            running_offset += 1;
+
+            f(Step::Insert(I::from_usize(new_offset)));
+        } else {
+            let old_offset = old_idx.to_usize();
+            if old_match.get(old_offset).map(|m| m.to_usize()) != Some(new_offset) {
+                return Err(DiffError::InconsistentMatch { old_idx: old_offset, new_idx: new_offset });
+            }
 
-                let delete_offset = delete_offsets[*old_idx];
-                if (old_idx - delete_offset + running_offset) != new_offset {
-                    let r#move = Move { item: &new[new_offset], from_index: *old_idx, to_index: new_offset };
-                    changes.push(Change::Move(r#move));
-                }
-            },
+            let position = old_offset
+                .checked_sub(delete_offsets[old_offset].to_usize())
+                .and_then(|p| p.checked_add(running_offset))
+                .ok_or(DiffError::OffsetOverflow { old_idx: old_offset, new_idx: new_offset })?;
+            f(Step::Match { old_idx: *old_idx, new_idx: I::from_usize(new_offset), moved: position != new_offset });
         }
     }
+    observer.lap(4);
+    pass.end();
+    Ok(())
+}
 
+/// Deletes come first in ascending old index, followed by inserts in
+/// ascending new index with replaces and moves interleaved at their new
+/// index. See `DeleteOrder` for the order naive application needs.
+#[inline]
+pub fn diff<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    diff_indexed(old, new)
+}
+
+/// Same as `diff`, storing positions as `I` (e.g. `u32`) instead of `usize`.
+pub fn diff_indexed<'a, T: Eq + Hash, I: Index>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T, I>> {
+    let mut changes = Vec::new();
+    diff_indexed_with(old, new, |change| changes.push(change));
+    changes
+}
+
+/// Hands each change to `f` as soon as it is computed instead of collecting them.
+///
+/// Changes arrive in the same order `diff` would return them.
+#[inline]
+pub fn diff_with<'a, T: Eq + Hash, F: FnMut(Change<'a, T>)>(old: &'a [T], new: &'a [T], f: F) {
+    diff_indexed_with(old, new, f)
+}
+
+pub fn diff_indexed_with<'a, T, I, F>(old: &'a [T], new: &'a [T], mut f: F)
+where
+    T: Eq + Hash,
+    I: Index,
+    F: FnMut(Change<'a, T, I>),
+{
+    let _span = trace::Diff::enter(old.len(), new.len());
+    if old.len() <= SMALL_THRESHOLD && new.len() <= SMALL_THRESHOLD {
+        return small::diff_small_with(old, new, f);
+    }
+
+    if let Some((deleted, inserted)) = single_run(old, new) {
+        deleted.for_each(|index| f(Change::Delete(Delete { item: &old[index], index: I::from_usize(index) })));
+        inserted.for_each(|index| f(Change::Insert(Insert { item: &new[index], index: I::from_usize(index) })));
+        return;
+    }
+
+    let symbols = build::<_, I>(old, new);
+
+    walk(&symbols, |step| emit(old, new, step, &mut f));
+}
+
+/// Detects a single run of items inserted (appends, prepends, a loaded
+/// page) or removed (truncation) with two linear scans, returning the
+/// deleted old range and the inserted new range; one of them is empty.
+/// Saves building the table for the common pagination shapes. Gives up if
+/// an item of the run also follows it, since pairing by occurrence would
+/// then match it with a different copy than the one the run leaves.
+fn single_run<T: Eq + Hash>(old: &[T], new: &[T]) -> Option<(Range<usize>, Range<usize>)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    if prefix + suffix < old.len().min(new.len()) {
+        return None;
+    }
+    let (deleted, inserted) = (prefix..old.len() - suffix, prefix..new.len() - suffix);
+    let run = if deleted.is_empty() { &new[inserted.clone()] } else { &old[deleted.clone()] };
+    let tail = &new[new.len() - suffix..];
+    if !run.is_empty() && !tail.is_empty() {
+        let run = run.iter().collect::<HashSet<_>>();
+        if tail.iter().any(|item| run.contains(item)) {
+            return None;
+        }
+    }
+    Some((deleted, inserted))
+}
+
+#[inline]
+pub(crate) fn emit<'a, T: PartialEq, I: Index, F: FnMut(Change<'a, T, I>)>(old: &'a [T], new: &'a [T], step: Step<I>, f: &mut F) {
+    match step {
+        Step::Delete(index) => f(Change::Delete(Delete { item: &old[index.to_usize()], index })),
+        Step::Insert(index) => f(Change::Insert(Insert { item: &new[index.to_usize()], index })),
+        Step::Match { old_idx, new_idx, moved } => {
+            let old_item = &old[old_idx.to_usize()];
+            let new_item = &new[new_idx.to_usize()];
+            if old_item != new_item {
+                f(Change::Replace(Replace { old_item, new_item, index: new_idx }));
+            }
+
+            if moved {
+                f(Change::Move(Move { item: new_item, from_index: old_idx, to_index: new_idx }));
+            }
+        },
+    }
+}
+
+/// Matches items by `key` instead of by value, as DeepDiff's `diffId`. An
+/// item whose key matched but whose content changed is reported as a
+/// `Replace` at its new index (followed by a `Move` if it also moved).
+pub fn diff_by_key<'a, T, K, F>(old: &'a [T], new: &'a [T], mut key: F) -> Vec<Change<'a, T>>
+where
+    T: PartialEq,
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    let old_keys = old.iter().map(&mut key).collect::<Vec<_>>();
+    let new_keys = new.iter().map(&mut key).collect::<Vec<_>>();
+    let symbols = build::<_, usize>(old_keys, new_keys);
+
+    let mut changes = Vec::new();
+    walk(&symbols, |step| emit(old, new, step, &mut |change| changes.push(change)));
     changes
 }
 
+/// Same as `diff`, returning an error instead of panicking if the matching
+/// turns out to be malformed.
+pub fn checked_diff<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> Result<Vec<Change<'a, T>>, DiffError> {
+    let symbols = build::<_, usize>(old, new);
+    let mut delete_offsets = vec![0; old.len()];
+    let mut changes = Vec::new();
+    try_walk_matches(&symbols.old_match, &symbols.new_match, &mut delete_offsets, |step| emit(old, new, step, &mut |change| changes.push(change)), &mut ())?;
+    Ok(changes)
+}
+
+/// Same as `diff`, refusing inputs beyond `limits` with an error instead of
+/// allocating for them. The unique-item limit aborts the table build as
+/// soon as it is crossed.
+pub fn try_diff<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], mut limits: Limits) -> Result<Changeset<'a, T>, DiffError> {
+    for len in [old.len(), new.len()] {
+        if len > limits.max_len {
+            return Err(DiffError::TooLong { len, limit: limits.max_len });
+        }
+    }
+
+    let symbols = build_observed::<_, usize, _>(old, new, &mut limits).ok_or(DiffError::TooManyUnique { limit: limits.max_unique })?;
+    let mut delete_offsets = vec![0; old.len()];
+    let mut changes = Vec::new();
+    try_walk_matches(&symbols.old_match, &symbols.new_match, &mut delete_offsets, |step| emit(old, new, step, &mut |change| changes.push(change)), &mut ())?;
+    Ok(changes.into())
+}
+
+/// Same as `diff`, also reporting where the time went.
+pub fn diff_with_stats<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> (Vec<Change<'a, T>>, DiffStats) {
+    let mut stats = DiffStats::default();
+
+    let run = |stats: &mut DiffStats| {
+        let mut changes = Vec::new();
+        let mut timer = PassTimer { stats, start: Instant::now() };
+
+        let symbols = build_observed::<_, usize, _>(old, new, &mut timer).expect("timers never expire");
+        let (mut matches, mut moves) = (0, 0);
+        walk_observed(&symbols, |step| {
+            if let Step::Match { moved, .. } = step {
+                matches += 1;
+                moves += moved as usize;
+            }
+            emit(old, new, step, &mut |change| changes.push(change));
+        }, &mut timer);
+
+        stats.table_entries = symbols.table_entries;
+        stats.hash_lookups = old.len() + new.len();
+        stats.matches = matches;
+        stats.moves_detected = moves;
+        changes
+    };
+
+    #[cfg(feature = "stats")]
+    let changes = {
+        let (changes, allocations) = crate::stats::measure(|| run(&mut stats));
+        stats.allocations = allocations.allocations;
+        stats.peak_bytes = allocations.peak_bytes;
+        changes
+    };
+    #[cfg(not(feature = "stats"))]
+    let changes = run(&mut stats);
+
+    (changes, stats)
+}
+
+pub enum Budgeted<'a, T> {
+    Complete(Vec<Change<'a, T>>),
+    /// The budget ran out; every old item is deleted and every new item inserted.
+    Degraded(Vec<Change<'a, T>>),
+}
+
+impl<'a, T> Budgeted<'a, T> {
+    pub fn changes(&self) -> &[Change<'a, T>] {
+        match self {
+            Budgeted::Complete(changes) | Budgeted::Degraded(changes) => changes,
+        }
+    }
+
+    pub fn into_changes(self) -> Vec<Change<'a, T>> {
+        match self {
+            Budgeted::Complete(changes) | Budgeted::Degraded(changes) => changes,
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        matches!(self, Budgeted::Degraded(_))
+    }
+}
+
+/// Diffs within `budget`, falling back to delete-all/insert-all when the
+/// symbol table passes would overrun it.
+///
+/// The deadline is checked while building the table; emitting changes is
+/// linear and always runs to completion.
+pub fn diff_with_deadline<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], budget: Duration) -> Budgeted<'a, T> {
+    let mut deadline = Deadline(Instant::now() + budget);
+    let mut changes = Vec::new();
+
+    match build_observed::<_, usize, _>(old, new, &mut deadline) {
+        Some(symbols) => {
+            walk(&symbols, |step| emit(old, new, step, &mut |change| changes.push(change)));
+            Budgeted::Complete(changes)
+        },
+        None => {
+            changes.reserve(old.len() + new.len());
+            changes.extend(old.iter().enumerate().map(|(index, item)| Change::Delete(Delete { item, index })));
+            changes.extend(new.iter().enumerate().map(|(index, item)| Change::Insert(Insert { item, index })));
+            Budgeted::Degraded(changes)
+        },
+    }
+}
+
+pub enum DiffOrReset<'a, T> {
+    Changes(Vec<Change<'a, T>>),
+    /// More changes than the caller wanted to animate; reload instead.
+    Reset,
+}
+
+/// Same as `diff`, giving up with `Reset` once there are more than
+/// `max_changes` changes, so a UI can reload its list instead of animating
+/// hundreds of updates. Unmatched items alone decide most resets right
+/// after the table build; otherwise changes stop being collected as soon as
+/// the count crosses the threshold.
+pub fn diff_or_reset<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], max_changes: usize) -> DiffOrReset<'a, T> {
+    let symbols = build::<_, usize>(old, new);
+    let unmatched = |matches: &[usize]| matches.iter().filter(|idx| **idx == usize::NONE).count();
+    if unmatched(&symbols.old_match) + unmatched(&symbols.new_match) > max_changes {
+        return DiffOrReset::Reset;
+    }
+
+    let mut changes = Vec::new();
+    let mut exceeded = false;
+    walk(&symbols, |step| {
+        if !exceeded {
+            emit(old, new, step, &mut |change| changes.push(change));
+            exceeded = changes.len() > max_changes;
+        }
+    });
+    if exceeded {
+        DiffOrReset::Reset
+    } else {
+        DiffOrReset::Changes(changes)
+    }
+}
+
+/// Similarity in `0.0..=1.0`: twice the matched items over the total
+/// length, as difflib's `ratio()`. Moved items count as matched, so this
+/// equals difflib's `quick_ratio()` and is at least its `ratio()`. Runs only
+/// passes 1-4 and allocates no changes. Two empty inputs are identical.
+pub fn similarity_ratio<T: Eq + Hash>(old: &[T], new: &[T]) -> f64 {
+    let total = old.len() + new.len();
+    if total == 0 {
+        return 1.0;
+    }
+    let symbols = build::<_, usize>(old, new);
+    let matches = symbols.new_match.iter().filter(|old_idx| **old_idx != usize::NONE).count();
+    2.0 * matches as f64 / total as f64
+}
+
+/// Membership comparison of two sequences as multisets: each bucket holds
+/// distinct items with their counts, in order of first occurrence (in new
+/// for `only_in_new`, in old otherwise). An item found twice in old and once
+/// in new is counted once in `in_both` and once in `only_in_old`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SymmetricDiff<'a, T> {
+    pub only_in_old: Vec<(&'a T, usize)>,
+    pub only_in_new: Vec<(&'a T, usize)>,
+    pub in_both: Vec<(&'a T, usize)>,
+}
+
+/// Buckets the items of `old` and `new` by whether `diff` would pair them,
+/// for callers that need membership rather than positions.
+pub fn symmetric_diff<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> SymmetricDiff<'a, T> {
+    fn count<'a, T: Eq + Hash>(bucket: &mut Vec<(&'a T, usize)>, slots: &mut HashMap<&'a T, usize>, item: &'a T) {
+        let slot = *slots.entry(item).or_insert_with(|| {
+            bucket.push((item, 0));
+            bucket.len() - 1
+        });
+        bucket[slot].1 += 1;
+    }
+
+    let symbols = build::<_, usize>(old, new);
+    let mut result = SymmetricDiff { only_in_old: Vec::new(), only_in_new: Vec::new(), in_both: Vec::new() };
+    let (mut old_slots, mut new_slots, mut both_slots) = (HashMap::new(), HashMap::new(), HashMap::new());
+    for (item, matched) in old.iter().zip(&symbols.old_match) {
+        match *matched {
+            usize::NONE => count(&mut result.only_in_old, &mut old_slots, item),
+            _ => count(&mut result.in_both, &mut both_slots, item),
+        }
+    }
+    for (item, matched) in new.iter().zip(&symbols.new_match) {
+        if *matched == usize::NONE {
+            count(&mut result.only_in_new, &mut new_slots, item);
+        }
+    }
+    result
+}
+
+/// The new index of every old item, `None` if it was deleted. Selection
+/// and scroll-position preservation need this rather than the edit script.
+/// Pairs items exactly as `diff` does.
+pub fn index_map<T: Eq + Hash>(old: &[T], new: &[T]) -> Vec<Option<usize>> {
+    let old_match = if old.len() <= SMALL_THRESHOLD && new.len() <= SMALL_THRESHOLD {
+        let mut old_match = vec![usize::NONE; old.len()];
+        small::match_small(old, new, &mut old_match, &mut vec![usize::NONE; new.len()]);
+        old_match
+    } else {
+        build::<_, usize>(old, new).old_match
+    };
+    old_match.into_iter().map(|new_idx| Some(new_idx).filter(|idx| *idx != usize::NONE)).collect()
+}
+
+/// Inverts an `index_map` into the old index of every one of `new_len` new
+/// items, `None` if it was inserted.
+pub fn invert_index_map(map: &[Option<usize>], new_len: usize) -> Vec<Option<usize>> {
+    let mut inverse = vec![None; new_len];
+    for (old_idx, new_idx) in map.iter().enumerate() {
+        if let Some(new_idx) = new_idx {
+            inverse[*new_idx] = Some(old_idx);
+        }
+    }
+    inverse
+}
+
+/// Diffs two streams of owned items without requiring contiguous storage.
+///
+/// Only one copy of each distinct item is kept, in the symbol table; the
+/// result addresses items by position so callers can resolve them against
+/// their own storage.
+pub fn diff_iter<T, O, N>(old: O, new: N) -> Vec<IndexChange>
+where
+    T: Eq + Hash,
+    O: IntoIterator<Item = T>,
+    N: IntoIterator<Item = T>,
+{
+    let mut changes = Vec::new();
+    diff_iter_with(old, new, |change| changes.push(change));
+    changes
+}
+
+pub fn diff_iter_with<T, O, N, F>(old: O, new: N, mut f: F)
+where
+    T: Eq + Hash,
+    O: IntoIterator<Item = T>,
+    N: IntoIterator<Item = T>,
+    F: FnMut(IndexChange),
+{
+    let symbols = build::<_, usize>(old, new);
+
+    walk(&symbols, |step| match step {
+        Step::Delete(index) => f(IndexChange::Delete { index }),
+        Step::Insert(index) => f(IndexChange::Insert { index }),
+        Step::Match { old_idx, new_idx, moved } => {
+            if moved {
+                f(IndexChange::Move { from_index: old_idx, to_index: new_idx });
+            }
+        },
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    impl<'a, T> Change<'a, T> {
-        pub fn insert(&self) -> Option<&Insert<T>> {
+    impl<'a, T, I> Change<'a, T, I> {
+        pub fn insert(&self) -> Option<&Insert<'_, T, I>> {
             match self {
                 Change::Insert(i) => Some(i),
                 _ => None
             }
         }
 
-        pub fn delete(&self) -> Option<&Delete<T>> {
+        pub fn delete(&self) -> Option<&Delete<'_, T, I>> {
             match self {
                 Change::Delete(d) => Some(d),
                 _ => None
             }
         }
 
-        pub fn replace(&self) -> Option<&Replace<T>> {
+        pub fn replace(&self) -> Option<&Replace<'_, T, I>> {
             match self {
                 Change::Replace(r) => Some(r),
                 _ => None
             }
         }
 
-        pub fn r#move(&self) -> Option<&Move<T>> {
+        pub fn r#move(&self) -> Option<&Move<'_, T, I>> {
             match self {
                 Change::Move(m) => Some(m),
                 _ => None
@@ -266,6 +1000,87 @@ mod tests {
         assert!(changes[1].insert().is_some());
     }
 
+    #[test]
+    fn single_runs() {
+        let page = (0..40).collect::<Vec<u32>>();
+        let appended = (0..60).collect::<Vec<u32>>();
+        let prepended = (20..40).chain(0..40).collect::<Vec<u32>>();
+        let truncated = (0..15).chain(35..40).collect::<Vec<u32>>();
+
+        assert_eq!(single_run(&page, &appended), Some((40..40, 40..60)));
+        assert_eq!(single_run(&page, &truncated), Some((15..35, 15..15)));
+        assert_eq!(single_run(&page, &page), Some((40..40, 40..40)));
+        let mut swapped = page.clone();
+        swapped.swap(3, 30);
+        assert_eq!(single_run(&page, &swapped), None);
+
+        // Items of the run repeated after it are paired by occurrence.
+        assert_eq!(single_run(&page, &prepended), None);
+        assert_eq!(single_run(&page, &(100..120).chain(0..40).collect::<Vec<u32>>()), Some((0..0, 0..20)));
+        assert!(diff(&page, &page).is_empty());
+    }
+
+    #[test]
+    fn entry_points_agree_on_repeated_runs() {
+        fn shape<T>(changes: &[Change<'_, T>]) -> Vec<IndexChange> {
+            changes
+                .iter()
+                .map(|change| match change {
+                    Change::Insert(insert) => IndexChange::Insert { index: insert.index },
+                    Change::Delete(delete) => IndexChange::Delete { index: delete.index },
+                    Change::Replace(replace) => IndexChange::Replace { index: replace.index },
+                    Change::Move(mv) => IndexChange::Move { from_index: mv.from_index, to_index: mv.to_index },
+                })
+                .collect()
+        }
+
+        let page = (0..40).collect::<Vec<u32>>();
+        let cases = [
+            (page.clone(), (20..40).chain(0..40).collect::<Vec<u32>>()),
+            (page.clone(), (0..40).chain(0..10).collect()),
+            ((0..40).chain(30..40).collect(), page.clone()),
+            ((10..40).chain(0..40).collect(), page.clone()),
+        ];
+        for (old, new) in &cases {
+            let expected = shape(&diff(old, new));
+            assert_eq!(shape(&crate::options::diff_with_options(old, new, &Default::default())), expected);
+            assert_eq!(shape(&checked_diff(old, new).unwrap()), expected);
+            assert_eq!(shape(&try_diff(old, new, Limits::default()).unwrap()), expected);
+            assert_eq!(shape(&diff_with_stats(old, new).0), expected);
+            assert_eq!(shape(diff_with_deadline(old, new, Duration::from_secs(60)).changes()), expected);
+            assert!(matches!(diff_or_reset(old, new, usize::MAX), DiffOrReset::Changes(changes) if shape(&changes) == expected));
+            assert_eq!(shape(&crate::cache::DiffCache::new(1).diff(old, new)), expected);
+            assert_eq!(shape(&crate::incremental::DiffState::new(old, new).changes(old, new)), expected);
+            assert_eq!(shape(&crate::parallel::diff_parallel(old, new)), expected);
+            assert_eq!(shape(&crate::ordered::diff_ord(old, new)), expected);
+            assert_eq!(shape(&crate::input::diff_input(old.as_slice(), new.as_slice())), expected);
+
+            let side = crate::merge::replay(old.len(), &diff(old, new), &mut HashMap::new());
+            let mut map = vec![None; old.len()];
+            for (new_idx, entry) in side.entries.iter().enumerate() {
+                if let Some(base) = entry.base {
+                    map[base] = Some(new_idx);
+                }
+            }
+            assert_eq!(index_map(old, new), map);
+        }
+    }
+
+    #[test]
+    fn symmetric() {
+        let old = vec!["a", "b", "b", "c", "b"];
+        let new = vec!["d", "b", "a", "d", "a"];
+        assert_eq!(symmetric_diff(&old, &new), SymmetricDiff {
+            only_in_old: vec![(&"b", 2), (&"c", 1)],
+            only_in_new: vec![(&"d", 2), (&"a", 1)],
+            in_both: vec![(&"a", 1), (&"b", 1)],
+        });
+
+        let same = symmetric_diff(&old, &old);
+        assert!(same.only_in_old.is_empty() && same.only_in_new.is_empty());
+        assert_eq!(same.in_both, vec![(&"a", 1), (&"b", 3), (&"c", 1)]);
+    }
+
     #[test]
     fn same_prefix() {
         let old = vec!["a", "b", "c"];
@@ -449,4 +1264,192 @@ mod tests {
         assert!(changes[0].delete().is_some());
         assert!(changes[1].insert().is_some());
     }
+
+    #[test]
+    fn iter_matches_slices() {
+        let old = vec![3, 2, 1, 5];
+        let new = vec![1, 4, 3];
+        let expected = diff(&old, &new);
+        let changes = diff_iter(old.clone(), new.iter().copied());
+        assert_eq!(changes.len(), expected.len());
+
+        for (change, expected) in changes.iter().zip(expected.iter()) {
+            match (change, expected) {
+                (IndexChange::Insert { index }, Change::Insert(i)) => assert_eq!(*index, i.index),
+                (IndexChange::Delete { index }, Change::Delete(d)) => assert_eq!(*index, d.index),
+                (IndexChange::Move { from_index, to_index }, Change::Move(m)) => {
+                    assert_eq!(*from_index, m.from_index);
+                    assert_eq!(*to_index, m.to_index);
+                },
+                _ => panic!("mismatched change kinds"),
+            }
+        }
+    }
+
+    #[test]
+    fn iter_over_chained_chunks() {
+        let chunks = [vec!["a", "b"], vec!["c"], vec!["d", "e"]];
+        let new = ["a", "c", "d", "x"];
+        let changes = diff_iter(chunks.iter().flatten(), new.iter());
+        assert_eq!(changes.len(), 3);
+
+        assert!(matches!(changes[0], IndexChange::Delete { index: 1 }));
+        assert!(matches!(changes[1], IndexChange::Delete { index: 4 }));
+        assert!(matches!(changes[2], IndexChange::Insert { index: 3 }));
+    }
+
+    #[test]
+    fn compact_index() {
+        let old = vec![1, 2, 3, 4, 5];
+        let new = vec![1, 5, 2, 3, 4];
+        let changes = diff_indexed::<_, u32>(&old, &new);
+        assert_eq!(changes.len(), 4);
+
+        assert_eq!(changes[0].r#move().unwrap().from_index, 4u32);
+        assert_eq!(changes[0].r#move().unwrap().to_index, 1u32);
+        assert_eq!(changes[1].r#move().unwrap().from_index, 1u32);
+        assert_eq!(changes[1].r#move().unwrap().to_index, 2u32);
+    }
+
+    #[test]
+    fn visit_without_collecting() {
+        let old = vec!["a", "b", "c", "d"];
+        let new = vec!["c", "d", "e", "f"];
+        let mut inserted = Vec::new();
+        let mut deleted = Vec::new();
+        diff_with(&old, &new, |change| match change {
+            Change::Insert(i) => inserted.push(*i.item),
+            Change::Delete(d) => deleted.push(*d.item),
+            _ => panic!("unexpected change"),
+        });
+
+        assert_eq!(deleted, vec!["a", "b"]);
+        assert_eq!(inserted, vec!["e", "f"]);
+    }
+
+    #[test]
+    fn similarity() {
+        assert_eq!(similarity_ratio::<u8>(&[], &[]), 1.0);
+        assert_eq!(similarity_ratio(&[1, 2, 3], &[1, 2, 3]), 1.0);
+        assert_eq!(similarity_ratio(&[1, 2, 3], &[3, 2, 1]), 1.0);
+        assert_eq!(similarity_ratio(&[1, 2], &[3, 4]), 0.0);
+        assert_eq!(similarity_ratio(b"abcd", b"bcde"), 0.75);
+        assert_eq!(similarity_ratio(&[1, 1, 2], &[1, 2, 2]), 2.0 * 2.0 / 6.0);
+    }
+
+    #[test]
+    fn stats_counters() {
+        let old = vec!["a", "b", "c", "d"];
+        let new = vec!["d", "a", "b", "e"];
+        let (changes, stats) = diff_with_stats(&old, &new);
+        assert_eq!(changes.len(), diff(&old, &new).len());
+
+        assert_eq!(stats.table_entries, 5);
+        assert_eq!(stats.hash_lookups, 8);
+        assert_eq!(stats.matches, 3);
+        assert_eq!(stats.moves_detected, changes.iter().filter(|c| c.r#move().is_some()).count());
+    }
+
+    #[test]
+    fn deadline_complete() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "b", "a"];
+        let result = diff_with_deadline(&old, &new, Duration::from_secs(60));
+        assert!(!result.is_degraded());
+        assert_eq!(result.changes().len(), 2);
+    }
+
+    #[test]
+    fn deadline_degraded() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "b", "a", "d"];
+        let result = diff_with_deadline(&old, &new, Duration::from_secs(0));
+        assert!(result.is_degraded());
+
+        let changes = result.into_changes();
+        assert_eq!(changes.len(), 7);
+        assert_eq!(changes[0].delete().unwrap().index, 0);
+        assert_eq!(changes[2].delete().unwrap().index, 2);
+        assert_eq!(changes[3].insert().unwrap().index, 0);
+        assert_eq!(changes[6].insert().unwrap().item, &"d");
+    }
+
+    /// Duplicate-heavy inputs that stress the offset bookkeeping of pass 6.
+    const DUPLICATE_CORPUS: &[(&[u8], &[u8])] = &[
+        (b"aaaa", b"aa"),
+        (b"aa", b"aaaa"),
+        (b"abab", b"baba"),
+        (b"aabbaabb", b"bbaabbaa"),
+        (b"abcabcabc", b"cbacbacba"),
+        (b"aaabaaab", b"baaabaaa"),
+        (b"xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaax", b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+        (b"ababababababababababababababababababababab", b"bbbbbbbbbbbbbbbbbbbbbaaaaaaaaaaaaaaaaaaaaa"),
+    ];
+
+    #[test]
+    fn checked_duplicate_corpus() {
+        for (old, new) in DUPLICATE_CORPUS {
+            let changes = checked_diff(old, new).unwrap();
+            assert_eq!(changes.len(), diff(old, new).len(), "{:?} -> {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn malformed_matches() {
+        let mut delete_offsets = vec![0; 2];
+        let result = try_walk_matches(&[usize::NONE, 0], &[0, usize::NONE], &mut delete_offsets, |_| (), &mut ());
+        assert_eq!(result, Err(DiffError::InconsistentMatch { old_idx: 0, new_idx: 0 }));
+
+        let result = try_walk_matches::<usize, _, _>(&[0], &[5], &mut [0], |_| (), &mut ());
+        assert_eq!(result, Err(DiffError::InconsistentMatch { old_idx: 5, new_idx: 0 }));
+    }
+
+    #[test]
+    fn limits() {
+        let old = vec!["a", "b", "a"];
+        let new = vec!["b", "c", "d", "a"];
+
+        assert_eq!(try_diff(&old, &new, Limits::default()).unwrap().len(), diff(&old, &new).len());
+        assert_eq!(try_diff(&old, &new, Limits { max_len: 3, ..Limits::default() }).err(), Some(DiffError::TooLong { len: 4, limit: 3 }));
+        assert_eq!(try_diff(&old, &new, Limits { max_unique: 3, ..Limits::default() }).err(), Some(DiffError::TooManyUnique { limit: 3 }));
+        assert!(try_diff(&old, &new, Limits { max_len: 4, max_unique: 4 }).is_ok());
+    }
+
+    #[test]
+    fn or_reset() {
+        let old = vec![1, 2, 3, 4];
+        let new = vec![4, 1, 2, 3, 5];
+        // A delete-free move flags the shifted items too: 4 moves and an insert.
+        let count = diff(&old, &new).len();
+        assert!(matches!(diff_or_reset(&old, &new, count), DiffOrReset::Changes(changes) if changes.len() == count));
+        assert!(matches!(diff_or_reset(&old, &new, count - 1), DiffOrReset::Reset));
+        assert!(matches!(diff_or_reset(&old, &[7, 8, 9], 6), DiffOrReset::Reset));
+    }
+
+    #[test]
+    fn index_maps() {
+        let old = vec!["a", "b", "c", "d"];
+        let new = vec!["d", "a", "x", "c"];
+        let map = index_map(&old, &new);
+        assert_eq!(map, vec![Some(1), None, Some(3), Some(0)]);
+        assert_eq!(invert_index_map(&map, new.len()), vec![Some(3), Some(0), None, Some(2)]);
+
+        let old = (0..100).collect::<Vec<_>>();
+        let new = old.iter().rev().copied().collect::<Vec<_>>();
+        assert!(index_map(&old, &new).iter().enumerate().all(|(idx, new_idx)| *new_idx == Some(99 - idx)));
+    }
+
+    #[test]
+    fn by_key_replaces() {
+        let old = vec![(1, "a"), (2, "b"), (3, "c")];
+        let new = vec![(3, "C"), (1, "a"), (2, "b")];
+        let changes = diff_by_key(&old, &new, |item| item.0);
+
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0].replace().unwrap().old_item, &(3, "c"));
+        assert_eq!(changes[0].replace().unwrap().new_item, &(3, "C"));
+        assert_eq!(changes[0].replace().unwrap().index, 0);
+        assert_eq!(changes[1].r#move().unwrap().from_index, 2);
+        assert!(changes[2].r#move().is_some() && changes[3].r#move().is_some());
+    }
 }
@@ -0,0 +1,143 @@
+use std::hash::Hash;
+
+use crate::hackel::{self, Change, Index, Symbols};
+
+/// A mutation the caller has just applied to the new sequence.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Mutation {
+    Insert { index: usize },
+    Remove { index: usize },
+    Replace { index: usize },
+    Move { from_index: usize, to_index: usize },
+}
+
+/// Keeps the matching between a fixed old sequence and an evolving new one.
+///
+/// Mutations only shift the stored matches, so `changes` is a linear walk
+/// with no hashing. Inserted and replaced items are never matched against
+/// old items, so the result can be less compact than a full `diff`.
+pub struct DiffState {
+    symbols: Symbols<usize>,
+}
+
+impl DiffState {
+    pub fn new<T: Eq + Hash>(old: &[T], new: &[T]) -> Self {
+        DiffState { symbols: hackel::build(old, new) }
+    }
+
+    pub fn old_len(&self) -> usize {
+        self.symbols.old_match.len()
+    }
+
+    pub fn new_len(&self) -> usize {
+        self.symbols.new_match.len()
+    }
+
+    pub fn apply(&mut self, mutation: Mutation) {
+        match mutation {
+            Mutation::Insert { index } => self.insert(index, usize::NONE),
+            Mutation::Remove { index } => {
+                let old_idx = self.remove(index);
+                self.unmatch_old(old_idx);
+            },
+            Mutation::Replace { index } => {
+                let old_idx = std::mem::replace(&mut self.symbols.new_match[index], usize::NONE);
+                self.unmatch_old(old_idx);
+            },
+            Mutation::Move { from_index, to_index } => {
+                let old_idx = self.remove(from_index);
+                self.insert(to_index, old_idx);
+            },
+        }
+    }
+
+    /// Emits the changes from `old` to `new`, which must reflect every applied mutation.
+    pub fn changes<'a, T: Eq>(&self, old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+        assert_eq!(old.len(), self.old_len(), "old sequence does not match the diff state");
+        assert_eq!(new.len(), self.new_len(), "new sequence does not match the diff state");
+
+        let mut changes = Vec::new();
+        hackel::walk(&self.symbols, |step| hackel::emit(old, new, step, &mut |change| changes.push(change)));
+        changes
+    }
+
+    fn insert(&mut self, index: usize, old_idx: usize) {
+        for matched in self.symbols.old_match.iter_mut().filter(|m| **m != usize::NONE && **m >= index) {
+            *matched += 1;
+        }
+        self.symbols.new_match.insert(index, old_idx);
+        if old_idx != usize::NONE {
+            self.symbols.old_match[old_idx] = index;
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> usize {
+        let old_idx = self.symbols.new_match.remove(index);
+        for matched in self.symbols.old_match.iter_mut().filter(|m| **m != usize::NONE && **m > index) {
+            *matched -= 1;
+        }
+        old_idx
+    }
+
+    fn unmatch_old(&mut self, old_idx: usize) {
+        if old_idx != usize::NONE {
+            self.symbols.old_match[old_idx] = usize::NONE;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds<T>(changes: &[Change<'_, T>]) -> Vec<(char, usize, usize)> {
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Insert(i) => ('i', i.index, i.index),
+                Change::Delete(d) => ('d', d.index, d.index),
+                Change::Replace(r) => ('r', r.index, r.index),
+                Change::Move(m) => ('m', m.from_index, m.to_index),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert_after_diff() {
+        let old = vec!["a", "b", "c"];
+        let mut new = vec!["a", "c"];
+        let mut state = DiffState::new(&old, &new);
+
+        new.insert(1, "x");
+        state.apply(Mutation::Insert { index: 1 });
+
+        assert_eq!(kinds(&state.changes(&old, &new)), kinds(&hackel::diff(&old, &new)));
+    }
+
+    #[test]
+    fn remove_and_replace() {
+        let old = vec!["a", "b", "c", "d"];
+        let mut new = old.clone();
+        let mut state = DiffState::new(&old, &new);
+
+        new.remove(0);
+        state.apply(Mutation::Remove { index: 0 });
+        new[1] = "x";
+        state.apply(Mutation::Replace { index: 1 });
+
+        assert_eq!(kinds(&state.changes(&old, &new)), kinds(&hackel::diff(&old, &new)));
+    }
+
+    #[test]
+    fn move_keeps_match() {
+        let old = vec!["a", "b", "c"];
+        let mut new = old.clone();
+        let mut state = DiffState::new(&old, &new);
+
+        let item = new.remove(2);
+        new.insert(0, item);
+        state.apply(Mutation::Move { from_index: 2, to_index: 0 });
+
+        assert_eq!(kinds(&state.changes(&old, &new)), kinds(&hackel::diff(&old, &new)));
+    }
+}
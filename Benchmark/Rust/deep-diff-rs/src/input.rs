@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::hackel::{self, Change, Delete, Insert, Move, Replace, Step};
+
+/// An indexable collection the differ can read in place.
+///
+/// Implemented for slices, `Vec` and `VecDeque`; persistent vectors such as
+/// `im::Vector` only need `len` and `get` to avoid copying into a `Vec`.
+pub trait DiffInput {
+    type Item;
+
+    fn len(&self) -> usize;
+
+    /// Panics if `index >= self.len()`.
+    fn get(&self, index: usize) -> &Self::Item;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> DiffInput for [T] {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn get(&self, index: usize) -> &T {
+        &self[index]
+    }
+}
+
+impl<T> DiffInput for Vec<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get(&self, index: usize) -> &T {
+        &self[index]
+    }
+}
+
+impl<T> DiffInput for VecDeque<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn get(&self, index: usize) -> &T {
+        &self[index]
+    }
+}
+
+/// Same as `diff` over any `DiffInput`, reading items through `get`.
+pub fn diff_input<'a, C, T>(old: &'a C, new: &'a C) -> Vec<Change<'a, T>>
+where
+    C: DiffInput<Item = T> + ?Sized,
+    T: Eq + Hash + 'a,
+{
+    let symbols = hackel::build::<_, usize>((0..old.len()).map(|idx| old.get(idx)), (0..new.len()).map(|idx| new.get(idx)));
+
+    let mut changes = Vec::new();
+    hackel::walk(&symbols, |step| match step {
+        Step::Delete(index) => changes.push(Change::Delete(Delete { item: old.get(index), index })),
+        Step::Insert(index) => changes.push(Change::Insert(Insert { item: new.get(index), index })),
+        Step::Match { old_idx, new_idx, moved } => {
+            let (old_item, new_item) = (old.get(old_idx), new.get(new_idx));
+            if old_item != new_item {
+                changes.push(Change::Replace(Replace { old_item, new_item, index: new_idx }));
+            }
+            if moved {
+                changes.push(Change::Move(Move { item: new_item, from_index: old_idx, to_index: new_idx }));
+            }
+        },
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(changes: &[Change<'_, u32>]) -> Vec<(u8, usize, usize)> {
+        changes.iter().map(|change| match change {
+            Change::Delete(d) => (0, d.index, d.index),
+            Change::Insert(i) => (1, i.index, i.index),
+            Change::Replace(r) => (2, r.index, r.index),
+            Change::Move(m) => (3, m.from_index, m.to_index),
+        }).collect()
+    }
+
+    #[test]
+    fn deque_matches_slices() {
+        let old = (0..100).map(|i| i * 7 % 31).collect::<Vec<u32>>();
+        let new = (0..90).map(|i| i * 5 % 29).collect::<Vec<u32>>();
+
+        // Rotated so the deque's storage wraps around.
+        let mut old_deque = VecDeque::with_capacity(old.len());
+        old_deque.extend(old[50..].iter().copied());
+        for item in old[..50].iter().rev() {
+            old_deque.push_front(*item);
+        }
+        let new_deque = new.iter().copied().collect::<VecDeque<_>>();
+        assert!(!old_deque.as_slices().1.is_empty());
+
+        let expected = summary(&hackel::diff(&old, &new));
+        assert_eq!(summary(&diff_input(&old_deque, &new_deque)), expected);
+        assert_eq!(summary(&diff_input(&old, &new)), expected);
+        assert_eq!(summary(&diff_input(&old[..], &new[..])), expected);
+    }
+}
@@ -0,0 +1,139 @@
+//! End-to-end checks for changesets shipped between processes: the sender
+//! records checksums of the old and new sequences next to the changes, and
+//! the receiver refuses to apply them to anything else.
+
+use std::hash::{Hash, Hasher};
+
+use crate::apply::{apply_dry_run, apply_to_vec};
+use crate::changeset::{Changeset, CowChange};
+use crate::error::IntegrityError;
+use crate::hackel::Change;
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher`, its output does not depend on the
+/// Rust release, so checksums from another build still compare equal.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Checksum of `items` as stored in a `CheckedChangeset`. `Hash` feeds
+/// integers in native byte order, so sender and receiver must agree on
+/// endianness and pointer width.
+pub fn checksum<T: Hash>(items: &[T]) -> u64 {
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    items.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A changeset together with optional checksums of the sequence it applies
+/// to (`pre`) and of the one it produces (`post`).
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckedChangeset<'a, T: Clone> {
+    pub pre: Option<u64>,
+    pub post: Option<u64>,
+    pub changes: Vec<CowChange<'a, T>>,
+}
+
+impl<'a, T: Clone> CheckedChangeset<'a, T> {
+    /// Wraps `changes` without checksums; see `with_checksums`.
+    pub fn new(changes: Changeset<'a, T>) -> Self {
+        CheckedChangeset { pre: None, post: None, changes: changes.into_iter().map(CowChange::from).collect() }
+    }
+
+    /// Records the checksums of the `old` and `new` sequences the changes
+    /// were computed from.
+    pub fn with_checksums(self, old: &[T], new: &[T]) -> Self
+    where
+        T: Hash,
+    {
+        CheckedChangeset { pre: Some(checksum(old)), post: Some(checksum(new)), ..self }
+    }
+
+    /// Applies the changes to `model`, checking it against `pre` first and
+    /// the result against `post`. On any error `model` is left untouched.
+    pub fn apply(&self, model: &mut Vec<T>) -> Result<(), IntegrityError>
+    where
+        T: Hash,
+    {
+        if let Some(expected) = self.pre {
+            let actual = checksum(model);
+            if actual != expected {
+                return Err(IntegrityError::PreMismatch { expected, actual });
+            }
+        }
+        let changes = self.changes.iter().map(CowChange::as_change).collect::<Vec<Change<'_, T>>>();
+        apply_dry_run(model.len(), &changes)?;
+
+        let mut result = model.clone();
+        apply_to_vec(&mut result, &changes);
+        if let Some(expected) = self.post {
+            let actual = checksum(&result);
+            if actual != expected {
+                return Err(IntegrityError::PostMismatch { expected, actual });
+            }
+        }
+        *model = result;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::changeset::diff_result;
+
+    #[test]
+    fn verified_round_trip() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "a", "d"];
+        let checked = CheckedChangeset::new(diff_result(&old, &new).into_changeset()).with_checksums(&old, &new);
+
+        let mut model = old.clone();
+        assert_eq!(checked.apply(&mut model), Ok(()));
+        assert_eq!(model, new);
+
+        let mut stale = vec!["a", "b", "x"];
+        assert!(matches!(checked.apply(&mut stale), Err(IntegrityError::PreMismatch { .. })));
+        assert_eq!(stale, ["a", "b", "x"]);
+    }
+
+    #[test]
+    fn post_mismatch() {
+        let old = vec![1, 2, 3];
+        let new = vec![3, 2];
+        let mut checked = CheckedChangeset::new(diff_result(&old, &new).into_changeset()).with_checksums(&old, &new);
+        checked.post = Some(checksum(&[2, 3]));
+
+        let mut model = old.clone();
+        assert!(matches!(checked.apply(&mut model), Err(IntegrityError::PostMismatch { .. })));
+        assert_eq!(model, old);
+
+        // Without checksums, only the shape of the changes is checked.
+        let unchecked = CheckedChangeset::new(diff_result(&old, &new).into_changeset());
+        assert_eq!(unchecked.apply(&mut vec![1, 2]), Err(IntegrityError::Apply(crate::error::ApplyError::OutOfBounds { change: 1, index: 2, len: 2 })));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip() {
+        let old = vec!["a".to_string(), "b".to_string()];
+        let new = vec!["b".to_string(), "c".to_string()];
+        let checked = CheckedChangeset::new(diff_result(&old, &new).into_changeset()).with_checksums(&old, &new);
+
+        let json = serde_json::to_string(&checked).unwrap();
+        let parsed: CheckedChangeset<'_, String> = serde_json::from_str(&json).unwrap();
+        let mut model = old.clone();
+        parsed.apply(&mut model).unwrap();
+        assert_eq!(model, new);
+    }
+}
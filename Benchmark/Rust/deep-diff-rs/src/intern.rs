@@ -0,0 +1,112 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::Hash;
+
+use crate::hackel::{self, Change, Delete, Insert, Move};
+
+/// Maps each distinct item to a small id so the differ hashes and compares `u32`s.
+///
+/// An interner can be kept around and reused across diffs of the same data.
+pub struct Interner<'a, T: ?Sized = str> {
+    ids: HashMap<&'a T, u32>,
+    items: Vec<&'a T>,
+}
+
+impl<'a, T: ?Sized + Eq + Hash> Interner<'a, T> {
+    pub fn new() -> Self {
+        Interner { ids: HashMap::new(), items: Vec::new() }
+    }
+
+    pub fn intern(&mut self, item: &'a T) -> u32 {
+        if let Some(id) = self.ids.get(item) {
+            return *id;
+        }
+
+        let id = u32::try_from(self.items.len()).expect("too many distinct items to intern");
+        self.ids.insert(item, id);
+        self.items.push(item);
+        id
+    }
+
+    pub fn intern_all<S: Borrow<T>>(&mut self, items: &'a [S]) -> Vec<u32> {
+        items.iter().map(|item| self.intern(item.borrow())).collect()
+    }
+
+    pub fn resolve(&self, id: u32) -> &'a T {
+        self.items[id as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<'a, T: ?Sized + Eq + Hash> Default for Interner<'a, T> {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+/// Diffs string lists by interning them first, for inputs with heavy repetition.
+pub fn diff_interned<'a, S: Borrow<str>>(old: &'a [S], new: &'a [S]) -> Vec<Change<'a, S>> {
+    let mut interner = Interner::<str>::new();
+    let old_ids = interner.intern_all(old);
+    let new_ids = interner.intern_all(new);
+
+    let mut changes = Vec::new();
+    hackel::diff_with(&old_ids, &new_ids, |change| changes.push(match change {
+        Change::Insert(i) => Change::Insert(Insert { item: &new[i.index], index: i.index }),
+        Change::Delete(d) => Change::Delete(Delete { item: &old[d.index], index: d.index }),
+        Change::Replace(_) => unreachable!("matched ids always refer to equal items"),
+        Change::Move(m) => Change::Move(Move { item: &new[m.to_index], from_index: m.from_index, to_index: m.to_index }),
+    }));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_deduplicates() {
+        let items = vec!["info", "warn", "info", "info", "error", "warn"];
+        let mut interner = Interner::<str>::new();
+        let ids = interner.intern_all(&items);
+
+        assert_eq!(ids, vec![0, 1, 0, 0, 2, 1]);
+        assert_eq!(interner.len(), 3);
+        assert_eq!(interner.resolve(2), "error");
+    }
+
+    #[test]
+    fn matches_plain_diff() {
+        let old = vec!["a", "-", "b", "-", "c", "-"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let new = vec!["-", "c", "-", "a", "-", "d"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        let expected = hackel::diff(&old, &new);
+        let changes = diff_interned(&old, &new);
+        assert_eq!(changes.len(), expected.len());
+
+        for (change, expected) in changes.iter().zip(expected.iter()) {
+            match (change, expected) {
+                (Change::Insert(a), Change::Insert(b)) => assert_eq!((a.item, a.index), (b.item, b.index)),
+                (Change::Delete(a), Change::Delete(b)) => assert_eq!((a.item, a.index), (b.item, b.index)),
+                (Change::Move(a), Change::Move(b)) => {
+                    assert_eq!((a.item, a.from_index, a.to_index), (b.item, b.from_index, b.to_index))
+                },
+                _ => panic!("mismatched change kinds"),
+            }
+        }
+    }
+}
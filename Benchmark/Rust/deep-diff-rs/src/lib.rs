@@ -1,12 +1,88 @@
 use std::os::raw::{c_char, c_int};
-use std::ffi::CStr;
 use std::slice;
 
-mod hackel;
+pub mod algorithm;
+pub mod alternatives;
+pub mod anchors;
+pub mod animation;
+pub mod apply;
+pub mod bench;
+pub mod bits;
+pub mod cache;
+pub mod changelog;
+pub mod changeset;
+pub mod chunk;
+pub mod columns;
+#[cfg(feature = "similar")]
+pub mod compat;
+pub mod crdt;
+pub mod cursor;
+pub mod dynamic;
+pub mod error;
+pub mod ext;
+pub mod ffi;
+#[cfg(feature = "mmap")]
+pub mod files;
+#[cfg(feature = "flatbuffers")]
+pub mod flat;
+pub mod fuzzy;
+pub mod hackel;
+pub mod incremental;
+pub mod input;
+pub mod integrity;
+pub mod intern;
+pub mod merge;
+pub mod minhash;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod myers;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+#[cfg(all(feature = "objc", target_vendor = "apple"))]
+pub mod objc;
+pub mod options;
+pub mod ordered;
+pub mod ot;
+pub mod parallel;
+pub mod pinned;
+#[cfg(feature = "plist")]
+pub mod property_list;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "syn")]
+pub mod rust_ast;
+pub mod sections;
+pub mod shared;
+pub mod small;
+pub mod snapshot;
+pub mod stats;
+pub mod substring;
+#[cfg(feature = "serde")]
+pub mod swift;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(test)]
+mod testing;
+pub mod timeseries;
+pub mod tokens;
+mod trace;
+pub mod undo;
+#[cfg(feature = "verify")]
+pub mod verify;
+pub mod viewport;
+pub mod weighted;
+pub mod winnow;
 
+#[cfg(all(feature = "stats", any(test, feature = "counting-allocator")))]
+#[global_allocator]
+static ALLOCATOR: stats::CountingAllocator = stats::CountingAllocator;
+
+/// # Safety
+///
+/// `old` and `new` must point to `old_len` and `new_len` valid C string pointers.
 #[no_mangle]
-pub extern fn diffWithString(old: *const *const c_char, old_len: c_int, new: *const *const c_char, new_len: c_int) {
-    let old = unsafe { slice::from_raw_parts(old, old_len as usize) };
-    let new = unsafe { slice::from_raw_parts(new, new_len as usize) };
-    hackel::diff(old, &new);
+pub unsafe extern "C" fn diffWithString(old: *const *const c_char, old_len: c_int, new: *const *const c_char, new_len: c_int) {
+    let old = slice::from_raw_parts(old, old_len as usize);
+    let new = slice::from_raw_parts(new, new_len as usize);
+    hackel::diff_indexed::<_, u32>(old, new);
 }
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::changeset::Changeset;
+use crate::hackel::{Change, Delete, Insert, Move, Replace};
+
+/// Both sides changed the same stretch of the base differently.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Conflict {
+    /// Base positions between the two unchanged items around the conflict.
+    pub base: Range<usize>,
+    /// New positions of our side of the stretch.
+    pub ours: Range<usize>,
+    /// New positions of their side of the stretch.
+    pub theirs: Range<usize>,
+}
+
+/// One position of a side's new sequence.
+pub(crate) struct Entry<'a, T> {
+    /// The base item placed here, if any.
+    pub(crate) base: Option<usize>,
+    /// Replacing or inserted content.
+    pub(crate) item: Option<&'a T>,
+    pub(crate) moved: bool,
+}
+
+impl<'a, T: PartialEq> Entry<'a, T> {
+    fn same(&self, other: &Self) -> bool {
+        self.base == other.base && self.moved == other.moved && self.item == other.item
+    }
+
+    fn is_survivor(&self, base: usize) -> bool {
+        self.base == Some(base) && self.item.is_none() && !self.moved
+    }
+}
+
+/// A changeset replayed against the base, as `apply_to_vec` would.
+pub(crate) struct Side<'a, T> {
+    pub(crate) entries: Vec<Entry<'a, T>>,
+    /// Positions in `entries` of base items that kept their place.
+    position: Vec<Option<usize>>,
+}
+
+/// Replays `changes` over a base of `base_len` items, recording the base
+/// items they mention in `items`.
+pub(crate) fn replay<'a, T>(base_len: usize, changes: &[Change<'a, T>], items: &mut HashMap<usize, &'a T>) -> Side<'a, T> {
+    let mut new_len = base_len;
+    let mut consumed = vec![false; base_len];
+    for change in changes {
+        match change {
+            Change::Insert(_) => new_len += 1,
+            Change::Delete(delete) => {
+                new_len -= 1;
+                consumed[delete.index] = true;
+                items.insert(delete.index, delete.item);
+            },
+            Change::Move(mv) => {
+                consumed[mv.from_index] = true;
+                items.insert(mv.from_index, mv.item);
+            },
+            Change::Replace(_) => {},
+        }
+    }
+
+    let mut slots = (0..new_len).map(|_| None).collect::<Vec<_>>();
+    for change in changes {
+        match change {
+            Change::Insert(insert) => slots[insert.index] = Some(Entry { base: None, item: Some(insert.item), moved: false }),
+            Change::Move(mv) => slots[mv.to_index] = Some(Entry { base: Some(mv.from_index), item: None, moved: true }),
+            Change::Delete(_) | Change::Replace(_) => {},
+        }
+    }
+    let mut survivors = (0..base_len).filter(|idx| !consumed[*idx]);
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = Some(Entry { base: survivors.next(), item: None, moved: false });
+    }
+    assert!(survivors.next().is_none(), "changeset leaves base items unplaced");
+
+    let mut entries = slots.into_iter().map(|slot| slot.expect("every slot is filled")).collect::<Vec<_>>();
+    for change in changes {
+        if let Change::Replace(replace) = change {
+            let entry = &mut entries[replace.index];
+            entry.item = Some(replace.new_item);
+            if let Some(base) = entry.base {
+                items.insert(base, replace.old_item);
+            }
+        }
+    }
+
+    // Batch indexing marks every shifted item as moved; only items out of
+    // order with the rest really moved.
+    let placed = entries.iter().enumerate().filter_map(|(idx, entry)| entry.base.map(|base| (base, idx))).collect::<Vec<_>>();
+    for entry in entries.iter_mut() {
+        entry.moved = entry.base.is_some();
+    }
+    for idx in longest_increasing(&placed) {
+        entries[idx].moved = false;
+    }
+
+    let mut position = vec![None; base_len];
+    for (idx, entry) in entries.iter().enumerate() {
+        if let (Some(base), None, false) = (entry.base, entry.item, entry.moved) {
+            position[base] = Some(idx);
+        }
+    }
+    Side { entries, position }
+}
+
+/// Entry positions of a longest run of `(base, position)` pairs whose base
+/// indices increase, by patience sorting.
+pub(crate) fn longest_increasing(placed: &[(usize, usize)]) -> Vec<usize> {
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![usize::MAX; placed.len()];
+    for (idx, (base, _)) in placed.iter().enumerate() {
+        let pile = tails.partition_point(|tail| placed[*tail].0 < *base);
+        if pile > 0 {
+            previous[idx] = tails[pile - 1];
+        }
+        if pile == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[pile] = idx;
+        }
+    }
+
+    let mut run = Vec::with_capacity(tails.len());
+    let mut idx = tails.last().copied().unwrap_or(usize::MAX);
+    while idx != usize::MAX {
+        run.push(placed[idx].1);
+        idx = previous[idx];
+    }
+    run
+}
+
+/// Combines two changesets computed independently against the same base of
+/// `base_len` items, as a three-way merge does.
+///
+/// Base items that both sides leave in place split the base into stretches.
+/// A stretch only one side changed takes that side's version; one both
+/// changed identically is taken once; anything else is a conflict, so as
+/// with `git merge` edits right next to each other conflict. An item moved
+/// by one side and deleted or moved elsewhere by the other is a conflict too.
+///
+/// Indices of the result follow `diff`. It contains no shift-only moves, so
+/// in-place items do not need to be available to build it.
+///
+/// # Panics
+///
+/// Panics if a changeset does not describe a batch update of `base_len` items.
+pub fn merge_changesets<'a, T: PartialEq>(base_len: usize, ours: &[Change<'a, T>], theirs: &[Change<'a, T>]) -> Result<Changeset<'a, T>, Vec<Conflict>> {
+    let mut items = HashMap::new();
+    let ours = replay(base_len, ours, &mut items);
+    let theirs = replay(base_len, theirs, &mut items);
+
+    let mut merged: Vec<&Entry<'a, T>> = Vec::new();
+    let mut conflicts = Vec::new();
+    // Each stable base item closes the stretch before it; a virtual one closes the last.
+    let mut start = (0, 0, 0);
+    for base in 0..=base_len {
+        let end = if base == base_len {
+            (base_len, ours.entries.len(), theirs.entries.len())
+        } else {
+            match (ours.position[base], theirs.position[base]) {
+                (Some(o), Some(t)) => (base, o, t),
+                _ => continue,
+            }
+        };
+
+        let base_range = start.0..end.0;
+        let ours_chunk = &ours.entries[start.1..end.1];
+        let theirs_chunk = &theirs.entries[start.2..end.2];
+        let unchanged = |chunk: &[Entry<'a, T>]| chunk.len() == base_range.len() && chunk.iter().zip(base_range.clone()).all(|(entry, base)| entry.is_survivor(base));
+        let identical = ours_chunk.len() == theirs_chunk.len() && ours_chunk.iter().zip(theirs_chunk).all(|(o, t)| o.same(t));
+
+        if unchanged(ours_chunk) || identical {
+            merged.extend(theirs_chunk);
+        } else if unchanged(theirs_chunk) {
+            merged.extend(ours_chunk);
+        } else {
+            conflicts.push(Conflict { base: base_range, ours: start.1..end.1, theirs: start.2..end.2 });
+        }
+
+        if base < base_len {
+            merged.push(&ours.entries[end.1]);
+        }
+        start = (base + 1, end.1 + 1, end.2 + 1);
+    }
+
+    // A moved item may only land once, and only if the other side kept it somewhere.
+    let mut placed = vec![0; base_len];
+    for entry in &merged {
+        if let Some(base) = entry.base {
+            placed[base] += 1;
+        }
+    }
+    let kept = |side: &Side<'a, T>, base: usize| side.entries.iter().position(|entry| entry.base == Some(base));
+    for entry in merged.iter().filter(|entry| entry.moved) {
+        let base = entry.base.expect("moves have a base item");
+        let (ours_at, theirs_at) = (kept(&ours, base), kept(&theirs, base));
+        let moved_twice = ours_at.is_some_and(|o| ours.entries[o].moved) && theirs_at.is_some_and(|t| theirs.entries[t].moved) && !identical_move(&ours, &theirs, base);
+        if placed[base] > 1 || ours_at.is_none() || theirs_at.is_none() || moved_twice {
+            let ours = ours_at.map_or(0..0, |o| o..o + 1);
+            let theirs = theirs_at.map_or(0..0, |t| t..t + 1);
+            conflicts.push(Conflict { base: base..base + 1, ours, theirs });
+        }
+    }
+    if !conflicts.is_empty() {
+        conflicts.sort_by_key(|conflict| (conflict.base.start, conflict.base.end));
+        conflicts.dedup();
+        return Err(conflicts);
+    }
+
+    let mut changes = Vec::new();
+    for base in (0..base_len).filter(|base| placed[*base] == 0) {
+        changes.push(Change::Delete(Delete { item: items[&base], index: base }));
+    }
+    for (index, entry) in merged.iter().enumerate() {
+        match (entry.base, entry.item) {
+            (None, Some(item)) => changes.push(Change::Insert(Insert { item, index })),
+            (Some(base), Some(new_item)) => changes.push(Change::Replace(Replace { old_item: items[&base], new_item, index })),
+            _ => {},
+        }
+        if let (Some(base), true) = (entry.base, entry.moved) {
+            changes.push(Change::Move(Move { item: items[&base], from_index: base, to_index: index }));
+        }
+    }
+    Ok(Changeset::from(changes))
+}
+
+/// `true` when both sides moved `base` and the merge kept a single copy.
+fn identical_move<T>(ours: &Side<'_, T>, theirs: &Side<'_, T>, base: usize) -> bool {
+    let landing = |side: &Side<'_, T>| side.entries.iter().position(|entry| entry.base == Some(base) && entry.moved);
+    landing(ours) == landing(theirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_to_vec;
+    use crate::hackel;
+
+    fn merge<'a>(base: &'a [&'a str], ours: &'a [&'a str], theirs: &'a [&'a str]) -> Result<Vec<&'a str>, Vec<Conflict>> {
+        let ours = hackel::diff(base, ours);
+        let theirs = hackel::diff(base, theirs);
+        let merged = merge_changesets(base.len(), &ours, &theirs)?;
+        let mut model = base.to_vec();
+        apply_to_vec(&mut model, &merged);
+        Ok(model)
+    }
+
+    #[test]
+    fn disjoint_edits() {
+        let base = ["a", "b", "c", "d", "e"];
+        assert_eq!(merge(&base, &["a", "x", "b", "c", "d", "e"], &["a", "b", "c", "e"]), Ok(vec!["a", "x", "b", "c", "e"]));
+        assert_eq!(merge(&base, &["b", "c", "d", "e"], &["a", "b", "c", "d", "e", "f"]), Ok(vec!["b", "c", "d", "e", "f"]));
+    }
+
+    #[test]
+    fn same_edit_on_both_sides() {
+        let base = ["a", "b", "c"];
+        assert_eq!(merge(&base, &["a", "x", "c"], &["a", "x", "c"]), Ok(vec!["a", "x", "c"]));
+        assert_eq!(merge(&base, &base, &base), Ok(base.to_vec()));
+    }
+
+    #[test]
+    fn overlapping_edits() {
+        let base = ["a", "b", "c", "d"];
+        let conflicts = merge(&base, &["a", "x", "c", "d"], &["a", "y", "c", "d"]).unwrap_err();
+        assert_eq!(conflicts, vec![Conflict { base: 1..2, ours: 1..2, theirs: 1..2 }]);
+    }
+
+    #[test]
+    fn moves() {
+        let base = ["a", "b", "c", "d", "e"];
+        assert_eq!(merge(&base, &["e", "a", "b", "c", "d"], &["a", "b", "x", "c", "d", "e"]), Ok(vec!["e", "a", "b", "x", "c", "d"]));
+        assert!(merge(&base, &["e", "a", "b", "c", "d"], &["a", "b", "c", "d"]).is_err());
+        assert!(merge(&base, &["e", "a", "b", "c", "d"], &["a", "b", "c", "e", "d"]).is_err());
+    }
+
+    #[test]
+    fn replaces() {
+        let base = vec![(1, "a"), (2, "b"), (3, "c")];
+        let ours = vec![(1, "A"), (2, "b"), (3, "c")];
+        let theirs = vec![(1, "a"), (2, "b"), (3, "C")];
+        let ours_changes = hackel::diff_by_key(&base, &ours, |row| row.0);
+        let theirs_changes = hackel::diff_by_key(&base, &theirs, |row| row.0);
+
+        let merged = merge_changesets(base.len(), &ours_changes, &theirs_changes).ok().unwrap();
+        let mut model = base.clone();
+        apply_to_vec(&mut model, &merged);
+        assert_eq!(model, vec![(1, "A"), (2, "b"), (3, "C")]);
+    }
+}
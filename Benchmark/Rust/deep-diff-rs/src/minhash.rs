@@ -0,0 +1,102 @@
+//! MinHash signatures for near-duplicate detection over item sets: the share
+//! of equal slots in two signatures estimates the Jaccard similarity of the
+//! sets, and banding buckets likely pairs before running the exact differ.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::bench::mix;
+
+/// Per-slot minimum hashes of a set. Signatures are only comparable when
+/// made with the same number of slots.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Signature(pub Vec<u64>);
+
+impl Signature {
+    /// Signature of the distinct items of `items` with `slots` hash
+    /// functions; the estimate's standard error is about `1 / sqrt(slots)`.
+    pub fn new<'a, T: Hash + 'a>(items: impl IntoIterator<Item = &'a T>, slots: usize) -> Self {
+        let mut mins = vec![u64::MAX; slots];
+        for item in items {
+            let mut hasher = DefaultHasher::new();
+            item.hash(&mut hasher);
+            let hash = hasher.finish();
+            // Mixing in the slot derives one hash function per slot.
+            for (slot, min) in mins.iter_mut().enumerate() {
+                *min = (*min).min(mix(hash ^ mix(slot as u64)));
+            }
+        }
+        Signature(mins)
+    }
+
+    /// Estimated Jaccard similarity of the two sets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the signatures have different numbers of slots.
+    pub fn jaccard(&self, other: &Signature) -> f64 {
+        assert_eq!(self.0.len(), other.0.len(), "signatures have different numbers of slots");
+        if self.0.is_empty() {
+            return 0.0;
+        }
+        let equal = self.0.iter().zip(&other.0).filter(|(a, b)| a == b).count();
+        equal as f64 / self.0.len() as f64
+    }
+
+    /// Hash of every band of `rows` consecutive slots; a trailing partial
+    /// band is dropped. Sets sharing any band are candidate duplicates.
+    pub fn bands(&self, rows: usize) -> Vec<u64> {
+        self.0
+            .chunks_exact(rows.max(1))
+            .map(|band| {
+                let mut hasher = DefaultHasher::new();
+                band.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect()
+    }
+}
+
+/// Index pairs `(i, j)`, `i < j`, of signatures sharing at least one band
+/// of `rows` slots, ascending. With `b` bands, pairs of similarity `s` are
+/// found with probability `1 - (1 - s^rows)^b`.
+pub fn candidate_pairs(signatures: &[Signature], rows: usize) -> Vec<(usize, usize)> {
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (idx, signature) in signatures.iter().enumerate() {
+        for (band, hash) in signature.bands(rows).into_iter().enumerate() {
+            buckets.entry((band, hash)).or_default().push(idx);
+        }
+    }
+    let mut pairs = BTreeSet::new();
+    for members in buckets.values() {
+        for (at, first) in members.iter().enumerate() {
+            pairs.extend(members[at + 1..].iter().map(|second| (*first, *second)));
+        }
+    }
+    pairs.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_jaccard() {
+        let a = (0..1000).collect::<Vec<u32>>();
+        let b = (250..1250).collect::<Vec<u32>>();
+        let (sig_a, sig_b) = (Signature::new(&a, 256), Signature::new(&b, 256));
+        // True similarity is 750 / 1250.
+        assert!((sig_a.jaccard(&sig_b) - 0.6).abs() < 0.1, "{}", sig_a.jaccard(&sig_b));
+        assert_eq!(sig_a.jaccard(&sig_a), 1.0);
+        assert_eq!(Signature::new(a.iter().rev(), 256), sig_a);
+    }
+
+    #[test]
+    fn buckets_near_duplicates() {
+        let docs = [(0..500).collect::<Vec<u32>>(), (5..505).collect(), (10_000..10_500).collect()];
+        let signatures = docs.iter().map(|doc| Signature::new(doc, 64)).collect::<Vec<_>>();
+        assert_eq!(candidate_pairs(&signatures, 4), vec![(0, 1)]);
+        assert_eq!(signatures[0].bands(5).len(), 12);
+    }
+}
@@ -0,0 +1,139 @@
+//! Compact MessagePack encoding of changesets. Each change is an array led by
+//! an integer tag instead of a keyed map, so a one-byte fixint replaces the
+//! variant and field names:
+//!
+//! | kind    | array                              |
+//! |---------|------------------------------------|
+//! | insert  | `[0, index, item]`                 |
+//! | delete  | `[1, index, item]`                 |
+//! | replace | `[2, index, old_item, new_item]`   |
+//! | move    | `[3, from_index, to_index, item]`  |
+
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::changeset::CowChange;
+use crate::hackel::{Change, ChangeKind};
+
+fn tag(kind: ChangeKind) -> u8 {
+    match kind {
+        ChangeKind::Insert => 0,
+        ChangeKind::Delete => 1,
+        ChangeKind::Replace => 2,
+        ChangeKind::Move => 3,
+    }
+}
+
+struct Compact<'c, 'a, T>(&'c Change<'a, T>);
+
+impl<'c, 'a, T: Serialize> Serialize for Compact<'c, 'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = match self.0 {
+            Change::Insert(_) | Change::Delete(_) => 3,
+            Change::Replace(_) | Change::Move(_) => 4,
+        };
+        let mut tuple = serializer.serialize_tuple(len)?;
+        tuple.serialize_element(&tag(self.0.kind()))?;
+        match self.0 {
+            Change::Insert(insert) => {
+                tuple.serialize_element(&insert.index)?;
+                tuple.serialize_element(insert.item)?;
+            },
+            Change::Delete(delete) => {
+                tuple.serialize_element(&delete.index)?;
+                tuple.serialize_element(delete.item)?;
+            },
+            Change::Replace(replace) => {
+                tuple.serialize_element(&replace.index)?;
+                tuple.serialize_element(replace.old_item)?;
+                tuple.serialize_element(replace.new_item)?;
+            },
+            Change::Move(mv) => {
+                tuple.serialize_element(&mv.from_index)?;
+                tuple.serialize_element(&mv.to_index)?;
+                tuple.serialize_element(mv.item)?;
+            },
+        }
+        tuple.end()
+    }
+}
+
+struct Owned<T: Clone + 'static>(CowChange<'static, T>);
+
+struct CompactVisitor<T>(PhantomData<T>);
+
+fn next<'de, A: SeqAccess<'de>, V: Deserialize<'de>>(seq: &mut A, at: usize) -> Result<V, A::Error> {
+    seq.next_element()?.ok_or_else(|| de::Error::invalid_length(at, &"a tagged change array"))
+}
+
+impl<'de, T: Clone + Deserialize<'de> + 'static> Visitor<'de> for CompactVisitor<T> {
+    type Value = Owned<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a tagged change array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let change = match next::<_, u8>(&mut seq, 0)? {
+            0 => CowChange::Insert { index: next(&mut seq, 1)?, item: Cow::Owned(next(&mut seq, 2)?) },
+            1 => CowChange::Delete { index: next(&mut seq, 1)?, item: Cow::Owned(next(&mut seq, 2)?) },
+            2 => CowChange::Replace { index: next(&mut seq, 1)?, old_item: Cow::Owned(next(&mut seq, 2)?), new_item: Cow::Owned(next(&mut seq, 3)?) },
+            3 => CowChange::Move { from_index: next(&mut seq, 1)?, to_index: next(&mut seq, 2)?, item: Cow::Owned(next(&mut seq, 3)?) },
+            tag => return Err(de::Error::invalid_value(de::Unexpected::Unsigned(tag.into()), &"a change tag from 0 to 3")),
+        };
+        Ok(Owned(change))
+    }
+}
+
+impl<'de, T: Clone + Deserialize<'de> + 'static> Deserialize<'de> for Owned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(CompactVisitor(PhantomData))
+    }
+}
+
+/// Encodes `changes` as a MessagePack array of tagged change arrays.
+pub fn to_msgpack<T: Serialize>(changes: &[Change<'_, T>]) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec(&changes.iter().map(Compact).collect::<Vec<_>>())
+}
+
+/// Decodes a changeset written by `to_msgpack`.
+pub fn from_msgpack<T: Clone + DeserializeOwned + 'static>(bytes: &[u8]) -> Result<Vec<CowChange<'static, T>>, rmp_serde::decode::Error> {
+    let changes: Vec<Owned<T>> = rmp_serde::from_slice(bytes)?;
+    Ok(changes.into_iter().map(|change| change.0).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn round_trip() {
+        let old = vec![(1, "a".to_string()), (2, "b".to_string()), (3, "c".to_string())];
+        let new = vec![(3, "c".to_string()), (4, "d".to_string()), (1, "A".to_string())];
+        let changes = hackel::diff_by_key(&old, &new, |row| row.0);
+
+        let decoded = from_msgpack::<(u32, String)>(&to_msgpack(&changes).unwrap()).unwrap();
+        let expected = changes.into_iter().map(|change| CowChange::from(change).into_owned()).collect::<Vec<_>>();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn integer_tags() {
+        let old = vec![7u8];
+        let new = vec![];
+        // fixarray(1) [fixarray(3) [0x01 tag, 0x00 index, 0x07 item]]
+        assert_eq!(to_msgpack(&hackel::diff(&old, &new)).unwrap(), vec![0x91, 0x93, 0x01, 0x00, 0x07]);
+    }
+
+    #[test]
+    fn unknown_tag() {
+        assert!(from_msgpack::<u8>(&[0x91, 0x93, 0x09, 0x00, 0x07]).is_err());
+        assert!(from_msgpack::<u8>(&[0x91, 0x92, 0x00, 0x00]).is_err());
+    }
+}
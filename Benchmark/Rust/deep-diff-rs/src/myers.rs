@@ -0,0 +1,204 @@
+use std::ops::{Index as Idx, IndexMut, Range};
+
+use crate::hackel::{self, Change, Index};
+
+/// Diagonal-indexed vector for the forward and backward searches.
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> Self {
+        V { offset: max_d as isize, v: vec![0; 2 * max_d] }
+    }
+}
+
+impl Idx<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+fn max_d(old_len: usize, new_len: usize) -> usize {
+    (old_len + new_len).div_ceil(2) + 1
+}
+
+fn common_prefix<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+/// Finds the middle snake of the shortest edit script, as in Myers' linear
+/// space refinement. Returns its start in absolute coordinates.
+fn middle_snake<T: PartialEq>(old: &[T], old_range: Range<usize>, new: &[T], new_range: Range<usize>, vf: &mut V, vb: &mut V) -> Option<(usize, usize)> {
+    let n = old_range.len();
+    let m = new_range.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+
+    vf[1] = 0;
+    vb[1] = 0;
+
+    for d in 0..max_d(n, m) as isize {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            if x < n && y < m {
+                x += common_prefix(&old[old_range.start + x..old_range.end], &new[new_range.start + y..new_range.end]);
+            }
+            vf[k] = x;
+
+            if odd && (k - delta).abs() < d && vf[k] + vb[-(k - delta)] >= n {
+                return Some((x0 + old_range.start, y0 + new_range.start));
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let advance = common_suffix(&old[old_range.start..old_range.end - x], &new[new_range.start..new_range.end - y]);
+                x += advance;
+                y += advance;
+            }
+            vb[k] = x;
+
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[-(k - delta)] >= n {
+                return Some((n - x + old_range.start, m - y + new_range.start));
+            }
+        }
+    }
+
+    None
+}
+
+/// Search vectors plus the matching being filled in.
+struct Conquer<'m, I> {
+    vf: V,
+    vb: V,
+    old_match: &'m mut [I],
+    new_match: &'m mut [I],
+}
+
+impl<'m, I: Index> Conquer<'m, I> {
+    fn pair(&mut self, old_idx: usize, new_idx: usize) {
+        self.old_match[old_idx] = I::from_usize(new_idx);
+        self.new_match[new_idx] = I::from_usize(old_idx);
+    }
+
+    fn run<T: PartialEq>(&mut self, old: &[T], mut old_range: Range<usize>, new: &[T], mut new_range: Range<usize>) {
+        let prefix = common_prefix(&old[old_range.clone()], &new[new_range.clone()]);
+        for i in 0..prefix {
+            self.pair(old_range.start + i, new_range.start + i);
+        }
+        old_range.start += prefix;
+        new_range.start += prefix;
+
+        let suffix = common_suffix(&old[old_range.clone()], &new[new_range.clone()]);
+        for i in 1..=suffix {
+            self.pair(old_range.end - i, new_range.end - i);
+        }
+        old_range.end -= suffix;
+        new_range.end -= suffix;
+
+        if old_range.is_empty() || new_range.is_empty() {
+            return;
+        }
+
+        if let Some((x, y)) = middle_snake(old, old_range.clone(), new, new_range.clone(), &mut self.vf, &mut self.vb) {
+            self.run(old, old_range.start..x, new, new_range.start..y);
+            self.run(old, x..old_range.end, new, y..new_range.end);
+        }
+    }
+}
+
+/// Pairs items along a longest common subsequence, so the result never contains moves.
+pub(crate) fn matches<T: PartialEq, I: Index>(old: &[T], new: &[T]) -> (Vec<I>, Vec<I>) {
+    let mut old_match = vec![I::NONE; old.len()];
+    let mut new_match = vec![I::NONE; new.len()];
+    let max_d = max_d(old.len(), new.len());
+    let mut conquer = Conquer { vf: V::new(max_d), vb: V::new(max_d), old_match: &mut old_match, new_match: &mut new_match };
+
+    conquer.run(old, 0..old.len(), new, 0..new.len());
+    (old_match, new_match)
+}
+
+/// Myers' O(ND) diff. Produces a minimal script of deletes and inserts.
+pub fn diff_myers<'a, T: Eq>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    let mut changes = Vec::new();
+    diff_myers_with(old, new, |change| changes.push(change));
+    changes
+}
+
+pub fn diff_myers_with<'a, T: Eq, F: FnMut(Change<'a, T>)>(old: &'a [T], new: &'a [T], mut f: F) {
+    let (old_match, new_match) = matches::<_, usize>(old, new);
+    let mut delete_offsets = vec![0; old.len()];
+    hackel::walk_matches(&old_match, &new_match, &mut delete_offsets, |step| hackel::emit(old, new, step, &mut f), &mut ());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ternary_inputs;
+
+    fn lcs_len(old: &[u8], new: &[u8]) -> usize {
+        let mut table = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for i in 0..old.len() {
+            for j in 0..new.len() {
+                table[i + 1][j + 1] = if old[i] == new[j] { table[i][j] + 1 } else { table[i][j + 1].max(table[i + 1][j]) };
+            }
+        }
+        table[old.len()][new.len()]
+    }
+
+    #[test]
+    fn minimal_on_all_small_inputs() {
+        let inputs = ternary_inputs(5);
+
+        for old in &inputs {
+            for new in &inputs {
+                let (old_match, new_match) = matches::<_, usize>(old, new);
+                let pairs = new_match.iter().enumerate().filter(|(_, o)| **o != usize::NONE).collect::<Vec<_>>();
+
+                assert_eq!(pairs.len(), lcs_len(old, new), "{:?} -> {:?}", old, new);
+                for window in pairs.windows(2) {
+                    assert!(window[0].1 < window[1].1);
+                }
+                for (new_idx, old_idx) in pairs {
+                    assert_eq!(old[*old_idx], new[new_idx]);
+                    assert_eq!(old_match[*old_idx], new_idx);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_moves() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "b", "a"];
+        let changes = diff_myers(&old, &new);
+        assert_eq!(changes.len(), 4);
+        assert!(changes.iter().all(|c| matches!(c, Change::Insert(_) | Change::Delete(_))));
+    }
+}
@@ -0,0 +1,148 @@
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::hackel::{self, Builder, Change, IndexChange, Symbols};
+use crate::small::SMALL_THRESHOLD;
+
+/// Items hashed between two yields to the executor.
+pub const YIELD_INTERVAL: usize = 4096;
+
+/// Returns `Pending` once after waking itself, letting the executor run other tasks.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+async fn yield_every(idx: usize) {
+    if idx % YIELD_INTERVAL == YIELD_INTERVAL - 1 {
+        YieldNow(false).await;
+    }
+}
+
+/// Passes 1-4 with a yield every `YIELD_INTERVAL` items, driving the same
+/// builder `hackel::build` does.
+async fn build_yielding<T: Eq + Hash>(old: &[T], new: &[T]) -> Symbols<usize> {
+    let mut builder = Builder::with_capacity(old.len(), new.len());
+    for (idx, item) in new.iter().enumerate() {
+        builder.push_new(item);
+        yield_every(idx).await;
+    }
+    for (idx, item) in old.iter().enumerate() {
+        builder.push_old(item);
+        yield_every(idx).await;
+    }
+    builder.finish(&mut ()).expect("unobserved builds never expire")
+}
+
+/// Same as `diff`, yielding to the executor periodically while hashing so a
+/// large diff does not stall other tasks on the same thread. Works on any
+/// executor; emission is linear and runs without yielding.
+pub async fn diff_yielding<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    if old.len() <= SMALL_THRESHOLD && new.len() <= SMALL_THRESHOLD {
+        return hackel::diff(old, new);
+    }
+
+    let symbols = build_yielding(old, new).await;
+    let mut changes = Vec::new();
+    hackel::walk(&symbols, |step| hackel::emit(old, new, step, &mut |change| changes.push(change)));
+    changes
+}
+
+fn index_change<T>(change: &Change<'_, T>) -> IndexChange {
+    match change {
+        Change::Insert(insert) => IndexChange::Insert { index: insert.index },
+        Change::Delete(delete) => IndexChange::Delete { index: delete.index },
+        Change::Replace(replace) => IndexChange::Replace { index: replace.index },
+        Change::Move(mv) => IndexChange::Move { from_index: mv.from_index, to_index: mv.to_index },
+    }
+}
+
+/// Diffs off the event loop. Inside a tokio runtime the work runs on its
+/// blocking pool; anywhere else it falls back to `diff_yielding`.
+pub async fn diff_async<T>(old: Arc<[T]>, new: Arc<[T]>) -> Vec<IndexChange>
+where
+    T: Eq + Hash + Send + Sync + 'static,
+{
+    if tokio::runtime::Handle::try_current().is_ok() {
+        let task = tokio::task::spawn_blocking(move || hackel::diff(&old, &new).iter().map(index_change).collect());
+        return match task.await {
+            Ok(changes) => changes,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        };
+    }
+
+    diff_yielding(&old, &new).await.iter().map(index_change).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> (Vec<u32>, Vec<u32>) {
+        let old = (0..20_000).map(|i| (i * 7) % 5_000).collect();
+        let new = (0..18_000).map(|i| (i * 11) % 4_000).collect();
+        (old, new)
+    }
+
+    #[test]
+    fn yielding_matches_diff() {
+        let (old, new) = inputs();
+        let symbols = futures::executor::block_on(build_yielding(&old, &new));
+        let sequential = hackel::build::<_, usize>(&old, &new);
+        assert_eq!(symbols.old_match, sequential.old_match);
+        assert_eq!(symbols.new_match, sequential.new_match);
+        assert_eq!(symbols.table_entries, sequential.table_entries);
+    }
+
+    #[test]
+    fn yields_while_hashing() {
+        let (old, new) = inputs();
+        let mut future = Box::pin(diff_yielding(&old, &new));
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+
+        let mut pending = 0;
+        let changes = loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(changes) => break changes,
+                Poll::Pending => pending += 1,
+            }
+        };
+        assert_eq!(pending, old.len() / YIELD_INTERVAL + new.len() / YIELD_INTERVAL);
+        assert_eq!(changes.len(), hackel::diff(&old, &new).len());
+    }
+
+    #[test]
+    fn offloads_in_tokio() {
+        let (old, new) = inputs();
+        let expected = hackel::diff(&old, &new).iter().map(index_change).collect::<Vec<_>>();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let changes = runtime.block_on(diff_async(old.into(), new.into()));
+        assert_eq!(changes, expected);
+
+        let (old, new) = inputs();
+        assert_eq!(futures::executor::block_on(diff_async(old.into(), new.into())), expected);
+    }
+
+    #[test]
+    fn repeated_run_agrees_in_and_out_of_tokio() {
+        let old = (0..40).collect::<Arc<[u32]>>();
+        let new = (20..40).chain(0..40).collect::<Arc<[u32]>>();
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let inside = runtime.block_on(diff_async(old.clone(), new.clone()));
+        assert_eq!(futures::executor::block_on(diff_async(old, new)), inside);
+    }
+}
@@ -0,0 +1,57 @@
+//! Objective-C entry point taking `NSArray<NSString *>` directly. Strings
+//! are hashed and compared with `-hash` and `-isEqualToString:`, which read
+//! their UTF-16 storage in place, so the benchmark app no longer copies
+//! every string into a C string first.
+
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2_foundation::{NSArray, NSDictionary, NSNumber, NSString};
+
+use crate::hackel::{self, Change};
+
+/// A change as `@{@"kind": @"insert" | @"delete" | @"replace" | @"move",
+/// @"index": index, @"toIndex": toIndex}`, indexed as in `DeepDiffChange`.
+fn change_object(change: &Change<'_, &NSString>) -> Retained<NSDictionary<NSString, AnyObject>> {
+    let (kind, index, to_index) = match change {
+        Change::Insert(insert) => ("insert", insert.index, insert.index),
+        Change::Delete(delete) => ("delete", delete.index, delete.index),
+        Change::Replace(replace) => ("replace", replace.index, replace.index),
+        Change::Move(mv) => ("move", mv.from_index, mv.to_index),
+    };
+    let keys = [NSString::from_str("kind"), NSString::from_str("index"), NSString::from_str("toIndex")];
+    let kind = NSString::from_str(kind);
+    let (index, to_index) = (NSNumber::new_usize(index), NSNumber::new_usize(to_index));
+    let objects: [&AnyObject; 3] = [(*kind).as_ref(), (*index).as_ref(), (*to_index).as_ref()];
+    NSDictionary::from_slices(&[&*keys[0], &*keys[1], &*keys[2]], &objects)
+}
+
+/// Diffs two string arrays and returns the changes as an `NSArray` of
+/// dictionaries (see `change_object`), retained for the caller
+/// (`NS_RETURNS_RETAINED`).
+///
+/// # Safety
+///
+/// Neither array may be mutated during the call.
+#[no_mangle]
+pub unsafe extern "C" fn deep_diff_ns_array(old: &NSArray<NSString>, new: &NSArray<NSString>) -> *mut NSArray<NSDictionary<NSString, AnyObject>> {
+    let old = old.to_vec_unchecked();
+    let new = new.to_vec_unchecked();
+    let changes = hackel::diff(&old, &new).iter().map(change_object).collect::<Vec<_>>();
+    Retained::into_raw(NSArray::from_retained_slice(&changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_ns_strings() {
+        let strings = |items: &[&str]| NSArray::from_retained_slice(&items.iter().map(|item| NSString::from_str(item)).collect::<Vec<_>>());
+        let (old, new) = (strings(&["a", "b", "c"]), strings(&["c", "a"]));
+
+        let changes = unsafe { Retained::from_raw(deep_diff_ns_array(&old, &new)) }.unwrap();
+        assert_eq!(changes.count(), hackel::diff(&["a", "b", "c"], &["c", "a"]).len());
+        let kinds = changes.to_vec().iter().map(|change| change.objectForKey(&NSString::from_str("kind")).unwrap().downcast::<NSString>().unwrap().to_string()).collect::<Vec<_>>();
+        assert!(kinds.contains(&"delete".to_string()));
+    }
+}
@@ -0,0 +1,646 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::algorithm::{self, Algorithm};
+use crate::hackel::{self, Change, Delete, Index, Insert, Move, Replace, Step};
+
+/// How indices in the output relate to the arrays being changed.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Indexing {
+    /// Deletes use old indices and inserts/moves use new indices, as in a
+    /// `performBatchUpdates` block.
+    #[default]
+    Batch,
+    /// Every change is valid against the array produced by applying all the
+    /// previous ones: deletes run first in descending order, then the new
+    /// array is built left to right with inserts, moves and replaces.
+    Sequential,
+}
+
+/// How repeated items are paired between old and new.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Duplicates {
+    /// The k-th occurrence in new matches the k-th occurrence in old.
+    #[default]
+    Occurrence,
+    /// Repeated items only match next to a neighbour that already matched,
+    /// so separators and placeholders stay where they are instead of being
+    /// paired across the list. Only affects the Heckel backend.
+    Positional,
+}
+
+/// Order of the deletes in `Indexing::Batch` output.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum DeleteOrder {
+    /// Ascending old index, as pass 5 finds them.
+    #[default]
+    Ascending,
+    /// Descending old index. Together with the inserts, which are always in
+    /// ascending new index, removing and then inserting one by one turns old
+    /// into new whenever the output has no moves.
+    Descending,
+}
+
+/// What the Heckel symbol table is keyed by.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Hashing {
+    /// The items themselves, hashed with `Hash` and compared with `Eq`.
+    #[default]
+    Exact,
+    /// 128-bit hashes of the items, so the table neither compares nor
+    /// holds items; every resulting match is then confirmed with `Eq`, and
+    /// a colliding pair is reported as a delete and an insert. Only affects
+    /// the Heckel backend with `Duplicates::Occurrence`.
+    Hash128,
+    /// Matches items by a 64-bit hash alone and never calls `Eq`, so a
+    /// collision pairs two different items and leaves the older one in the
+    /// output. Worth it when equality is expensive, such as long strings;
+    /// collisions become likely past a few billion distinct items.
+    /// `Algorithm::Auto` still compares items to pick a backend.
+    HashOnly64,
+    /// As `HashOnly64` with 128-bit hashes.
+    HashOnly128,
+}
+
+impl Hashing {
+    /// `true` for the modes that treat equal hashes as equal items.
+    pub fn skips_eq(self) -> bool {
+        matches!(self, Hashing::HashOnly64 | Hashing::HashOnly128)
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DiffOptions {
+    pub algorithm: Algorithm,
+    pub indexing: Indexing,
+    pub duplicates: Duplicates,
+    pub delete_order: DeleteOrder,
+    /// Reports an item that both moved and changed as a single `Replace` at
+    /// its new index instead of a `Replace` plus a `Move`. Only applies to
+    /// `Indexing::Batch`; sequential output needs the move to stay valid.
+    /// Items only change under `diff_by_key_with_options`.
+    pub collapse_replaced_moves: bool,
+    /// Reports items that would move this many positions or more as a
+    /// delete and an insert, so UIs do not animate long-distance moves. Only
+    /// applies to `Indexing::Batch`.
+    pub move_distance_limit: Option<usize>,
+    pub hashing: Hashing,
+}
+
+/// Builder-style setters, so call sites only name the knobs they change:
+/// `DiffOptions::new().algorithm(Algorithm::Auto).move_distance_limit(20)`.
+impl DiffOptions {
+    pub fn new() -> Self {
+        DiffOptions::default()
+    }
+
+    pub fn algorithm(self, algorithm: Algorithm) -> Self {
+        DiffOptions { algorithm, ..self }
+    }
+
+    pub fn indexing(self, indexing: Indexing) -> Self {
+        DiffOptions { indexing, ..self }
+    }
+
+    pub fn duplicates(self, duplicates: Duplicates) -> Self {
+        DiffOptions { duplicates, ..self }
+    }
+
+    pub fn delete_order(self, delete_order: DeleteOrder) -> Self {
+        DiffOptions { delete_order, ..self }
+    }
+
+    pub fn collapse_replaced_moves(self, collapse_replaced_moves: bool) -> Self {
+        DiffOptions { collapse_replaced_moves, ..self }
+    }
+
+    pub fn move_distance_limit(self, limit: usize) -> Self {
+        DiffOptions { move_distance_limit: Some(limit), ..self }
+    }
+
+    pub fn hashing(self, hashing: Hashing) -> Self {
+        DiffOptions { hashing, ..self }
+    }
+}
+
+pub fn diff_with_options<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], options: &DiffOptions) -> Vec<Change<'a, T>> {
+    let (old_match, new_match) = matches(old, new, options);
+    emit(old, new, old_match, new_match, options, options.hashing.skips_eq())
+}
+
+/// Same as `diff_with_options`, matching items by `key` as `diff_by_key`
+/// does; the algorithm, duplicate and hashing options apply to the keys. An
+/// item whose key matched but whose content changed is a `Replace`, which
+/// `collapse_replaced_moves` folds its move into.
+pub fn diff_by_key_with_options<'a, T, K, F>(old: &'a [T], new: &'a [T], mut key: F, options: &DiffOptions) -> Vec<Change<'a, T>>
+where
+    T: PartialEq,
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    let old_keys = old.iter().map(&mut key).collect::<Vec<_>>();
+    let new_keys = new.iter().map(&mut key).collect::<Vec<_>>();
+    let (old_match, new_match) = matches(&old_keys, &new_keys, options);
+    emit(old, new, old_match, new_match, options, false)
+}
+
+fn matches<T: Eq + Hash>(old: &[T], new: &[T], options: &DiffOptions) -> (Vec<usize>, Vec<usize>) {
+    match (options.duplicates, options.algorithm.select(old, new)) {
+        (Duplicates::Positional, Algorithm::Heckel) => {
+            let symbols = hackel::build_anchored(old, new);
+            (symbols.old_match, symbols.new_match)
+        },
+        (Duplicates::Occurrence, Algorithm::Heckel) if options.hashing != Hashing::Exact => hashed_matches(old, new, options.hashing),
+        (_, algorithm) => algorithm::matches(old, new, algorithm),
+    }
+}
+
+/// Emits the changes for the matches; `skip_eq` treats matched items as
+/// equal instead of comparing them for replaces.
+fn emit<'a, T: PartialEq>(old: &'a [T], new: &'a [T], mut old_match: Vec<usize>, mut new_match: Vec<usize>, options: &DiffOptions, skip_eq: bool) -> Vec<Change<'a, T>> {
+    if let (Some(limit), Indexing::Batch) = (options.move_distance_limit, options.indexing) {
+        limit_moves(&mut old_match, &mut new_match, limit);
+    }
+    let mut changes = Vec::new();
+
+    match options.indexing {
+        Indexing::Batch => {
+            emit_batch(old, new, &old_match, &new_match, options, skip_eq, |change| changes.push(change));
+            if options.delete_order == DeleteOrder::Descending {
+                let deletes = changes.iter().take_while(|change| matches!(change, Change::Delete(_))).count();
+                changes[..deletes].reverse();
+            }
+        },
+        Indexing::Sequential => emit_sequential(old, new, &old_match, &new_match, skip_eq, |change| changes.push(change)),
+    }
+
+    changes
+}
+
+fn hash64<T: Hash>(item: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn hash128<T: Hash>(item: &T) -> u128 {
+    let mut high = DefaultHasher::new();
+    // Seeding one half keeps the two halves independent.
+    high.write_u64(0x9e37_79b9_7f4a_7c15);
+    item.hash(&mut high);
+    u128::from(high.finish()) << 64 | u128::from(hash64(item))
+}
+
+/// Heckel matches keyed by item hashes. Unless `hashing` skips `Eq`,
+/// colliding pairs are unmatched.
+fn hashed_matches<T: Eq + Hash>(old: &[T], new: &[T], hashing: Hashing) -> (Vec<usize>, Vec<usize>) {
+    let symbols = match hashing {
+        Hashing::HashOnly64 => hackel::build::<_, usize>(old.iter().map(hash64), new.iter().map(hash64)),
+        _ => hackel::build::<_, usize>(old.iter().map(hash128), new.iter().map(hash128)),
+    };
+    let (mut old_match, mut new_match) = (symbols.old_match, symbols.new_match);
+    if hashing.skips_eq() {
+        return (old_match, new_match);
+    }
+    for (new_idx, old_idx) in new_match.iter_mut().enumerate() {
+        if *old_idx != usize::NONE && old[*old_idx] != new[new_idx] {
+            old_match[*old_idx] = usize::NONE;
+            *old_idx = usize::NONE;
+        }
+    }
+    (old_match, new_match)
+}
+
+fn emit_batch<'a, T: PartialEq, F: FnMut(Change<'a, T>)>(old: &'a [T], new: &'a [T], old_match: &[usize], new_match: &[usize], options: &DiffOptions, skip_eq: bool, mut f: F) {
+    let mut delete_offsets = vec![0; old.len()];
+    hackel::walk_matches(old_match, new_match, &mut delete_offsets, |mut step| {
+        match &mut step {
+            // Matched items count as equal, so there is nothing to replace.
+            Step::Match { old_idx, new_idx, moved } if skip_eq => {
+                if *moved {
+                    f(Change::Move(Move { item: &new[*new_idx], from_index: *old_idx, to_index: *new_idx }));
+                }
+                return;
+            },
+            Step::Match { old_idx, new_idx, moved } => *moved &= !(options.collapse_replaced_moves && old[*old_idx] != new[*new_idx]),
+            Step::Delete(_) | Step::Insert(_) => {},
+        }
+        hackel::emit(old, new, step, &mut f)
+    }, &mut ());
+}
+
+/// Fenwick tree counting marked old items: the surviving items not placed
+/// yet in `emit_sequential`, the deleted ones in `limit_moves`.
+struct Unplaced(Vec<usize>);
+
+impl Unplaced {
+    fn new(len: usize) -> Self {
+        Unplaced(vec![0; len + 1])
+    }
+
+    fn add(&mut self, index: usize, delta: isize) {
+        let mut i = index + 1;
+        while i < self.0.len() {
+            self.0[i] = (self.0[i] as isize + delta) as usize;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Number of unplaced items before `index`.
+    fn rank(&self, index: usize) -> usize {
+        let mut i = index;
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.0[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+}
+
+/// Unmatches items reported as moving `limit` or more positions, in new
+/// order. Unmatching one shifts the pass 6 positions of others, so this
+/// repeats until a pass finds nothing; usually one or two do.
+fn limit_moves(old_match: &mut [usize], new_match: &mut [usize], limit: usize) {
+    loop {
+        let mut deletes = Unplaced::new(old_match.len());
+        for (old_idx, matched) in old_match.iter().enumerate() {
+            if *matched == usize::NONE {
+                deletes.add(old_idx, 1);
+            }
+        }
+
+        let mut inserts = 0;
+        let mut changed = false;
+        for (new_idx, matched) in new_match.iter_mut().enumerate() {
+            let old_idx = *matched;
+            if old_idx == usize::NONE {
+                inserts += 1;
+                continue;
+            }
+            let position = old_idx - deletes.rank(old_idx) + inserts;
+            if position != new_idx && old_idx.abs_diff(new_idx) >= limit {
+                old_match[old_idx] = usize::NONE;
+                *matched = usize::NONE;
+                deletes.add(old_idx, 1);
+                inserts += 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            return;
+        }
+    }
+}
+
+/// While building position `new_idx`, the array holds the finished prefix
+/// followed by the unplaced survivors in old order, so an item's current
+/// position is `new_idx` plus its rank among those survivors.
+fn emit_sequential<'a, T: PartialEq, F: FnMut(Change<'a, T>)>(old: &'a [T], new: &'a [T], old_match: &[usize], new_match: &[usize], skip_eq: bool, mut f: F) {
+    let mut unplaced = Unplaced::new(old.len());
+
+    for index in (0..old.len()).rev() {
+        if old_match[index] == usize::NONE {
+            f(Change::Delete(Delete { item: &old[index], index }));
+        } else {
+            unplaced.add(index, 1);
+        }
+    }
+
+    for (new_idx, old_idx) in new_match.iter().enumerate() {
+        if *old_idx == usize::NONE {
+            f(Change::Insert(Insert { item: &new[new_idx], index: new_idx }));
+            continue;
+        }
+
+        let from_index = new_idx + unplaced.rank(*old_idx);
+        unplaced.add(*old_idx, -1);
+        if from_index != new_idx {
+            f(Change::Move(Move { item: &new[new_idx], from_index, to_index: new_idx }));
+        }
+        if !skip_eq && old[*old_idx] != new[new_idx] {
+            f(Change::Replace(Replace { old_item: &old[*old_idx], new_item: &new[new_idx], index: new_idx }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_to_vec;
+    use crate::testing::ternary_inputs;
+
+    impl<'a, T> Change<'a, T> {
+        fn delete_index(&self) -> Option<usize> {
+            match self {
+                Change::Delete(d) => Some(d.index),
+                _ => None,
+            }
+        }
+
+        fn insert_index(&self) -> Option<usize> {
+            match self {
+                Change::Insert(i) => Some(i.index),
+                _ => None,
+            }
+        }
+    }
+
+    fn apply_sequentially<T: Clone>(old: &[T], changes: &[Change<'_, T>]) -> Vec<T> {
+        let mut items = old.to_vec();
+        for change in changes {
+            match change {
+                Change::Delete(d) => {
+                    items.remove(d.index);
+                },
+                Change::Insert(i) => items.insert(i.index, i.item.clone()),
+                Change::Move(m) => {
+                    let item = items.remove(m.from_index);
+                    items.insert(m.to_index, item);
+                },
+                Change::Replace(r) => items[r.index] = r.new_item.clone(),
+            }
+        }
+        items
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_options() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let old = b"abcabcabc".to_vec();
+        let new = b"cbacbaxyz".to_vec();
+        let bytes = (0..=255u8).collect::<Vec<_>>();
+        let mut u = Unstructured::new(&bytes);
+        while let Ok(options) = DiffOptions::arbitrary(&mut u) {
+            if u.is_empty() {
+                break;
+            }
+            let changes = diff_with_options(&old, &new, &options);
+            if options.indexing == Indexing::Sequential {
+                assert_eq!(apply_sequentially(&old, &changes), new, "{:?}", options);
+            }
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let built = DiffOptions::new().algorithm(Algorithm::Myers).indexing(Indexing::Sequential).move_distance_limit(3).hashing(Hashing::Hash128);
+        assert_eq!(built, DiffOptions {
+            algorithm: Algorithm::Myers,
+            indexing: Indexing::Sequential,
+            move_distance_limit: Some(3),
+            hashing: Hashing::Hash128,
+            ..DiffOptions::default()
+        });
+        assert_eq!(DiffOptions::new(), DiffOptions::default());
+    }
+
+    #[test]
+    fn batch_is_default() {
+        let old = vec![1, 2, 3, 4, 5];
+        let new = vec![1, 5, 2, 3, 4];
+        let changes = diff_with_options(&old, &new, &DiffOptions::default());
+        assert_eq!(changes.len(), hackel::diff(&old, &new).len());
+    }
+
+    #[test]
+    fn sequential_reproduces_new() {
+        let options = DiffOptions { indexing: Indexing::Sequential, ..DiffOptions::default() };
+        let cases: Vec<(Vec<u8>, Vec<u8>)> = vec![
+            (vec![1, 2, 3, 4, 5], vec![1, 5, 2, 3, 4]),
+            (vec![3, 2, 1], vec![1, 4, 3]),
+            (vec![1, 3, 0, 2], vec![0, 2, 3, 1]),
+            (b"sitting".to_vec(), b"kitten".to_vec()),
+            (vec![1, 1, 2, 2, 1], vec![2, 1, 1, 2, 3, 1]),
+        ];
+
+        for (old, new) in cases {
+            for algorithm in [Algorithm::Heckel, Algorithm::Myers, Algorithm::Small] {
+                let options = DiffOptions { algorithm, ..options.clone() };
+                let changes = diff_with_options(&old, &new, &options);
+                assert_eq!(apply_sequentially(&old, &changes), new, "{:?} -> {:?} with {:?}", old, new, algorithm);
+            }
+        }
+    }
+
+    #[test]
+    fn collapse_replaced_moves() {
+        let old = [(1, "a"), (2, "b"), (3, "c")];
+        let new = [(3, "C"), (1, "a"), (2, "b")];
+
+        let changes = diff_by_key_with_options(&old, &new, |row| row.0, &DiffOptions::default());
+        assert!(matches!(changes.as_slice(), [Change::Replace(_), Change::Move(_), ..]));
+
+        let options = DiffOptions::new().collapse_replaced_moves(true);
+        let changes = diff_by_key_with_options(&old, &new, |row| row.0, &options);
+        match changes.as_slice() {
+            [Change::Replace(r), Change::Move(a), Change::Move(b)] => {
+                assert_eq!((r.old_item, r.new_item, r.index), (&(3, "c"), &(3, "C"), 0));
+                assert_eq!((a.to_index, b.to_index), (1, 2));
+            },
+            _ => panic!("expected a replace and two moves"),
+        }
+    }
+
+    #[test]
+    fn move_distance_limit() {
+        let old = vec!["a", "b", "c", "d", "e", "f"];
+        let new = vec!["d", "e", "f", "a", "b", "c"];
+
+        let options = DiffOptions { move_distance_limit: Some(3), ..DiffOptions::default() };
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(changes.iter().map(|change| (change.delete_index(), change.insert_index())).collect::<Vec<_>>(), vec![
+            (Some(3), None),
+            (Some(4), None),
+            (Some(5), None),
+            (None, Some(0)),
+            (None, Some(1)),
+            (None, Some(2)),
+        ]);
+        let mut model = old.clone();
+        apply_to_vec(&mut model, &changes);
+        assert_eq!(model, new);
+
+        let options = DiffOptions { move_distance_limit: Some(4), ..DiffOptions::default() };
+        assert_eq!(diff_with_options(&old, &new, &options).len(), hackel::diff(&old, &new).len());
+    }
+
+    #[test]
+    fn move_distance_limit_holds() {
+        let old = (0..40).collect::<Vec<u32>>();
+        let new = (0..40).map(|n| (n * 7) % 40).collect::<Vec<u32>>();
+        for limit in 0..10 {
+            let options = DiffOptions { move_distance_limit: Some(limit), ..DiffOptions::default() };
+            let changes = diff_with_options(&old, &new, &options);
+            assert!(changes.iter().all(|change| !matches!(change, Change::Move(m) if m.from_index.abs_diff(m.to_index) >= limit)));
+            let mut model = old.clone();
+            apply_to_vec(&mut model, &changes);
+            assert_eq!(model, new);
+        }
+    }
+
+    #[test]
+    fn hash128_matches_exact() {
+        let options = DiffOptions { hashing: Hashing::Hash128, ..DiffOptions::default() };
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["a", "b", "c", "d"], &["d", "x", "b", "a"]),
+            (&["a", "a", "b", "a"], &["b", "a", "a", "c", "a"]),
+            (&[], &["a"]),
+        ];
+        for (old, new) in cases {
+            let changes = diff_with_options(old, new, &options);
+            assert_eq!(changes.len(), hackel::diff(old, new).len());
+            let mut model = old.to_vec();
+            apply_to_vec(&mut model, &changes);
+            assert_eq!(&model, new);
+        }
+    }
+
+    #[test]
+    fn hash128_collisions_are_verified() {
+        // Hashes only the id, so rows with one id collide.
+        #[derive(PartialEq, Eq, Clone, Debug)]
+        struct Row(u32, &'static str);
+
+        impl Hash for Row {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        let old = vec![Row(1, "a"), Row(2, "b")];
+        let new = vec![Row(1, "a"), Row(2, "B")];
+        let options = DiffOptions { hashing: Hashing::Hash128, ..DiffOptions::default() };
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(changes.iter().map(|change| (change.delete_index(), change.insert_index())).collect::<Vec<_>>(), vec![(Some(1), None), (None, Some(1))]);
+    }
+
+    #[test]
+    fn hash_only_skips_eq() {
+        // Equal ids hash alike, and `Eq` must never run.
+        #[derive(Clone, Debug)]
+        struct Blob(u32);
+
+        impl PartialEq for Blob {
+            fn eq(&self, _: &Self) -> bool {
+                panic!("compared with Eq");
+            }
+        }
+        impl Eq for Blob {}
+        impl Hash for Blob {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        let old = (0..50).map(Blob).collect::<Vec<_>>();
+        let new = (0..50).rev().filter(|n| n % 7 != 0).chain(100..103).map(Blob).collect::<Vec<_>>();
+        for hashing in [Hashing::HashOnly64, Hashing::HashOnly128] {
+            for indexing in [Indexing::Batch, Indexing::Sequential] {
+                let options = DiffOptions { algorithm: Algorithm::Heckel, hashing, indexing, ..DiffOptions::default() };
+                let changes = diff_with_options(&old, &new, &options);
+                let mut model = old.clone();
+                match indexing {
+                    Indexing::Batch => apply_to_vec(&mut model, &changes),
+                    Indexing::Sequential => model = apply_sequentially(&old, &changes),
+                }
+                assert_eq!(model.iter().map(|blob| blob.0).collect::<Vec<_>>(), new.iter().map(|blob| blob.0).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn positional_duplicates() {
+        let options = DiffOptions { algorithm: Algorithm::Heckel, duplicates: Duplicates::Positional, indexing: Indexing::Sequential, ..DiffOptions::default() };
+        let inputs = ternary_inputs(5);
+
+        for old in &inputs {
+            for new in &inputs {
+                let changes = diff_with_options(old, new, &options);
+                assert_eq!(apply_sequentially(old, &changes), *new, "{:?} -> {:?}", old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn all_duplicates() {
+        let options = DiffOptions { duplicates: Duplicates::Positional, ..DiffOptions::default() };
+
+        let old = vec!["-"; 40];
+        let new = vec!["-"; 40];
+        assert!(diff_with_options(&old, &new, &options).is_empty());
+
+        let new = vec!["-"; 35];
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(changes.len(), 5);
+        assert!(changes.iter().all(|c| matches!(c, Change::Delete(d) if d.index >= 35)));
+
+        let new = vec!["+"; 40];
+        assert_eq!(diff_with_options(&old, &new, &options).len(), 80);
+    }
+
+    #[test]
+    fn positional_separators_follow_rows() {
+        // Each row is an item followed by its separator; swapping rows moves
+        // the separators along instead of pairing them by occurrence.
+        let old = ["a", "-", "b", "-"];
+        let new = ["b", "-", "a", "-"];
+        let options = DiffOptions { algorithm: Algorithm::Heckel, duplicates: Duplicates::Positional, ..DiffOptions::default() };
+
+        let moves = diff_with_options(&old, &new, &options)
+            .iter()
+            .filter_map(|c| match c {
+                Change::Move(m) => Some((*m.item, m.from_index, m.to_index)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(moves, vec![("b", 2, 0), ("-", 3, 1), ("a", 0, 2), ("-", 1, 3)]);
+    }
+
+    #[test]
+    fn descending_deletes_apply_naively() {
+        let old = b"the quick brown fox".to_vec();
+        let new = b"a quick red fox jumps".to_vec();
+        let options = DiffOptions { algorithm: Algorithm::Myers, delete_order: DeleteOrder::Descending, ..DiffOptions::default() };
+        let changes = diff_with_options(&old, &new, &options);
+
+        let deletes = changes.iter().filter_map(|c| c.delete_index()).collect::<Vec<_>>();
+        let inserts = changes.iter().filter_map(|c| c.insert_index()).collect::<Vec<_>>();
+        assert!(deletes.windows(2).all(|w| w[0] > w[1]));
+        assert!(inserts.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(apply_sequentially(&old, &changes), new);
+    }
+
+    #[test]
+    fn descending_deletes_with_moves() {
+        let old = (0..100).collect::<Vec<_>>();
+        let new = old.iter().rev().step_by(3).copied().collect::<Vec<_>>();
+        let options = DiffOptions { delete_order: DeleteOrder::Descending, ..DiffOptions::default() };
+        let changes = diff_with_options(&old, &new, &options);
+
+        let deletes = changes.iter().filter_map(|c| c.delete_index()).collect::<Vec<_>>();
+        assert_eq!(deletes.len(), 100 - new.len());
+        assert!(deletes.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn sequential_single_move() {
+        let old = vec!["a", "b", "c", "d"];
+        let new = vec!["d", "a", "b", "c"];
+        let options = DiffOptions { indexing: Indexing::Sequential, ..DiffOptions::default() };
+        let changes = diff_with_options(&old, &new, &options);
+
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            Change::Move(m) => assert_eq!((m.from_index, m.to_index), (3, 0)),
+            _ => panic!("expected a move"),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::cmp::Ordering;
+
+use crate::hackel::{self, Change, Delete, Index, Insert};
+
+/// Diff for `T: Ord` that never hashes: both sides are sorted by item and
+/// merged, pairing the k-th occurrence in new with the k-th in old exactly as
+/// `diff` does. O((n + m) log(n + m)) comparisons; pays off over the symbol
+/// table when comparing is cheap and hashing is not.
+pub fn diff_ord<'a, T: Ord>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    // Stable sorts keep equal items in index order.
+    let mut old_order = (0..old.len()).collect::<Vec<_>>();
+    old_order.sort_by(|a, b| old[*a].cmp(&old[*b]));
+    let mut new_order = (0..new.len()).collect::<Vec<_>>();
+    new_order.sort_by(|a, b| new[*a].cmp(&new[*b]));
+
+    let mut old_match = vec![usize::NONE; old.len()];
+    let mut new_match = vec![usize::NONE; new.len()];
+    let (mut old_iter, mut new_iter) = (old_order.into_iter().peekable(), new_order.into_iter().peekable());
+    while let (Some(old_idx), Some(new_idx)) = (old_iter.peek().copied(), new_iter.peek().copied()) {
+        match old[old_idx].cmp(&new[new_idx]) {
+            Ordering::Less => {
+                old_iter.next();
+            },
+            Ordering::Greater => {
+                new_iter.next();
+            },
+            Ordering::Equal => {
+                old_match[old_idx] = new_idx;
+                new_match[new_idx] = old_idx;
+                old_iter.next();
+                new_iter.next();
+            },
+        }
+    }
+
+    hackel::changes_from_matches(old, new, &old_match, &new_match)
+}
+
+/// Diff for inputs that are both sorted ascending without duplicates, such
+/// as sorted id lists: a single merge pass with no symbol table.
+/// Order is kept, so there are only deletes and inserts, in the order `diff`
+/// emits them. The output is unspecified for unsorted input.
+pub fn diff_sorted<'a, T: Ord>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    let (mut old_idx, mut new_idx) = (0, 0);
+    let mut changes = Vec::new();
+    let mut inserts = Vec::new();
+    while old_idx < old.len() || new_idx < new.len() {
+        let order = match (old.get(old_idx), new.get(new_idx)) {
+            (Some(old_item), Some(new_item)) => old_item.cmp(new_item),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => {
+                changes.push(Change::Delete(Delete { item: &old[old_idx], index: old_idx }));
+                old_idx += 1;
+            },
+            Ordering::Greater => {
+                inserts.push(Change::Insert(Insert { item: &new[new_idx], index: new_idx }));
+                new_idx += 1;
+            },
+            Ordering::Equal => {
+                old_idx += 1;
+                new_idx += 1;
+            },
+        }
+    }
+    changes.append(&mut inserts);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{describe, ternary_inputs};
+
+    #[test]
+    fn agrees_with_diff() {
+        let inputs = ternary_inputs(4);
+        for old in &inputs {
+            for new in &inputs {
+                assert_eq!(describe(&diff_ord(old, new)), describe(&hackel::diff(old, new)), "{:?} -> {:?}", old, new);
+            }
+        }
+
+        let old = (0..200u8).map(|i| i.wrapping_mul(37) % 50).collect::<Vec<_>>();
+        let new = (0..180u8).map(|i| i.wrapping_mul(11) % 60).collect::<Vec<_>>();
+        assert_eq!(describe(&diff_ord(&old, &new)), describe(&hackel::diff(&old, &new)));
+    }
+
+    #[test]
+    fn sorted() {
+        let old = vec![1u8, 3, 4, 7, 9];
+        let new = vec![0u8, 3, 5, 7, 9, 12];
+        assert_eq!(describe(&diff_sorted(&old, &new)), vec![('d', 0, 1), ('d', 2, 4), ('i', 0, 0), ('i', 2, 5), ('i', 5, 12)]);
+        assert_eq!(describe(&diff_sorted(&old, &new)), describe(&hackel::diff(&old, &new)));
+        assert!(diff_sorted(&old, &old).is_empty());
+        assert_eq!(describe(&diff_sorted(&[], &old[..2])), vec![('i', 0, 1), ('i', 1, 3)]);
+    }
+}
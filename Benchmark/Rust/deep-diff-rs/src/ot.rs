@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use crate::changeset::Changeset;
+use crate::error::DiffError;
+use crate::hackel::{Change, Delete, Insert};
+use crate::merge;
+
+/// Operational transform component, applied left to right over the old
+/// sequence.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Operation<'a, T> {
+    /// Keeps the next items of the old sequence.
+    Retain(usize),
+    /// Inserts items at the current position.
+    Insert(Vec<&'a T>),
+    /// Drops the next items of the old sequence.
+    Delete(usize),
+}
+
+/// Appends `op`, merging it into the last operation of the same kind.
+fn push<'a, T>(ops: &mut Vec<Operation<'a, T>>, op: Operation<'a, T>) {
+    match (ops.last_mut(), op) {
+        (_, Operation::Retain(0)) | (_, Operation::Delete(0)) => {},
+        (Some(Operation::Retain(last)), Operation::Retain(n)) => *last += n,
+        (Some(Operation::Delete(last)), Operation::Delete(n)) => *last += n,
+        (Some(Operation::Insert(last)), Operation::Insert(items)) => last.extend(items),
+        (_, op) => ops.push(op),
+    }
+}
+
+/// Converts a changeset over `old_len` items into retain/insert/delete
+/// operations. OT has no moves, so moved items become a delete and an
+/// insert; replaced items too. Items that `diff` only reports as moved
+/// because others shifted around them are retained.
+///
+/// # Panics
+///
+/// Panics if `changes` do not describe a batch update of `old_len` items.
+pub fn to_operations<'a, T>(old_len: usize, changes: &[Change<'a, T>]) -> Vec<Operation<'a, T>> {
+    let mut items = HashMap::new();
+    let side = merge::replay(old_len, changes, &mut items);
+
+    let mut ops = Vec::new();
+    let mut old_idx = 0;
+    for entry in &side.entries {
+        match (entry.base, entry.item, entry.moved) {
+            (Some(base), None, false) => {
+                push(&mut ops, Operation::Delete(base - old_idx));
+                push(&mut ops, Operation::Retain(1));
+                old_idx = base + 1;
+            },
+            (Some(base), item, moved) => {
+                if !moved {
+                    push(&mut ops, Operation::Delete(base + 1 - old_idx));
+                    old_idx = base + 1;
+                }
+                push(&mut ops, Operation::Insert(vec![item.unwrap_or(items[&base])]));
+            },
+            (None, item, _) => push(&mut ops, Operation::Insert(vec![item.expect("inserts carry an item")])),
+        }
+    }
+    push(&mut ops, Operation::Delete(old_len - old_idx));
+    ops
+}
+
+/// Converts operations over `old` back into a changeset of deletes and
+/// inserts, with indices as `diff` reports them.
+pub fn from_operations<'a, T>(old: &'a [T], ops: &[Operation<'a, T>]) -> Result<Changeset<'a, T>, DiffError> {
+    let mut deletes = Vec::new();
+    let mut inserts = Vec::new();
+    let (mut old_idx, mut new_idx) = (0, 0);
+    for op in ops {
+        match op {
+            Operation::Retain(n) => {
+                old_idx += n;
+                new_idx += n;
+            },
+            Operation::Delete(n) => {
+                let items = old.get(old_idx..old_idx + n).ok_or(DiffError::OperationsMismatch { old_len: old.len(), consumed: old_idx + n })?;
+                deletes.extend(items.iter().enumerate().map(|(offset, item)| Change::Delete(Delete { item, index: old_idx + offset })));
+                old_idx += n;
+            },
+            Operation::Insert(items) => {
+                inserts.extend(items.iter().map(|item| {
+                    new_idx += 1;
+                    Change::Insert(Insert { item: *item, index: new_idx - 1 })
+                }));
+            },
+        }
+    }
+
+    if old_idx != old.len() {
+        return Err(DiffError::OperationsMismatch { old_len: old.len(), consumed: old_idx });
+    }
+    deletes.extend(inserts);
+    Ok(Changeset::from(deletes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_to_vec;
+    use crate::hackel;
+
+    #[test]
+    fn text_edit() {
+        let old = "the quick fox".chars().collect::<Vec<_>>();
+        let new = "the slow fox!".chars().collect::<Vec<_>>();
+        let ops = to_operations(old.len(), &hackel::diff(&old, &new));
+
+        let mut rebuilt = Vec::new();
+        let mut old_idx = 0;
+        for op in &ops {
+            match op {
+                Operation::Retain(n) => {
+                    rebuilt.extend_from_slice(&old[old_idx..old_idx + n]);
+                    old_idx += n;
+                },
+                Operation::Delete(n) => old_idx += n,
+                Operation::Insert(items) => rebuilt.extend(items.iter().copied()),
+            }
+        }
+        assert_eq!(rebuilt, new);
+        assert!(ops.windows(2).all(|pair| std::mem::discriminant(&pair[0]) != std::mem::discriminant(&pair[1])));
+    }
+
+    #[test]
+    fn shifted_items_are_retained() {
+        let old = vec![1, 2, 3, 4];
+        let new = vec![4, 1, 2, 3];
+        let ops = to_operations(old.len(), &hackel::diff(&old, &new));
+        assert_eq!(ops, vec![Operation::Insert(vec![&4]), Operation::Retain(3), Operation::Delete(1)]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let old = vec!["a", "b", "c", "d", "e"];
+        let new = vec!["b", "x", "d", "a", "y"];
+        let ops = to_operations(old.len(), &hackel::diff(&old, &new));
+        let changes = from_operations(&old, &ops).ok().unwrap();
+
+        let mut model = old.clone();
+        apply_to_vec(&mut model, &changes);
+        assert_eq!(model, new);
+    }
+
+    #[test]
+    fn mismatched_length() {
+        let old = vec![1, 2, 3];
+        assert_eq!(from_operations(&old, &[Operation::Retain(2)]).err(), Some(DiffError::OperationsMismatch { old_len: 3, consumed: 2 }));
+        assert_eq!(from_operations(&old, &[Operation::Retain(2), Operation::Delete(2)]).err(), Some(DiffError::OperationsMismatch { old_len: 3, consumed: 4 }));
+    }
+}
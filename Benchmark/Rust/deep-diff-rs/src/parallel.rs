@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::thread;
+
+use crate::hackel::{self, Change, Counter, Index, Symbols};
+
+/// Below this many items in total, spawning a thread costs more than it saves.
+pub const PARALLEL_THRESHOLD: usize = 1 << 14;
+
+/// New side of the table: entry per distinct new item, entry id per position.
+struct NewSide<'a, T> {
+    ids: HashMap<&'a T, usize>,
+    counter: Vec<Counter>,
+    entry: Vec<usize>,
+}
+
+/// Old side of the table: occurrence queue per distinct old item.
+struct OldSide<'a, T> {
+    ids: HashMap<&'a T, usize>,
+    counter: Vec<Counter>,
+    head: Vec<usize>,
+    next: Vec<usize>,
+}
+
+fn new_side<T: Eq + Hash>(new: &[T]) -> NewSide<'_, T> {
+    let mut side = NewSide { ids: HashMap::new(), counter: Vec::new(), entry: Vec::with_capacity(new.len()) };
+
+    for item in new {
+        let counter = &mut side.counter;
+        let entry = *side.ids.entry(item).or_insert_with(|| {
+            counter.push(Counter::Zero);
+            counter.len() - 1
+        });
+        side.counter[entry] = side.counter[entry].increment();
+        side.entry.push(entry);
+    }
+
+    side
+}
+
+fn old_side<T: Eq + Hash>(old: &[T]) -> OldSide<'_, T> {
+    let mut side = OldSide { ids: HashMap::new(), counter: Vec::new(), head: Vec::new(), next: vec![usize::NONE; old.len()] };
+    let mut tail = Vec::new();
+
+    for (idx, item) in old.iter().enumerate() {
+        let counter = &mut side.counter;
+        let entry = *side.ids.entry(item).or_insert_with(|| {
+            counter.push(Counter::Zero);
+            counter.len() - 1
+        });
+        if entry == side.head.len() {
+            side.head.push(idx);
+            tail.push(idx);
+        } else {
+            side.next[tail[entry]] = idx;
+            tail[entry] = idx;
+        }
+        side.counter[entry] = side.counter[entry].increment();
+    }
+
+    side
+}
+
+/// Runs passes 1 and 2 on separate threads, then merges the two sides and
+/// matches exactly as the sequential build does.
+pub(crate) fn build_parallel<T: Eq + Hash + Sync>(old: &[T], new: &[T]) -> Symbols<usize> {
+    let (new_side, mut old_side) = thread::scope(|scope| {
+        let old_handle = scope.spawn(|| old_side(old));
+        let new_side = new_side(new);
+        (new_side, old_handle.join().expect("old side of the table panicked"))
+    });
+
+    let mut new_to_old = vec![usize::NONE; new_side.counter.len()];
+    for (item, entry) in &new_side.ids {
+        if let Some(old_entry) = old_side.ids.get(item) {
+            new_to_old[*entry] = *old_entry;
+        }
+    }
+
+    let mut symbols = Symbols {
+        old_match: vec![usize::NONE; old.len()],
+        new_match: vec![usize::NONE; new.len()],
+        table_entries: new_side.counter.len() + old_side.counter.len() - new_to_old.iter().filter(|e| **e != usize::NONE).count(),
+    };
+
+    for (new_idx, entry) in new_side.entry.iter().enumerate() {
+        let old_entry = new_to_old[*entry];
+        if old_entry == usize::NONE {
+            continue;
+        }
+        let old_idx = old_side.head[old_entry];
+        if old_idx == usize::NONE {
+            continue;
+        }
+        old_side.head[old_entry] = old_side.next[old_idx];
+
+        let new_counter = new_side.counter[*entry];
+        let old_counter = old_side.counter[old_entry];
+        let is_observation1 = new_counter == Counter::One && old_counter == Counter::One;
+        let is_observation2 = new_counter != Counter::Zero && old_counter != Counter::Zero && symbols.old_match[old_idx] == usize::NONE;
+
+        if is_observation1 || is_observation2 {
+            symbols.new_match[new_idx] = old_idx;
+            symbols.old_match[old_idx] = new_idx;
+        }
+    }
+
+    symbols
+}
+
+/// Same as `diff`, hashing the old and new sides on two threads for large inputs.
+pub fn diff_parallel<'a, T: Eq + Hash + Sync>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    if old.len() + new.len() < PARALLEL_THRESHOLD {
+        return hackel::diff(old, new);
+    }
+
+    let symbols = build_parallel(old, new);
+    let mut changes = Vec::new();
+    hackel::walk(&symbols, |step| hackel::emit(old, new, step, &mut |change| changes.push(change)));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sequential_build() {
+        let old = (0..5000).map(|i| (i * 7) % 1300).collect::<Vec<_>>();
+        let new = (0..4000).map(|i| (i * 11) % 1700).collect::<Vec<_>>();
+
+        let sequential = hackel::build::<_, usize>(&old, &new);
+        let parallel = build_parallel(&old, &new);
+        assert_eq!(parallel.old_match, sequential.old_match);
+        assert_eq!(parallel.new_match, sequential.new_match);
+        assert_eq!(parallel.table_entries, sequential.table_entries);
+    }
+
+    #[test]
+    fn large_input() {
+        let old = (0..PARALLEL_THRESHOLD).collect::<Vec<_>>();
+        let new = old.iter().rev().skip(10).copied().collect::<Vec<_>>();
+        assert_eq!(diff_parallel(&old, &new).len(), hackel::diff(&old, &new).len());
+    }
+}
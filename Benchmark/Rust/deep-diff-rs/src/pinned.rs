@@ -0,0 +1,83 @@
+use std::hash::Hash;
+
+use crate::error::PinError;
+use crate::hackel::{self, Change, Index};
+
+/// Same as `diff`, keeping the items at the `pinned` old indices (headers,
+/// sticky cells) in place: they are never reported as deleted or moved.
+/// Items whose match would cross a pinned one are reported as a delete and
+/// an insert instead, so the pinned item's batch-update position is its new
+/// index. Fails if a pinned item is missing from new, or if pinned items
+/// changed order among themselves.
+pub fn diff_pinned<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], pinned: &[usize]) -> Result<Vec<Change<'a, T>>, PinError> {
+    let symbols = hackel::build::<_, usize>(old, new);
+    let (mut old_match, mut new_match) = (symbols.old_match, symbols.new_match);
+
+    let mut anchors = Vec::with_capacity(pinned.len());
+    for index in pinned {
+        match old_match.get(*index) {
+            Some(matched) if *matched != usize::NONE => anchors.push((*index, *matched)),
+            _ => return Err(PinError::Deleted { index: *index }),
+        }
+    }
+    anchors.sort_unstable();
+    anchors.dedup();
+    if let Some(pair) = anchors.windows(2).find(|pair| pair[0].1 > pair[1].1) {
+        return Err(PinError::Reordered { index: pair[1].0 });
+    }
+
+    // A pair stays matched only between the same two anchors on both sides;
+    // then as many items are matched before each anchor in old as in new.
+    for (old_idx, matched) in old_match.iter_mut().enumerate() {
+        let new_idx = *matched;
+        if new_idx == usize::NONE {
+            continue;
+        }
+        let after = anchors.partition_point(|anchor| anchor.0 < old_idx);
+        let pinned_here = anchors.get(after).is_some_and(|anchor| anchor.0 == old_idx);
+        let lower = after.checked_sub(1).map(|idx| anchors[idx].1);
+        let upper = anchors.get(after + usize::from(pinned_here)).map(|anchor| anchor.1);
+        if !pinned_here && (lower.is_some_and(|lower| new_idx < lower) || upper.is_some_and(|upper| new_idx > upper)) {
+            *matched = usize::NONE;
+            new_match[new_idx] = usize::NONE;
+        }
+    }
+
+    Ok(hackel::changes_from_matches(old, new, &old_match, &new_match))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_to_vec;
+    use crate::hackel::ChangeKind;
+
+    #[test]
+    fn routes_around_pinned() {
+        let old = vec!["header", "a", "b", "footer", "c"];
+        let new = vec!["c", "header", "b", "a", "footer"];
+        let changes = diff_pinned(&old, &new, &[0, 3]).unwrap();
+
+        let touched = |index: usize| {
+            changes.iter().any(|change| match change {
+                Change::Delete(delete) => delete.index == index,
+                Change::Move(mv) => mv.from_index == index,
+                _ => false,
+            })
+        };
+        assert!(!touched(0) && !touched(3));
+        // `c` crossed the header, so it is deleted and inserted.
+        assert_eq!(changes.iter().filter(|change| change.kind() == ChangeKind::Delete).count(), 1);
+        let mut model = old.clone();
+        apply_to_vec(&mut model, &changes);
+        assert_eq!(model, new);
+    }
+
+    #[test]
+    fn violations() {
+        let old = vec!["header", "a", "footer"];
+        assert_eq!(diff_pinned(&old, &["a", "footer"], &[0]).err(), Some(PinError::Deleted { index: 0 }));
+        assert_eq!(diff_pinned(&old, &["footer", "a", "header"], &[0, 2]).err(), Some(PinError::Reordered { index: 2 }));
+        assert_eq!(diff_pinned(&old, &old, &[7]).err(), Some(PinError::Deleted { index: 7 }));
+    }
+}
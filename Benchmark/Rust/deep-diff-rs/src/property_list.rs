@@ -0,0 +1,49 @@
+//! Apple property-list encoding of changesets, in the shape of `SwiftChange`
+//! (`[{"move": {"item": …, "fromIndex": 0, "toIndex": 2}}, …]`), which is
+//! what `PropertyListDecoder` expects for a Codable enum of DeepDiff's
+//! changes. Plists have no null, so items must not serialize to one.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::hackel::Change;
+use crate::swift::SwiftChange;
+
+/// Encodes `changes` as an XML plist, readable in Xcode and `plutil`.
+pub fn to_plist_xml<T: Serialize>(changes: &[Change<'_, T>]) -> Result<Vec<u8>, plist::Error> {
+    let mut bytes = Vec::new();
+    plist::to_writer_xml(&mut bytes, &changes.iter().map(SwiftChange::from).collect::<Vec<_>>())?;
+    Ok(bytes)
+}
+
+/// Encodes `changes` as a binary plist, the compact form for archiving.
+pub fn to_plist_binary<T: Serialize>(changes: &[Change<'_, T>]) -> Result<Vec<u8>, plist::Error> {
+    let mut bytes = Vec::new();
+    plist::to_writer_binary(&mut bytes, &changes.iter().map(SwiftChange::from).collect::<Vec<_>>())?;
+    Ok(bytes)
+}
+
+/// Decodes a changeset from an XML or binary plist.
+pub fn from_plist<T: DeserializeOwned>(bytes: &[u8]) -> Result<Vec<SwiftChange<T>>, plist::Error> {
+    plist::from_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn round_trip() {
+        let old = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let new = vec!["c".to_string(), "x".to_string(), "a".to_string()];
+        let changes = hackel::diff(&old, &new);
+        let expected: Vec<SwiftChange<String>> = serde_json::from_str(&crate::swift::to_swift_json(&changes).unwrap()).unwrap();
+
+        assert_eq!(from_plist::<String>(&to_plist_binary(&changes).unwrap()).unwrap(), expected);
+        let xml = to_plist_xml(&changes).unwrap();
+        assert_eq!(from_plist::<String>(&xml).unwrap(), expected);
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("<key>move</key>") && xml.contains("<key>fromIndex</key>"), "{}", xml);
+    }
+}
@@ -0,0 +1,184 @@
+//! Protobuf encoding of changesets, matching `proto/changeset.proto`. The
+//! messages are declared with prost's derives, so building needs no `protoc`.
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use prost::Message;
+
+use crate::changeset::CowChange;
+use crate::hackel::Change;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct InsertMessage {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub item: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DeleteMessage {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub item: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ReplaceMessage {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub old_item: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub new_item: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MoveMessage {
+    #[prost(uint64, tag = "1")]
+    pub from_index: u64,
+    #[prost(uint64, tag = "2")]
+    pub to_index: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub item: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Kind {
+    #[prost(message, tag = "1")]
+    Insert(InsertMessage),
+    #[prost(message, tag = "2")]
+    Delete(DeleteMessage),
+    #[prost(message, tag = "3")]
+    Replace(ReplaceMessage),
+    #[prost(message, tag = "4")]
+    Move(MoveMessage),
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChangeMessage {
+    #[prost(oneof = "Kind", tags = "1, 2, 3, 4")]
+    pub kind: Option<Kind>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChangesetMessage {
+    #[prost(message, repeated, tag = "1")]
+    pub changes: Vec<ChangeMessage>,
+}
+
+/// Encodes `changes`, turning each item into bytes with `item`.
+pub fn encode<T, F: FnMut(&T) -> Vec<u8>>(changes: &[Change<'_, T>], mut item: F) -> Vec<u8> {
+    let changes = changes
+        .iter()
+        .map(|change| {
+            let kind = match change {
+                Change::Insert(insert) => Kind::Insert(InsertMessage { index: insert.index as u64, item: item(insert.item) }),
+                Change::Delete(delete) => Kind::Delete(DeleteMessage { index: delete.index as u64, item: item(delete.item) }),
+                Change::Replace(replace) => Kind::Replace(ReplaceMessage { index: replace.index as u64, old_item: item(replace.old_item), new_item: item(replace.new_item) }),
+                Change::Move(mv) => Kind::Move(MoveMessage { from_index: mv.from_index as u64, to_index: mv.to_index as u64, item: item(mv.item) }),
+            };
+            ChangeMessage { kind: Some(kind) }
+        })
+        .collect();
+    ChangesetMessage { changes }.encode_to_vec()
+}
+
+/// Why `decode` rejected its input.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The bytes are not a valid `Changeset` message.
+    Wire(prost::DecodeError),
+    /// A `Change` has none of its `kind` fields set.
+    MissingKind,
+    /// An index does not fit in `usize` on this platform.
+    IndexOverflow(u64),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Wire(err) => write!(f, "invalid changeset message: {}", err),
+            DecodeError::MissingKind => write!(f, "change without a kind"),
+            DecodeError::IndexOverflow(index) => write!(f, "index {} does not fit in usize", index),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::Wire(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<prost::DecodeError> for DecodeError {
+    fn from(err: prost::DecodeError) -> Self {
+        DecodeError::Wire(err)
+    }
+}
+
+fn index(value: u64) -> Result<usize, DecodeError> {
+    usize::try_from(value).map_err(|_| DecodeError::IndexOverflow(value))
+}
+
+/// Decodes a changeset written by `encode`, rebuilding items with `item`.
+pub fn decode<T: Clone, F: FnMut(&[u8]) -> T>(bytes: &[u8], mut item: F) -> Result<Vec<CowChange<'static, T>>, DecodeError> {
+    let message = ChangesetMessage::decode(bytes)?;
+    let mut owned = |bytes: &[u8]| Cow::Owned(item(bytes));
+    message
+        .changes
+        .into_iter()
+        .map(|change| {
+            Ok(match change.kind.ok_or(DecodeError::MissingKind)? {
+                Kind::Insert(insert) => CowChange::Insert { item: owned(&insert.item), index: index(insert.index)? },
+                Kind::Delete(delete) => CowChange::Delete { item: owned(&delete.item), index: index(delete.index)? },
+                Kind::Replace(replace) => CowChange::Replace { old_item: owned(&replace.old_item), new_item: owned(&replace.new_item), index: index(replace.index)? },
+                Kind::Move(mv) => CowChange::Move { item: owned(&mv.item), from_index: index(mv.from_index)?, to_index: index(mv.to_index)? },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn round_trip() {
+        let old = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let new = vec!["c".to_string(), "d".to_string(), "a".to_string()];
+        let changes = hackel::diff(&old, &new);
+
+        let bytes = encode(&changes, |item| item.as_bytes().to_vec());
+        let decoded = decode(&bytes, |bytes| String::from_utf8(bytes.to_vec()).unwrap()).unwrap();
+        let expected = changes.into_iter().map(|change| CowChange::from(change).into_owned()).collect::<Vec<_>>();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn replace_items() {
+        let old = vec![(1u8, 10u8), (2, 20)];
+        let new = vec![(2, 20), (1, 11)];
+        let changes = hackel::diff_by_key(&old, &new, |row| row.0);
+
+        let bytes = encode(&changes, |row| vec![row.0, row.1]);
+        let decoded = decode(&bytes, |bytes| (bytes[0], bytes[1])).unwrap();
+        let expected = changes.into_iter().map(|change| CowChange::from(change).into_owned()).collect::<Vec<_>>();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn missing_kind() {
+        let bytes = ChangesetMessage { changes: vec![ChangeMessage { kind: None }] }.encode_to_vec();
+        assert!(matches!(decode(&bytes, |bytes| bytes.to_vec()), Err(DecodeError::MissingKind)));
+        assert!(matches!(decode(&[0xff], |bytes| bytes.to_vec()), Err(DecodeError::Wire(_))));
+    }
+}
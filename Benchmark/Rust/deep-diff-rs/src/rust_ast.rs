@@ -0,0 +1,219 @@
+//! Semantic diffs of Rust source: both files are parsed with `syn` and their
+//! item trees diffed level by level, matching items by kind and name, so a
+//! review tool sees "signature of `impl Foo::fn bar` changed" instead of
+//! line noise.
+
+use quote::ToTokens;
+
+use crate::hackel::{self, Change};
+
+/// A change to one item, addressed by the keys of its enclosing items and
+/// its own, such as `["mod net", "impl Client", "fn send"]`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum AstChange {
+    Added { path: Vec<String> },
+    Removed { path: Vec<String> },
+    /// Reordered among its siblings.
+    Moved { path: Vec<String> },
+    /// A function's name, parameters, return type or qualifiers changed.
+    SignatureChanged { path: Vec<String> },
+    BodyChanged { path: Vec<String> },
+    /// Any other item, such as a struct or a `use`, changed, or a function's
+    /// attributes, doc comments or visibility did.
+    Changed { path: Vec<String> },
+}
+
+/// An item reduced to what the diff compares.
+#[derive(PartialEq)]
+struct Node<'a> {
+    key: String,
+    kind: Kind<'a>,
+}
+
+#[derive(PartialEq)]
+enum Kind<'a> {
+    Fn {
+        attrs: &'a [syn::Attribute],
+        vis: Option<&'a syn::Visibility>,
+        sig: &'a syn::Signature,
+        body: Option<&'a syn::Block>,
+    },
+    Container(Vec<Node<'a>>),
+    Item(&'a syn::Item),
+    ImplItem(&'a syn::ImplItem),
+    TraitItem(&'a syn::TraitItem),
+}
+
+/// Source text of `tokens` without the spaces `to_string` puts inside
+/// generics and paths, such as `Foo<&'a str>` for `Foo < & 'a str >`.
+fn source(tokens: &impl ToTokens) -> String {
+    let text = tokens.to_token_stream().to_string();
+    [(" <", "<"), ("< ", "<"), (" >", ">"), (" ,", ","), (" : ", ": "), (" :: ", "::"), (":: ", "::"), ("& ", "&")]
+        .iter()
+        .fold(text, |text, (from, to)| text.replace(from, to))
+}
+
+fn items(items: &[syn::Item]) -> Vec<Node<'_>> {
+    items.iter().map(item).collect()
+}
+
+fn item(item: &syn::Item) -> Node<'_> {
+    let (key, kind) = match item {
+        syn::Item::Fn(f) => (format!("fn {}", f.sig.ident), Kind::Fn { attrs: &f.attrs, vis: Some(&f.vis), sig: &f.sig, body: Some(&f.block) }),
+        syn::Item::Mod(m) => match &m.content {
+            Some((_, content)) => (format!("mod {}", m.ident), Kind::Container(items(content))),
+            None => (format!("mod {}", m.ident), Kind::Item(item)),
+        },
+        syn::Item::Impl(imp) => {
+            let key = match imp.trait_.as_ref().and_then(|(_, path, _)| path.segments.last()) {
+                Some(segment) => format!("impl{} {} for {}", source(&imp.generics), source(segment), source(&imp.self_ty)),
+                None => format!("impl{} {}", source(&imp.generics), source(&imp.self_ty)),
+            };
+            (key, Kind::Container(imp.items.iter().map(impl_item).collect()))
+        },
+        syn::Item::Trait(t) => (format!("trait {}", t.ident), Kind::Container(t.items.iter().map(trait_item).collect())),
+        syn::Item::Struct(s) => (format!("struct {}", s.ident), Kind::Item(item)),
+        syn::Item::Enum(e) => (format!("enum {}", e.ident), Kind::Item(item)),
+        syn::Item::Union(u) => (format!("union {}", u.ident), Kind::Item(item)),
+        syn::Item::Const(c) => (format!("const {}", c.ident), Kind::Item(item)),
+        syn::Item::Static(s) => (format!("static {}", s.ident), Kind::Item(item)),
+        syn::Item::Type(t) => (format!("type {}", t.ident), Kind::Item(item)),
+        syn::Item::Macro(m) => (format!("macro {}", m.ident.as_ref().map_or_else(String::new, ToString::to_string)), Kind::Item(item)),
+        syn::Item::Use(_) => ("use".to_string(), Kind::Item(item)),
+        _ => ("item".to_string(), Kind::Item(item)),
+    };
+    Node { key, kind }
+}
+
+fn impl_item(item: &syn::ImplItem) -> Node<'_> {
+    let (key, kind) = match item {
+        syn::ImplItem::Fn(f) => (format!("fn {}", f.sig.ident), Kind::Fn { attrs: &f.attrs, vis: Some(&f.vis), sig: &f.sig, body: Some(&f.block) }),
+        syn::ImplItem::Const(c) => (format!("const {}", c.ident), Kind::ImplItem(item)),
+        syn::ImplItem::Type(t) => (format!("type {}", t.ident), Kind::ImplItem(item)),
+        _ => ("item".to_string(), Kind::ImplItem(item)),
+    };
+    Node { key, kind }
+}
+
+fn trait_item(item: &syn::TraitItem) -> Node<'_> {
+    let (key, kind) = match item {
+        syn::TraitItem::Fn(f) => (format!("fn {}", f.sig.ident), Kind::Fn { attrs: &f.attrs, vis: None, sig: &f.sig, body: f.default.as_ref() }),
+        syn::TraitItem::Const(c) => (format!("const {}", c.ident), Kind::TraitItem(item)),
+        syn::TraitItem::Type(t) => (format!("type {}", t.ident), Kind::TraitItem(item)),
+        _ => ("item".to_string(), Kind::TraitItem(item)),
+    };
+    Node { key, kind }
+}
+
+fn diff_level(old: &[Node<'_>], new: &[Node<'_>], parent: &[String], changes: &mut Vec<AstChange>) {
+    let path = |node: &Node<'_>| parent.iter().cloned().chain(Some(node.key.clone())).collect::<Vec<_>>();
+    for change in hackel::diff_by_key(old, new, |node| node.key.clone()) {
+        match change {
+            Change::Insert(insert) => changes.push(AstChange::Added { path: path(insert.item) }),
+            Change::Delete(delete) => changes.push(AstChange::Removed { path: path(delete.item) }),
+            Change::Move(mv) => changes.push(AstChange::Moved { path: path(mv.item) }),
+            Change::Replace(replace) => match (&replace.old_item.kind, &replace.new_item.kind) {
+                (Kind::Container(old), Kind::Container(new)) => diff_level(old, new, &path(replace.new_item), changes),
+                (Kind::Fn { attrs: old_attrs, vis: old_vis, sig: old_sig, body: old_body }, Kind::Fn { attrs: new_attrs, vis: new_vis, sig: new_sig, body: new_body }) => {
+                    if old_attrs != new_attrs || old_vis != new_vis {
+                        changes.push(AstChange::Changed { path: path(replace.new_item) });
+                    }
+                    if old_sig != new_sig {
+                        changes.push(AstChange::SignatureChanged { path: path(replace.new_item) });
+                    }
+                    if old_body != new_body {
+                        changes.push(AstChange::BodyChanged { path: path(replace.new_item) });
+                    }
+                },
+                _ => changes.push(AstChange::Changed { path: path(replace.new_item) }),
+            },
+        }
+    }
+}
+
+/// Parses `old` and `new` as Rust files and diffs their items. Formatting
+/// and comments other than doc comments do not count as changes.
+pub fn diff_rust(old: &str, new: &str) -> Result<Vec<AstChange>, syn::Error> {
+    let old = syn::parse_file(old)?;
+    let new = syn::parse_file(new)?;
+    let mut changes = Vec::new();
+    diff_level(&items(&old.items), &items(&new.items), &[], &mut changes);
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn item_changes() {
+        let old = "
+            use std::fmt;
+            struct Point { x: i32 }
+            impl Point {
+                fn new(x: i32) -> Self { Point { x } }
+                fn norm(&self) -> i32 { self.x.abs() }
+            }
+            fn removed() {}
+        ";
+        let new = "
+            use std::fmt;
+            // Comments and layout do not matter.
+            struct Point { x: i32, y: i32 }
+            impl Point {
+                fn new(x: i32, y: i32) -> Self { Point { x, y } }
+                fn norm(&self) -> i32 {
+                    self.x.abs() + self.y.abs()
+                }
+            }
+            fn added() {}
+        ";
+        assert_eq!(diff_rust(old, new).unwrap(), vec![
+            AstChange::Removed { path: path(&["fn removed"]) },
+            AstChange::Changed { path: path(&["struct Point"]) },
+            AstChange::SignatureChanged { path: path(&["impl Point", "fn new"]) },
+            AstChange::BodyChanged { path: path(&["impl Point", "fn new"]) },
+            AstChange::BodyChanged { path: path(&["impl Point", "fn norm"]) },
+            AstChange::Added { path: path(&["fn added"]) },
+        ]);
+        assert_eq!(diff_rust(old, old).unwrap(), vec![]);
+        assert!(diff_rust(old, "fn (").is_err());
+    }
+
+    #[test]
+    fn fn_attributes_and_visibility() {
+        let old = "fn a() {} fn b() {} fn c() {}";
+        let new = "pub fn a() {} #[inline] fn b() {} /// Docs.\n fn c() {}";
+        assert_eq!(diff_rust(old, new).unwrap(), vec![
+            AstChange::Changed { path: path(&["fn a"]) },
+            AstChange::Changed { path: path(&["fn b"]) },
+            AstChange::Changed { path: path(&["fn c"]) },
+        ]);
+    }
+
+    #[test]
+    fn impls_keyed_by_full_type() {
+        let old = "
+            impl Foo<A> { fn a() {} }
+            impl Foo<B> { fn b() {} }
+            impl Foo<C> { fn c() {} }
+            impl<'a, T: Clone> From<&'a T> for crate::Bar<T> { fn from() {} }
+        ";
+        let new = "
+            impl Foo<A> { fn a() { x() } }
+            impl Foo<B> { fn b() {} }
+            impl Foo<D> { fn c() {} }
+            impl<'a, T: Clone> From<&'a T> for crate::Bar<T> { fn from() { y() } }
+        ";
+        assert_eq!(diff_rust(old, new).unwrap(), vec![
+            AstChange::Removed { path: path(&["impl Foo<C>"]) },
+            AstChange::BodyChanged { path: path(&["impl Foo<A>", "fn a"]) },
+            AstChange::Added { path: path(&["impl Foo<D>"]) },
+            AstChange::BodyChanged { path: path(&["impl<'a, T: Clone> From<&'a T> for crate::Bar<T>", "fn from"]) },
+        ]);
+    }
+}
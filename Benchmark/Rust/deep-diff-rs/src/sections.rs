@@ -0,0 +1,98 @@
+//! Diffing lists split into sections, such as a table view's grouped rows.
+//! Items are matched across the whole list, so one that changed section is
+//! reported as a single `MoveAcrossSections` rather than a delete in one
+//! section and an insert in another.
+
+use std::hash::Hash;
+
+use crate::hackel::{self, Step};
+
+/// A change addressed by `(section, index)`. Deletes use old positions, the
+/// rest new ones, as `diff` does.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum SectionedChange<'a, T> {
+    Insert { item: &'a T, at: (usize, usize) },
+    Delete { item: &'a T, at: (usize, usize) },
+    /// Same key, different item; followed by a move if it also moved.
+    Replace { old_item: &'a T, new_item: &'a T, at: (usize, usize) },
+    /// Moved within one section. Items only shifted by changes around them
+    /// are not reported.
+    Move { item: &'a T, section: usize, from_index: usize, to_index: usize },
+    /// Moved into another section.
+    MoveAcrossSections { item: &'a T, from: (usize, usize), to: (usize, usize) },
+}
+
+/// First flat index of every section, plus the total.
+fn starts<T>(sections: &[Vec<T>]) -> Vec<usize> {
+    let mut starts = vec![0];
+    for section in sections {
+        starts.push(starts[starts.len() - 1] + section.len());
+    }
+    starts
+}
+
+fn locate(starts: &[usize], flat: usize) -> (usize, usize) {
+    let section = starts.partition_point(|start| *start <= flat) - 1;
+    (section, flat - starts[section])
+}
+
+/// Diffs `old` into `new` section by section, matching items across all
+/// sections by `key`. Sections are matched by position; this reports
+/// changes to items only.
+pub fn diff_sections<'a, T, K, F>(old: &'a [Vec<T>], new: &'a [Vec<T>], mut key: F) -> Vec<SectionedChange<'a, T>>
+where
+    T: PartialEq,
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    let old_flat = old.iter().flatten().collect::<Vec<_>>();
+    let new_flat = new.iter().flatten().collect::<Vec<_>>();
+    let (old_starts, new_starts) = (starts(old), starts(new));
+    let symbols = hackel::build::<_, usize>(old_flat.iter().map(|item| key(item)).collect::<Vec<_>>(), new_flat.iter().map(|item| key(item)).collect::<Vec<_>>());
+
+    let mut changes = Vec::new();
+    hackel::walk(&symbols, |step| match step {
+        Step::Delete(idx) => changes.push(SectionedChange::Delete { item: old_flat[idx], at: locate(&old_starts, idx) }),
+        Step::Insert(idx) => changes.push(SectionedChange::Insert { item: new_flat[idx], at: locate(&new_starts, idx) }),
+        Step::Match { old_idx, new_idx, moved } => {
+            let (from, to) = (locate(&old_starts, old_idx), locate(&new_starts, new_idx));
+            let (old_item, new_item) = (old_flat[old_idx], new_flat[new_idx]);
+            if old_item != new_item {
+                changes.push(SectionedChange::Replace { old_item, new_item, at: to });
+            }
+            if from.0 != to.0 {
+                changes.push(SectionedChange::MoveAcrossSections { item: new_item, from, to });
+            } else if moved && from.1 != to.1 {
+                changes.push(SectionedChange::Move { item: new_item, section: to.0, from_index: from.1, to_index: to.1 });
+            }
+        },
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_across_sections() {
+        let old = vec![vec!["a", "b"], vec!["c"]];
+        let new = vec![vec!["a"], vec!["c", "b"]];
+        let changes = diff_sections(&old, &new, |item| *item);
+        assert_eq!(changes, vec![SectionedChange::MoveAcrossSections { item: &"b", from: (0, 1), to: (1, 1) }]);
+    }
+
+    #[test]
+    fn within_sections() {
+        let old = vec![vec![(1, "a"), (2, "b")], vec![(3, "c"), (4, "d")]];
+        let new = vec![vec![(2, "b"), (1, "a"), (5, "e")], vec![(4, "D")]];
+        let changes = diff_sections(&old, &new, |item| item.0);
+        assert_eq!(changes, vec![
+            SectionedChange::Delete { item: &(3, "c"), at: (1, 0) },
+            SectionedChange::Move { item: &(2, "b"), section: 0, from_index: 1, to_index: 0 },
+            SectionedChange::Move { item: &(1, "a"), section: 0, from_index: 0, to_index: 1 },
+            SectionedChange::Insert { item: &(5, "e"), at: (0, 2) },
+            SectionedChange::Replace { old_item: &(4, "d"), new_item: &(4, "D"), at: (1, 0) },
+        ]);
+    }
+}
@@ -0,0 +1,68 @@
+//! Diffing lists of shared immutable models (`Rc<U>`, `Arc<U>`, `&U`) by
+//! identity.
+//!
+//! `Rc` and `Arc` already check pointers before calling `U::eq`, but hashing
+//! still walks the whole model. Here both hashing and equality use only the
+//! address, so an item matches exactly when it is the same allocation.
+
+use std::ops::Deref;
+
+use crate::hackel::{self, Change, Delete, Insert, Move, Step};
+
+fn address<P: Deref>(item: &P) -> *const () {
+    &**item as *const P::Target as *const ()
+}
+
+/// Same as `diff`, matching items by the address they point to. A model that
+/// was rebuilt with equal content counts as a different item.
+pub fn diff_by_pointer<'a, P: Deref>(old: &'a [P], new: &'a [P]) -> Vec<Change<'a, P>> {
+    let symbols = hackel::build::<_, usize>(old.iter().map(address), new.iter().map(address));
+    let mut changes = Vec::new();
+    hackel::walk(&symbols, |step| match step {
+        Step::Delete(index) => changes.push(Change::Delete(Delete { item: &old[index], index })),
+        Step::Insert(index) => changes.push(Change::Insert(Insert { item: &new[index], index })),
+        Step::Match { old_idx, new_idx, moved } => {
+            if moved {
+                changes.push(Change::Move(Move { item: &new[new_idx], from_index: old_idx, to_index: new_idx }));
+            }
+        },
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Panics if the diff ever looks inside.
+    struct Opaque(u32);
+
+    impl PartialEq for Opaque {
+        fn eq(&self, _: &Self) -> bool {
+            panic!("compared by value")
+        }
+    }
+
+    #[test]
+    fn rc_by_identity() {
+        let models = (0..4).map(|i| Rc::new(Opaque(i))).collect::<Vec<_>>();
+        let old = models.clone();
+        let new = vec![models[3].clone(), models[0].clone(), models[1].clone(), Rc::new(Opaque(2))];
+
+        let changes = diff_by_pointer(&old, &new);
+        assert_eq!(changes.len(), 5);
+        assert!(matches!(&changes[0], Change::Delete(d) if d.item.0 == 2));
+        assert!(matches!(&changes[1], Change::Move(m) if (m.from_index, m.to_index) == (3, 0)));
+        assert!(matches!(&changes[4], Change::Insert(i) if i.index == 3 && i.item.0 == 2));
+    }
+
+    #[test]
+    fn arc_unchanged() {
+        let old = (0..100).map(|i| Arc::new(Opaque(i))).collect::<Vec<_>>();
+        let new = old.clone();
+        assert!(diff_by_pointer(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::hackel::{self, Change, Index};
+
+/// Inputs with at most this many items on both sides skip the symbol table.
+pub const SMALL_THRESHOLD: usize = 32;
+
+/// Pairs each new item with the first unmatched equal old item, which is the
+/// same pairing the symbol table produces, using only comparisons.
+pub(crate) fn match_small<T: PartialEq, I: Index>(old: &[T], new: &[T], old_match: &mut [I], new_match: &mut [I]) {
+    // Old items before `start` are all matched, so a common prefix costs
+    // one comparison per item.
+    let mut start = 0;
+    for (new_idx, item) in new.iter().enumerate() {
+        let found = old[start..]
+            .iter()
+            .enumerate()
+            .position(|(offset, candidate)| old_match[start + offset] == I::NONE && candidate == item);
+
+        if let Some(offset) = found {
+            let old_idx = start + offset;
+            new_match[new_idx] = I::from_usize(old_idx);
+            old_match[old_idx] = I::from_usize(new_idx);
+            while start < old.len() && old_match[start] != I::NONE {
+                start += 1;
+            }
+        }
+    }
+}
+
+/// O(n·m) diff by direct comparison. Up to `SMALL_THRESHOLD` items per side
+/// it works on stack arrays and performs no heap allocation of its own.
+pub fn diff_small_with<'a, T, I, F>(old: &'a [T], new: &'a [T], mut f: F)
+where
+    T: PartialEq,
+    I: Index,
+    F: FnMut(Change<'a, T, I>),
+{
+    if old.len() > SMALL_THRESHOLD || new.len() > SMALL_THRESHOLD {
+        let mut old_match = vec![I::NONE; old.len()];
+        let mut new_match = vec![I::NONE; new.len()];
+        let mut delete_offsets = vec![I::NONE; old.len()];
+        match_small(old, new, &mut old_match, &mut new_match);
+        hackel::walk_matches(&old_match, &new_match, &mut delete_offsets, |step| hackel::emit(old, new, step, &mut f), &mut ());
+        return;
+    }
+
+    let mut old_match = [I::NONE; SMALL_THRESHOLD];
+    let mut new_match = [I::NONE; SMALL_THRESHOLD];
+    let mut delete_offsets = [I::NONE; SMALL_THRESHOLD];
+    let old_match = &mut old_match[..old.len()];
+    let new_match = &mut new_match[..new.len()];
+
+    match_small(old, new, old_match, new_match);
+    hackel::walk_matches(old_match, new_match, &mut delete_offsets[..old.len()], |step| hackel::emit(old, new, step, &mut f), &mut ());
+}
+
+/// Diff for items that are only `PartialEq`, such as floats or trait
+/// objects, which the symbol table cannot hash. Pairs items as `diff` does,
+/// by direct comparison: O(n·m) in general, linear when the inputs share
+/// most of a prefix. Items unequal to themselves, like NaN, never match.
+pub fn diff_eq<'a, T: PartialEq>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    let mut changes = Vec::new();
+    diff_small_with(old, new, |change| changes.push(change));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{describe, ternary_inputs};
+
+    fn table_diff<'a>(old: &'a [u8], new: &'a [u8]) -> Vec<(char, usize, usize)> {
+        let symbols = hackel::build::<_, usize>(old, new);
+        let mut changes = Vec::new();
+        hackel::walk(&symbols, |step| hackel::emit(old, new, step, &mut |change| changes.push(change)));
+        describe(&changes)
+    }
+
+    fn small_diff<'a>(old: &'a [u8], new: &'a [u8]) -> Vec<(char, usize, usize)> {
+        let mut changes = Vec::new();
+        diff_small_with::<_, usize, _>(old, new, |change| changes.push(change));
+        describe(&changes)
+    }
+
+    #[test]
+    fn agrees_with_table() {
+        let inputs = ternary_inputs(4);
+
+        for old in &inputs {
+            for new in &inputs {
+                assert_eq!(small_diff(old, new), table_diff(old, new), "{:?} -> {:?}", old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn beyond_threshold() {
+        let old = (0..100u8).map(|i| i % 7).collect::<Vec<_>>();
+        let new = (0..90u8).map(|i| (i % 7) * 3 % 7).collect::<Vec<_>>();
+        assert_eq!(small_diff(&old, &new), table_diff(&old, &new));
+    }
+
+    #[test]
+    fn full_size() {
+        let old = (0..SMALL_THRESHOLD as u8).collect::<Vec<_>>();
+        let new = old.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(small_diff(&old, &new), table_diff(&old, &new));
+    }
+
+    #[test]
+    fn floats() {
+        let old = [1.5, 2.0, f64::NAN, 3.0];
+        let new = [3.0, 1.5, 2.5];
+        let changes = diff_eq(&old, &new)
+            .into_iter()
+            .map(|change| match change {
+                Change::Insert(i) => format!("insert {} at {}", i.item, i.index),
+                Change::Delete(d) => format!("delete {} at {}", d.item, d.index),
+                Change::Replace(r) => format!("replace {} at {}", r.new_item, r.index),
+                Change::Move(m) => format!("move {} from {} to {}", m.item, m.from_index, m.to_index),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(changes, ["delete 2 at 1", "delete NaN at 2", "move 3 from 3 to 0", "move 1.5 from 0 to 1", "insert 2.5 at 2"]);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::hackel::Change;
+
+/// The model `NSDiffableDataSourceSnapshot` applies: the final ordered item
+/// identifiers, plus the identifiers whose content must be reloaded.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct Snapshot<K> {
+    pub identifiers: Vec<K>,
+    pub reloaded: Vec<K>,
+}
+
+impl<K> Snapshot<K> {
+    /// Builds the snapshot for `new` after `changes`, identifying items with
+    /// `id`. Replaced items are reloaded; moves and inserts are implied by
+    /// the identifier order.
+    pub fn new<T, F: Fn(&T) -> K>(new: &[T], changes: &[Change<'_, T>], id: F) -> Self {
+        Snapshot {
+            identifiers: new.iter().map(&id).collect(),
+            reloaded: changes
+                .iter()
+                .filter_map(|change| match change {
+                    Change::Replace(replace) => Some(id(replace.new_item)),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{diff_with_options, DiffOptions};
+    use crate::{changeset, hackel};
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct Row {
+        id: u32,
+        title: &'static str,
+    }
+
+    #[test]
+    fn identifiers_follow_new() {
+        let old = vec![Row { id: 1, title: "a" }, Row { id: 2, title: "b" }];
+        let new = vec![Row { id: 2, title: "b" }, Row { id: 3, title: "c" }, Row { id: 1, title: "a" }];
+        let changes = hackel::diff(&old, &new);
+
+        let snapshot = Snapshot::new(&new, &changes, |row| row.id);
+        assert_eq!(snapshot.identifiers, vec![2, 3, 1]);
+        assert!(snapshot.reloaded.is_empty());
+    }
+
+    #[test]
+    fn unchanged_from_changeset() {
+        let rows = vec![Row { id: 1, title: "a" }];
+        let changeset = changeset::diff_result(&rows, &rows).into_changeset();
+
+        let snapshot = Snapshot::new(&rows, &changeset, |row| row.title);
+        assert_eq!(snapshot, Snapshot { identifiers: vec!["a"], reloaded: Vec::new() });
+    }
+
+    #[test]
+    fn deleted_rows_drop_out() {
+        let old = vec![Row { id: 1, title: "a" }, Row { id: 2, title: "b" }];
+        let new = vec![Row { id: 2, title: "b" }];
+        let changes = diff_with_options(&old, &new, &DiffOptions::default());
+        assert_eq!(Snapshot::new(&new, &changes, |row| row.id).identifiers, vec![2]);
+    }
+
+    #[test]
+    fn edited_rows_reload() {
+        let old = vec![Row { id: 1, title: "a" }, Row { id: 2, title: "b" }, Row { id: 3, title: "c" }];
+        let new = vec![Row { id: 1, title: "a" }, Row { id: 2, title: "B" }, Row { id: 3, title: "c" }];
+        let changes = hackel::diff_by_key(&old, &new, |row| row.id);
+
+        let snapshot = Snapshot::new(&new, &changes, |row| row.id);
+        assert_eq!(snapshot, Snapshot { identifiers: vec![1, 2, 3], reloaded: vec![2] });
+    }
+}
@@ -0,0 +1,162 @@
+#[cfg(feature = "stats")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "stats")]
+use std::cell::Cell;
+
+/// Counters describing a single `diff_with_stats` run.
+///
+/// `passes_ns` holds the time spent building the new side of the table, the
+/// old side, matching, emitting deletes, and emitting inserts/moves.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct DiffStats {
+    pub table_entries: usize,
+    pub hash_lookups: usize,
+    pub matches: usize,
+    pub moves_detected: usize,
+    pub passes_ns: [u64; 5],
+    #[cfg(feature = "stats")]
+    pub allocations: usize,
+    #[cfg(feature = "stats")]
+    pub peak_bytes: usize,
+}
+
+#[cfg(feature = "stats")]
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static CURRENT_BYTES: Cell<usize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Global allocator that counts allocations and live bytes per thread.
+///
+/// The library only installs it under the `counting-allocator` feature,
+/// meant for the static library the benchmark app links. Other binaries
+/// that want allocation counts in `DiffStats` and `measure` install it with
+/// `#[global_allocator]`; without it they stay zero.
+#[cfg(feature = "stats")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "stats")]
+impl CountingAllocator {
+    fn record_alloc(size: usize) {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        Self::grow(size);
+    }
+
+    fn grow(size: usize) {
+        let _ = CURRENT_BYTES.try_with(|c| {
+            let current = c.get() + size;
+            c.set(current);
+            let _ = PEAK_BYTES.try_with(|p| if current > p.get() { p.set(current) });
+        });
+    }
+
+    fn shrink(size: usize) {
+        let _ = CURRENT_BYTES.try_with(|c| c.set(c.get().saturating_sub(size)));
+    }
+}
+
+#[cfg(feature = "stats")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::shrink(layout.size());
+            Self::record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(feature = "stats")]
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct Allocations {
+    pub allocations: usize,
+    pub peak_bytes: usize,
+}
+
+/// Runs `f` and reports the allocations it made on the current thread.
+///
+/// `peak_bytes` is relative to the bytes already live when `f` started.
+/// Calls nest: an enclosing `measure` still sees the peak reached inside.
+#[cfg(feature = "stats")]
+pub fn measure<R, F: FnOnce() -> R>(f: F) -> (R, Allocations) {
+    let allocations = ALLOCATIONS.with(Cell::get);
+    let baseline = CURRENT_BYTES.with(Cell::get);
+    let outer_peak = PEAK_BYTES.with(|p| p.replace(baseline));
+
+    let result = f();
+
+    let peak = PEAK_BYTES.with(|p| p.replace(outer_peak.max(p.get())));
+    let stats = Allocations {
+        allocations: ALLOCATIONS.with(Cell::get) - allocations,
+        peak_bytes: peak.saturating_sub(baseline),
+    };
+    (result, stats)
+}
+
+#[cfg(all(test, feature = "stats"))]
+mod tests {
+    use super::*;
+    use crate::hackel::{diff_with_stats, Change};
+
+    #[test]
+    fn empty() {
+        let old: Vec<u32> = Vec::new();
+        let (changes, stats) = diff_with_stats(&old, &[]);
+        assert!(changes.is_empty());
+        assert_eq!(stats.allocations, 0);
+        assert_eq!(stats.peak_bytes, 0);
+    }
+
+    #[test]
+    fn counts_allocations() {
+        let old = (0..100).collect::<Vec<_>>();
+        let new = (50..150).collect::<Vec<_>>();
+        let (changes, stats) = diff_with_stats(&old, &new);
+        assert_eq!(changes.len(), 100);
+
+        assert!(stats.allocations > 0);
+        assert!(stats.peak_bytes >= changes.capacity() * std::mem::size_of::<Change<u32>>());
+    }
+
+    #[test]
+    fn measure_is_relative() {
+        let _keep = vec![0u8; 1 << 20];
+        let (_, stats) = measure(|| vec![0u8; 16]);
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.peak_bytes, 16);
+    }
+
+    #[test]
+    fn measure_nests() {
+        let ((_, inner), outer) = measure(|| {
+            drop(vec![0u8; 4096]);
+            measure(|| vec![0u8; 16])
+        });
+        assert_eq!(inner.peak_bytes, 16);
+        assert_eq!(outer.allocations, 2);
+        assert!(outer.peak_bytes >= 4096, "{:?}", outer);
+    }
+}
@@ -0,0 +1,62 @@
+use std::ops::Range;
+
+/// A contiguous run of items equal in both sequences.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct CommonRun {
+    pub old_start: usize,
+    pub new_start: usize,
+    pub len: usize,
+}
+
+impl CommonRun {
+    pub fn old_range(&self) -> Range<usize> {
+        self.old_start..self.old_start + self.len
+    }
+
+    pub fn new_range(&self) -> Range<usize> {
+        self.new_start..self.new_start + self.len
+    }
+}
+
+/// Longest common substring: the longest run of consecutive items found in
+/// both `old` and `new`, unlike the longest common subsequence, which may
+/// skip items. Ties go to the run starting first in old, then in new.
+/// `None` when the inputs share no item.
+///
+/// O(n·m) comparisons in O(m) memory.
+pub fn longest_common_substring<T: PartialEq>(old: &[T], new: &[T]) -> Option<CommonRun> {
+    // `row[j + 1]` is the length of the common run ending at the current old
+    // item and `new[j]`.
+    let mut prev = vec![0; new.len() + 1];
+    let mut row = vec![0; new.len() + 1];
+    let mut best: Option<CommonRun> = None;
+    for (old_idx, old_item) in old.iter().enumerate() {
+        for (new_idx, new_item) in new.iter().enumerate() {
+            row[new_idx + 1] = if old_item == new_item { prev[new_idx] + 1 } else { 0 };
+            let len = row[new_idx + 1];
+            if len > best.map_or(0, |run| run.len) {
+                best = Some(CommonRun { old_start: old_idx + 1 - len, new_start: new_idx + 1 - len, len });
+            }
+        }
+        std::mem::swap(&mut prev, &mut row);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_run() {
+        let old = b"xabcdyabcdez";
+        let new = b"abcdeqxab";
+        let run = longest_common_substring(old, new).unwrap();
+        assert_eq!(run, CommonRun { old_start: 6, new_start: 0, len: 5 });
+        assert_eq!(&old[run.old_range()], &new[run.new_range()]);
+
+        assert_eq!(longest_common_substring(b"ab", b"ba"), Some(CommonRun { old_start: 0, new_start: 1, len: 1 }));
+        assert_eq!(longest_common_substring(b"ab", b"cd"), None);
+        assert_eq!(longest_common_substring::<u8>(&[], b"cd"), None);
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::changeset::CowChange;
+use crate::hackel::Change;
+
+/// A change in the JSON shape of DeepDiff's Swift `Change` enum, e.g.
+/// `{"move":{"item":"a","fromIndex":0,"toIndex":2}}`. Serializes from
+/// borrowed items (`SwiftChange<&T>`) and deserializes into owned ones.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SwiftChange<T> {
+    Insert { item: T, index: usize },
+    Delete { item: T, index: usize },
+    #[serde(rename_all = "camelCase")]
+    Replace { old_item: T, new_item: T, index: usize },
+    #[serde(rename_all = "camelCase")]
+    Move { item: T, from_index: usize, to_index: usize },
+}
+
+impl<'c, 'a, T> From<&'c Change<'a, T>> for SwiftChange<&'a T> {
+    fn from(change: &'c Change<'a, T>) -> Self {
+        match change {
+            Change::Insert(insert) => SwiftChange::Insert { item: insert.item, index: insert.index },
+            Change::Delete(delete) => SwiftChange::Delete { item: delete.item, index: delete.index },
+            Change::Replace(replace) => SwiftChange::Replace { old_item: replace.old_item, new_item: replace.new_item, index: replace.index },
+            Change::Move(mv) => SwiftChange::Move { item: mv.item, from_index: mv.from_index, to_index: mv.to_index },
+        }
+    }
+}
+
+impl<T: Clone> From<CowChange<'_, T>> for SwiftChange<T> {
+    fn from(change: CowChange<'_, T>) -> Self {
+        match change {
+            CowChange::Insert { item, index } => SwiftChange::Insert { item: item.into_owned(), index },
+            CowChange::Delete { item, index } => SwiftChange::Delete { item: item.into_owned(), index },
+            CowChange::Replace { old_item, new_item, index } => SwiftChange::Replace { old_item: old_item.into_owned(), new_item: new_item.into_owned(), index },
+            CowChange::Move { item, from_index, to_index } => SwiftChange::Move { item: item.into_owned(), from_index, to_index },
+        }
+    }
+}
+
+/// A change as `normalize` leaves it. Deletes sort before inserts before
+/// moves.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Debug)]
+pub enum Normalized<T> {
+    Delete { item: T, index: usize },
+    Insert { item: T, index: usize },
+    Move { item: T, from_index: usize, to_index: usize },
+}
+
+impl<T: fmt::Debug> fmt::Display for Normalized<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Normalized::Delete { item, index } => write!(f, "delete {:?} at {}", item, index),
+            Normalized::Insert { item, index } => write!(f, "insert {:?} at {}", item, index),
+            Normalized::Move { item, from_index, to_index } => write!(f, "move {:?} from {} to {}", item, from_index, to_index),
+        }
+    }
+}
+
+/// Expands replaces into a delete and an insert and sorts the changes.
+/// DeepDiff folds a delete and an insert at the same index into a replace
+/// where this crate may not, so changesets of both compare equal only in
+/// this form.
+pub fn normalize<T: Ord, I: IntoIterator<Item = SwiftChange<T>>>(changes: I) -> Vec<Normalized<T>> {
+    let mut normalized = Vec::new();
+    for change in changes {
+        match change {
+            SwiftChange::Delete { item, index } => normalized.push(Normalized::Delete { item, index }),
+            SwiftChange::Insert { item, index } => normalized.push(Normalized::Insert { item, index }),
+            SwiftChange::Replace { old_item, new_item, index } => {
+                normalized.push(Normalized::Delete { item: old_item, index });
+                normalized.push(Normalized::Insert { item: new_item, index });
+            },
+            SwiftChange::Move { item, from_index, to_index } => normalized.push(Normalized::Move { item, from_index, to_index }),
+        }
+    }
+    normalized.sort();
+    normalized
+}
+
+/// Serializes `changes` as a JSON array in DeepDiff's shape, without
+/// whitespace, so outputs of both implementations compare byte for byte.
+pub fn to_swift_json<T: Serialize>(changes: &[Change<'_, T>]) -> serde_json::Result<String> {
+    serde_json::to_string(&changes.iter().map(SwiftChange::from).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn deepdiff_shape() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["c", "x", "a"];
+        let json = to_swift_json(&hackel::diff(&old, &new)).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"delete":{"item":"b","index":1}},{"move":{"item":"c","fromIndex":2,"toIndex":0}},{"insert":{"item":"x","index":1}},{"move":{"item":"a","fromIndex":0,"toIndex":2}}]"#
+        );
+    }
+
+    #[test]
+    fn replace_keys() {
+        let old = vec![(1, "a")];
+        let new = vec![(1, "b")];
+        let json = to_swift_json(&hackel::diff_by_key(&old, &new, |row| row.0)).unwrap();
+        assert_eq!(json, r#"[{"replace":{"oldItem":[1,"a"],"newItem":[1,"b"],"index":0}}]"#);
+    }
+
+    #[test]
+    fn round_trip() {
+        let old = vec!["a".to_string(), "b".to_string()];
+        let new = vec!["b".to_string()];
+        let json = to_swift_json(&hackel::diff(&old, &new)).unwrap();
+        let parsed: Vec<SwiftChange<String>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, vec![SwiftChange::Delete { item: "a".to_string(), index: 0 }]);
+    }
+
+    #[test]
+    fn normalized() {
+        let changes = vec![
+            SwiftChange::Move { item: "b", from_index: 1, to_index: 0 },
+            SwiftChange::Replace { old_item: "a", new_item: "c", index: 1 },
+        ];
+        assert_eq!(normalize(changes), vec![
+            Normalized::Delete { item: "a", index: 1 },
+            Normalized::Insert { item: "c", index: 1 },
+            Normalized::Move { item: "b", from_index: 1, to_index: 0 },
+        ]);
+        assert_eq!(Normalized::Insert { item: "c", index: 1 }.to_string(), r#"insert "c" at 1"#);
+    }
+}
@@ -0,0 +1,127 @@
+//! Strategies and assertions for property-testing code built on this crate.
+//!
+//! Enabled by the `test-utils` feature.
+
+use std::fmt::Debug;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::hackel::Change;
+
+/// An unrelated pair of sequences over a small alphabet.
+pub fn pair(max_len: usize, alphabet: u8) -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+    (vec(0..alphabet, 0..=max_len), vec(0..alphabet, 0..=max_len))
+}
+
+/// Distinct items and a permutation of them.
+pub fn shuffled(max_len: usize) -> impl Strategy<Value = (Vec<u32>, Vec<u32>)> {
+    (0..=max_len as u32).prop_flat_map(|len| {
+        let old = (0..len).collect::<Vec<_>>();
+        (Just(old.clone()), Just(old).prop_shuffle())
+    })
+}
+
+/// A sequence and a copy with up to `k` single-item inserts or removals.
+/// Inserted items are drawn from the same alphabet, so they may duplicate.
+pub fn k_edits(max_len: usize, k: usize, alphabet: u8) -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+    (vec(0..alphabet, 0..=max_len), vec((any::<bool>(), any::<prop::sample::Index>(), 0..alphabet), 0..=k)).prop_map(|(old, edits)| {
+        let mut new = old.clone();
+        for (insert, index, item) in edits {
+            if insert {
+                new.insert(index.index(new.len() + 1), item);
+            } else if !new.is_empty() {
+                new.remove(index.index(new.len()));
+            }
+        }
+        (old, new)
+    })
+}
+
+/// Sequences where most items repeat, like separators and placeholder cells.
+pub fn duplicates(max_len: usize) -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+    pair(max_len, 2)
+}
+
+/// Applies changes the way `performBatchUpdates` does: deletes and move
+/// sources address `old`, inserts, replaces and move targets address the
+/// result, and untouched survivors keep their relative order in the free
+/// slots. Returns `None` if the changes do not describe a valid batch.
+pub fn apply_batch<T: Clone>(old: &[T], new_len: usize, changes: &[Change<'_, T>]) -> Option<Vec<T>> {
+    let mut slots = vec![None; new_len];
+    let mut consumed = vec![false; old.len()];
+
+    for change in changes {
+        match change {
+            Change::Delete(d) => *consumed.get_mut(d.index)? = true,
+            Change::Insert(i) => *slots.get_mut(i.index)? = Some(i.item.clone()),
+            Change::Move(m) => {
+                *consumed.get_mut(m.from_index)? = true;
+                *slots.get_mut(m.to_index)? = Some(old[m.from_index].clone());
+            },
+            Change::Replace(_) => {},
+        }
+    }
+
+    let mut survivors = old.iter().zip(&consumed).filter(|(_, consumed)| !**consumed).map(|(item, _)| item.clone());
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = Some(survivors.next()?);
+    }
+    if survivors.next().is_some() {
+        return None;
+    }
+
+    for change in changes {
+        if let Change::Replace(r) = change {
+            *slots.get_mut(r.index)? = Some(r.new_item.clone());
+        }
+    }
+    slots.into_iter().collect()
+}
+
+/// Panics unless `changes` turn `old` into `new` under `apply_batch`.
+pub fn assert_changeset_valid<T: Clone + PartialEq + Debug>(old: &[T], new: &[T], changes: &[Change<'_, T>]) {
+    match apply_batch(old, new.len(), changes) {
+        Some(applied) => assert_eq!(applied, new, "changes do not turn {:?} into {:?}", old, new),
+        None => panic!("changes are not a valid batch for {:?} -> {:?}", old, new),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hackel, myers};
+
+    proptest! {
+        #[test]
+        fn diff_pairs((old, new) in pair(48, 6)) {
+            assert_changeset_valid(&old, &new, &hackel::diff(&old, &new));
+        }
+
+        #[test]
+        fn diff_shuffles((old, new) in shuffled(64)) {
+            let changes = hackel::diff(&old, &new);
+            assert!(changes.iter().all(|c| matches!(c, Change::Move(_))));
+            assert_changeset_valid(&old, &new, &changes);
+        }
+
+        #[test]
+        fn myers_k_edits((old, new) in k_edits(64, 4, 8)) {
+            let changes = myers::diff_myers(&old, &new);
+            assert!(changes.len() <= 4);
+            assert_changeset_valid(&old, &new, &changes);
+        }
+
+        #[test]
+        fn diff_duplicates((old, new) in duplicates(48)) {
+            assert_changeset_valid(&old, &new, &hackel::diff(&old, &new));
+        }
+    }
+
+    #[test]
+    fn invalid_batch() {
+        let old = [1, 2];
+        let changes = hackel::diff(&old, &[1, 2, 3]);
+        assert!(apply_batch(&old, 2, &changes).is_none());
+    }
+}
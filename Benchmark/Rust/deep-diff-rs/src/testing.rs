@@ -0,0 +1,32 @@
+//! Inputs and projections shared by the unit tests.
+
+use crate::hackel::Change;
+
+/// The base-3 digits, lowest first, of every number below `3^max_len`: small
+/// sequences over `0..3` for exhaustive checks.
+pub(crate) fn ternary_inputs(max_len: u32) -> Vec<Vec<u8>> {
+    (0..3u32.pow(max_len))
+        .map(|mut n| {
+            let mut items = Vec::new();
+            while n > 0 {
+                items.push((n % 3) as u8);
+                n /= 3;
+            }
+            items
+        })
+        .collect()
+}
+
+/// Each change as `(kind, index, value)`, where the value is the (new) item
+/// or, for a move, its new index.
+pub(crate) fn describe(changes: &[Change<'_, u8>]) -> Vec<(char, usize, usize)> {
+    changes
+        .iter()
+        .map(|change| match change {
+            Change::Insert(i) => ('i', i.index, *i.item as usize),
+            Change::Delete(d) => ('d', d.index, *d.item as usize),
+            Change::Replace(r) => ('r', r.index, *r.new_item as usize),
+            Change::Move(m) => ('m', m.from_index, m.to_index),
+        })
+        .collect()
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::hackel::{self, Delete, Index, Insert, Step};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TimedChange<'a, T> {
+    Insert(Insert<'a, T>),
+    Delete(Delete<'a, T>),
+    Shift(Shift<'a, T>),
+}
+
+/// An event matched across the two logs whose timestamp or position changed.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Shift<'a, T> {
+    pub old_item: &'a T,
+    pub new_item: &'a T,
+    pub from_index: usize,
+    pub to_index: usize,
+    /// New timestamp minus old timestamp.
+    pub delta: i64,
+}
+
+/// Old events of one identity, sorted by timestamp.
+struct Candidates {
+    events: Vec<(i64, usize)>,
+    taken: Vec<bool>,
+}
+
+impl Candidates {
+    /// Takes the untaken event closest to `timestamp`, earliest first on ties.
+    fn take_nearest(&mut self, timestamp: i64, tolerance: i64) -> Option<usize> {
+        let start = self.events.partition_point(|(t, _)| *t < timestamp.saturating_sub(tolerance));
+        let mut best: Option<(u64, usize)> = None;
+        for (slot, (t, _)) in self.events.iter().enumerate().skip(start) {
+            if *t > timestamp.saturating_add(tolerance) {
+                break;
+            }
+            let distance = t.abs_diff(timestamp);
+            if !self.taken[slot] && best.is_none_or(|(closest, _)| distance < closest) {
+                best = Some((distance, slot));
+            }
+        }
+        best.map(|(_, slot)| {
+            self.taken[slot] = true;
+            self.events[slot].1
+        })
+    }
+}
+
+/// Diffs two timestamped logs, such as the same events recorded by two
+/// systems with clock skew. `key` returns an event's identity and timestamp;
+/// events of equal identity match when their timestamps are at most
+/// `tolerance` apart, nearest first. Matched events whose timestamp or
+/// position changed are reported as `Shift`s; indices follow `diff`.
+pub fn diff_shifted<'a, T, K, F>(old: &'a [T], new: &'a [T], tolerance: i64, mut key: F) -> Vec<TimedChange<'a, T>>
+where
+    K: Eq + Hash,
+    F: FnMut(&T) -> (K, i64),
+{
+    let mut candidates: HashMap<K, Candidates> = HashMap::new();
+    let mut old_times = Vec::with_capacity(old.len());
+    for (idx, item) in old.iter().enumerate() {
+        let (identity, timestamp) = key(item);
+        let entry = candidates.entry(identity).or_insert_with(|| Candidates { events: Vec::new(), taken: Vec::new() });
+        entry.events.push((timestamp, idx));
+        entry.taken.push(false);
+        old_times.push(timestamp);
+    }
+    for entry in candidates.values_mut() {
+        entry.events.sort();
+    }
+
+    let mut old_match = vec![usize::NONE; old.len()];
+    let mut new_match = vec![usize::NONE; new.len()];
+    let mut new_times = Vec::with_capacity(new.len());
+    for (new_idx, item) in new.iter().enumerate() {
+        let (identity, timestamp) = key(item);
+        new_times.push(timestamp);
+        if let Some(old_idx) = candidates.get_mut(&identity).and_then(|entry| entry.take_nearest(timestamp, tolerance)) {
+            old_match[old_idx] = new_idx;
+            new_match[new_idx] = old_idx;
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut delete_offsets = vec![0; old.len()];
+    hackel::walk_matches(&old_match, &new_match, &mut delete_offsets, |step| match step {
+        Step::Delete(index) => changes.push(TimedChange::Delete(Delete { item: &old[index], index })),
+        Step::Insert(index) => changes.push(TimedChange::Insert(Insert { item: &new[index], index })),
+        Step::Match { old_idx, new_idx, moved } => {
+            let delta = new_times[new_idx] - old_times[old_idx];
+            if moved || delta != 0 {
+                changes.push(TimedChange::Shift(Shift { old_item: &old[old_idx], new_item: &new[new_idx], from_index: old_idx, to_index: new_idx, delta }));
+            }
+        },
+    }, &mut ());
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shifts(changes: &[TimedChange<'_, (&str, i64)>]) -> Vec<(usize, usize, i64)> {
+        changes
+            .iter()
+            .filter_map(|change| match change {
+                TimedChange::Shift(shift) => Some((shift.from_index, shift.to_index, shift.delta)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn skewed_clock() {
+        let old = vec![("login", 1_000), ("click", 1_500), ("logout", 3_000)];
+        let new = vec![("login", 1_020), ("click", 1_480), ("logout", 3_000)];
+        let changes = diff_shifted(&old, &new, 50, |event| (event.0, event.1));
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(shifts(&changes), vec![(0, 0, 20), (1, 1, -20)]);
+    }
+
+    #[test]
+    fn outside_tolerance() {
+        let old = vec![("login", 1_000)];
+        let new = vec![("login", 1_100)];
+        let changes = diff_shifted(&old, &new, 50, |event| (event.0, event.1));
+
+        assert!(matches!(changes[..], [TimedChange::Delete(_), TimedChange::Insert(_)]));
+    }
+
+    #[test]
+    fn nearest_wins() {
+        let old = vec![("tick", 100), ("tick", 200), ("tick", 300)];
+        let new = vec![("tick", 205), ("tick", 310)];
+        let changes = diff_shifted(&old, &new, 50, |event| (event.0, event.1));
+
+        assert!(matches!(changes[0], TimedChange::Delete(ref delete) if delete.index == 0));
+        assert_eq!(shifts(&changes), vec![(1, 0, 5), (2, 1, 10)]);
+    }
+
+    #[test]
+    fn reordered_events() {
+        let old = vec![("a", 10), ("b", 20)];
+        let new = vec![("b", 12), ("a", 14)];
+        let changes = diff_shifted(&old, &new, 10, |event| (event.0, event.1));
+
+        assert_eq!(shifts(&changes), vec![(1, 0, -8), (0, 1, 4)]);
+    }
+}
@@ -0,0 +1,200 @@
+//! Token-level diffs of source code. A `Tokenizer` splits text into lexical
+//! tokens, dropping whitespace, so reformatting produces no changes and an
+//! edit is reported as the few tokens it touched rather than whole lines.
+
+use std::ops::Range;
+
+use crate::hackel::{self, Change};
+
+/// Splits source text into tokens.
+pub trait Tokenizer {
+    /// Byte ranges of the significant tokens of `source`, in order.
+    fn tokenize(&self, source: &str) -> Vec<Range<usize>>;
+}
+
+/// Rust: identifiers, lifetimes, literals (raw strings included), comments
+/// and single-character punctuation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rust;
+
+/// Swift: as `Rust`, with `"""` multi-line strings instead of raw strings.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Swift;
+
+/// JSON: strings, numbers, literals and structural characters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+/// Lexical features that differ between the supported languages.
+struct Syntax {
+    comments: bool,
+    raw_strings: bool,
+    char_literals: bool,
+    triple_quotes: bool,
+}
+
+impl Tokenizer for Rust {
+    fn tokenize(&self, source: &str) -> Vec<Range<usize>> {
+        lex(source, &Syntax { comments: true, raw_strings: true, char_literals: true, triple_quotes: false })
+    }
+}
+
+impl Tokenizer for Swift {
+    fn tokenize(&self, source: &str) -> Vec<Range<usize>> {
+        lex(source, &Syntax { comments: true, raw_strings: false, char_literals: false, triple_quotes: true })
+    }
+}
+
+impl Tokenizer for Json {
+    fn tokenize(&self, source: &str) -> Vec<Range<usize>> {
+        lex(source, &Syntax { comments: false, raw_strings: false, char_literals: false, triple_quotes: false })
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+fn lex(source: &str, syntax: &Syntax) -> Vec<Range<usize>> {
+    let bytes = source.as_bytes();
+    let char_at = |idx: usize| source[idx..].chars().next();
+    let starts_with = |idx: usize, prefix: &str| source[idx..].starts_with(prefix);
+    // End of the quoted string opening at `idx` with `quote`, past escapes.
+    let quoted = |mut idx: usize, quote: &str| {
+        while idx < bytes.len() && !starts_with(idx, quote) {
+            idx += if bytes[idx] == b'\\' { 1 + char_at(idx + 1).map_or(0, char::len_utf8) } else { char_at(idx).map_or(1, char::len_utf8) };
+        }
+        (idx + quote.len()).min(bytes.len())
+    };
+
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while let Some(c) = char_at(idx) {
+        let start = idx;
+        idx = if c.is_whitespace() {
+            idx += c.len_utf8();
+            continue;
+        } else if syntax.comments && starts_with(idx, "//") {
+            source[idx..].find('\n').map_or(bytes.len(), |end| idx + end)
+        } else if syntax.comments && starts_with(idx, "/*") {
+            // Block comments nest in both Rust and Swift.
+            let (mut depth, mut end) = (0, idx);
+            while end < bytes.len() {
+                if starts_with(end, "/*") {
+                    depth += 1;
+                    end += 2;
+                } else if starts_with(end, "*/") {
+                    depth -= 1;
+                    end += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    end += char_at(end).map_or(1, char::len_utf8);
+                }
+            }
+            end.min(bytes.len())
+        } else if syntax.triple_quotes && starts_with(idx, "\"\"\"") {
+            quoted(idx + 3, "\"\"\"")
+        } else if c == '"' {
+            quoted(idx + 1, "\"")
+        } else if syntax.raw_strings && c == 'r' && (starts_with(idx + 1, "\"") || starts_with(idx + 1, "#")) && source[idx + 1..].trim_start_matches('#').starts_with('"') {
+            let hashes = source[idx + 1..].len() - source[idx + 1..].trim_start_matches('#').len();
+            let close = format!("\"{}", "#".repeat(hashes));
+            let body = idx + 2 + hashes;
+            source[body..].find(&close).map_or(bytes.len(), |end| body + end + close.len())
+        } else if syntax.char_literals && c == '\'' {
+            // A char literal closes within a few bytes; otherwise a lifetime.
+            match source[idx + 1..].char_indices().nth(1) {
+                Some((offset, '\'')) if bytes[idx + 1] != b'\\' => idx + 1 + offset + 1,
+                _ if starts_with(idx + 1, "\\") => quoted(idx + 1, "'"),
+                _ => idx + 1 + source[idx + 1..].find(|c: char| !is_word(c)).unwrap_or(bytes.len() - idx - 1),
+            }
+        } else if c.is_ascii_digit() || (c == '-' && !syntax.comments && char_at(idx + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let mut end = idx + 1;
+            while let Some(c) = char_at(end) {
+                let exponent_sign = (c == '+' || c == '-') && matches!(bytes[end - 1], b'e' | b'E') && !syntax.comments;
+                let fraction = c == '.' && char_at(end + 1).is_some_and(|c| c.is_ascii_digit());
+                if !(is_word(c) || fraction || exponent_sign) {
+                    break;
+                }
+                end += c.len_utf8();
+            }
+            end
+        } else if is_word(c) {
+            idx + source[idx..].find(|c: char| !is_word(c)).unwrap_or(bytes.len() - idx)
+        } else {
+            idx + c.len_utf8()
+        };
+        tokens.push(start..idx);
+    }
+    tokens
+}
+
+/// A token change addressed by byte ranges into the sources.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum TokenChange {
+    Insert { index: usize, new_range: Range<usize> },
+    Delete { index: usize, old_range: Range<usize> },
+    Move { from_index: usize, to_index: usize, old_range: Range<usize>, new_range: Range<usize> },
+}
+
+/// Diffs the tokens of `old` and `new` as `diff` does; indices count
+/// tokens. Whitespace between tokens never shows up in the changes.
+pub fn diff_tokens<K: Tokenizer + ?Sized>(tokenizer: &K, old: &str, new: &str) -> Vec<TokenChange> {
+    let old_ranges = tokenizer.tokenize(old);
+    let new_ranges = tokenizer.tokenize(new);
+    let old_tokens = old_ranges.iter().map(|range| &old[range.clone()]).collect::<Vec<_>>();
+    let new_tokens = new_ranges.iter().map(|range| &new[range.clone()]).collect::<Vec<_>>();
+
+    let mut changes = Vec::new();
+    hackel::diff_with(&old_tokens, &new_tokens, |change| match change {
+        Change::Insert(i) => changes.push(TokenChange::Insert { index: i.index, new_range: new_ranges[i.index].clone() }),
+        Change::Delete(d) => changes.push(TokenChange::Delete { index: d.index, old_range: old_ranges[d.index].clone() }),
+        Change::Move(m) => changes.push(TokenChange::Move {
+            from_index: m.from_index,
+            to_index: m.to_index,
+            old_range: old_ranges[m.from_index].clone(),
+            new_range: new_ranges[m.to_index].clone(),
+        }),
+        Change::Replace(_) => unreachable!("equal tokens never replace"),
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(tokenizer: &dyn Tokenizer, source: &'a str) -> Vec<&'a str> {
+        tokenizer.tokenize(source).into_iter().map(|range| &source[range]).collect()
+    }
+
+    #[test]
+    fn rust_tokens() {
+        let source = "fn f<'a>(x: &'a str) -> char { /* a /* nested */ note */ let s = r#\"q\"\"#; 'x' } // end";
+        assert_eq!(texts(&Rust, source), vec![
+            "fn", "f", "<", "'a", ">", "(", "x", ":", "&", "'a", "str", ")", "-", ">", "char", "{",
+            "/* a /* nested */ note */", "let", "s", "=", "r#\"q\"\"#", ";", "'x'", "}", "// end",
+        ]);
+        assert_eq!(texts(&Rust, "0..1.5e3 '\\n'"), vec!["0", ".", ".", "1.5e3", "'\\n'"]);
+        assert_eq!(texts(&Rust, "/* ü */ 'é' '"), vec!["/* ü */", "'é'", "'"]);
+    }
+
+    #[test]
+    fn swift_and_json_tokens() {
+        assert_eq!(texts(&Swift, "let s = \"\"\"\na \"b\"\n\"\"\" // c"), vec!["let", "s", "=", "\"\"\"\na \"b\"\n\"\"\"", "// c"]);
+        assert_eq!(texts(&Json, "{\"a\\\"b\": [-1.5e+3, true]}"), vec!["{", "\"a\\\"b\"", ":", "[", "-1.5e+3", ",", "true", "]", "}"]);
+    }
+
+    #[test]
+    fn reformatting_is_not_a_change() {
+        let old = "fn main() {\n    let x = 1;\n}\n";
+        let new = "fn main() { let x = 2; }";
+        assert_eq!(diff_tokens(&Rust, old, new), vec![
+            TokenChange::Delete { index: 8, old_range: 24..25 },
+            TokenChange::Insert { index: 8, new_range: 20..21 },
+        ]);
+        assert!(diff_tokens(&Json, "{\"a\": 1}", "{\n  \"a\" : 1\n}").is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+//! Optional `tracing` instrumentation. Without the `tracing` feature every
+//! guard is zero-sized and every call compiles away.
+
+/// Span covering one pass of the symbol table algorithm. It closes on `end` or drop.
+pub(crate) struct Pass {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl Pass {
+    #[inline]
+    #[allow(unused_variables)]
+    pub(crate) fn enter(pass: usize, items: usize) -> Pass {
+        Pass {
+            #[cfg(feature = "tracing")]
+            _span: tracing::trace_span!("heckel_pass", pass, items).entered(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn end(self) {}
+}
+
+/// Span covering a whole diff.
+pub(crate) struct Diff {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl Diff {
+    #[inline]
+    #[allow(unused_variables)]
+    pub(crate) fn enter(old_len: usize, new_len: usize) -> Diff {
+        Diff {
+            #[cfg(feature = "tracing")]
+            _span: tracing::debug_span!("diff", old_len, new_len).entered(),
+        }
+    }
+}
+
+/// Records an allocation of `len` elements of `T` made for `what`.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn alloc<T>(what: &'static str, len: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(what, len, bytes = len * std::mem::size_of::<T>(), "allocated");
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::hackel;
+
+    /// Collects the names of opened spans.
+    struct Spans(Arc<Mutex<Vec<String>>>);
+
+    impl Subscriber for Spans {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            spans.push(span.metadata().name().to_string());
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn spans_per_pass() {
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        let old = (0..100).collect::<Vec<_>>();
+        let new = (50..150).collect::<Vec<_>>();
+
+        tracing::subscriber::with_default(Spans(spans.clone()), || hackel::diff(&old, &new));
+
+        let spans = spans.lock().unwrap();
+        assert_eq!(spans[0], "diff");
+        assert_eq!(spans.iter().filter(|name| *name == "heckel_pass").count(), 5);
+    }
+}
@@ -0,0 +1,156 @@
+//! Undo and redo for a list model. Each edit is stored as the changeset
+//! leading to it and its inverse rather than as a snapshot, so history
+//! costs memory in proportion to what changed.
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::apply::{apply_to_vec, invert};
+use crate::changeset::CowChange;
+use crate::hackel;
+
+/// One recorded edit.
+struct Edit<T: Clone + 'static> {
+    forward: Vec<CowChange<'static, T>>,
+    backward: Vec<CowChange<'static, T>>,
+}
+
+impl<T: Clone + Eq + Hash + 'static> Edit<T> {
+    fn between(old: &[T], new: &[T]) -> Option<Self> {
+        let forward = hackel::diff(old, new);
+        if forward.is_empty() {
+            return None;
+        }
+        let backward = invert(old.len(), &forward);
+        let owned = |changes: Vec<_>| changes.into_iter().map(|change| CowChange::from(change).into_owned()).collect();
+        Some(Edit { forward: owned(forward), backward: owned(backward) })
+    }
+}
+
+fn apply<T: Clone + 'static>(items: &mut Vec<T>, changes: &[CowChange<'static, T>]) {
+    apply_to_vec(items, &changes.iter().map(CowChange::as_change).collect::<Vec<_>>());
+}
+
+/// A list with a bounded undo history. Recording a new state clears the redo
+/// stack; past `limit` steps the oldest is forgotten.
+pub struct UndoHistory<T: Clone + 'static> {
+    items: Vec<T>,
+    undo: VecDeque<Edit<T>>,
+    redo: Vec<Edit<T>>,
+    limit: usize,
+}
+
+impl<T: Clone + Eq + Hash + 'static> UndoHistory<T> {
+    pub fn new(items: Vec<T>, limit: usize) -> Self {
+        UndoHistory { items, undo: VecDeque::new(), redo: Vec::new(), limit }
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Number of steps `undo` can take.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Number of steps `redo` can take.
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Replaces the items with `new` as one undoable step. Recording the
+    /// current items again is a no-op.
+    pub fn record(&mut self, new: Vec<T>) {
+        let Some(edit) = Edit::between(&self.items, &new) else {
+            return;
+        };
+        self.items = new;
+        self.redo.clear();
+        self.undo.push_back(edit);
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Same as `record`, but merges the change into the latest step, so a
+    /// burst of small edits (typing, dragging) undoes at once and is stored
+    /// as its net changes.
+    pub fn record_coalesced(&mut self, new: Vec<T>) {
+        let Some(last) = self.undo.pop_back() else {
+            return self.record(new);
+        };
+        let mut before = self.items.clone();
+        apply(&mut before, &last.backward);
+        self.items = new;
+        self.redo.clear();
+        if let Some(edit) = Edit::between(&before, &self.items) {
+            self.undo.push_back(edit);
+        }
+    }
+
+    /// Reverts the latest step; returns false if there is none.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        apply(&mut self.items, &edit.backward);
+        self.redo.push(edit);
+        true
+    }
+
+    /// Reapplies the latest undone step; returns false if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        apply(&mut self.items, &edit.forward);
+        self.undo.push_back(edit);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = UndoHistory::new(vec!["a", "b", "c"], 2);
+        history.record(vec!["c", "a", "b"]);
+        history.record(vec!["c", "x", "b"]);
+        history.record(vec!["c", "x", "b", "d"]);
+        assert_eq!(history.undo_len(), 2);
+
+        assert!(history.undo());
+        assert_eq!(history.items(), ["c", "x", "b"]);
+        assert!(history.undo());
+        assert_eq!(history.items(), ["c", "a", "b"]);
+        assert!(!history.undo());
+
+        assert!(history.redo());
+        assert_eq!(history.items(), ["c", "x", "b"]);
+        history.record(vec!["x"]);
+        assert!(!history.redo());
+    }
+
+    #[test]
+    fn coalesces_into_latest() {
+        let mut history = UndoHistory::new(vec!["h"], 10);
+        history.record(vec!["h", "e"]);
+        history.record_coalesced(vec!["h", "e", "l"]);
+        history.record_coalesced(vec!["h", "e", "l", "p"]);
+        assert_eq!(history.undo_len(), 1);
+        assert!(history.undo());
+        assert_eq!(history.items(), ["h"]);
+
+        // Coalescing back to the earlier state leaves no step behind.
+        history.redo();
+        history.record_coalesced(vec!["h"]);
+        assert_eq!(history.undo_len(), 0);
+    }
+}
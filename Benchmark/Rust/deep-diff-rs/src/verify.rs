@@ -0,0 +1,149 @@
+use std::hash::Hash;
+
+use crate::algorithm::{self, Algorithm};
+use crate::error::DiffError;
+use crate::hackel::{Change, Delete, Insert};
+
+/// Minimal delete/insert script from a full O(n·m) longest common
+/// subsequence table. Slow, but simple enough to be obviously correct.
+pub fn reference_diff<'a, T: PartialEq>(old: &'a [T], new: &'a [T]) -> Vec<Change<'a, T>> {
+    let mut table = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i][j] = if old[i] == new[j] { table[i + 1][j + 1] + 1 } else { table[i + 1][j].max(table[i][j + 1]) };
+        }
+    }
+
+    let mut kept_old = vec![false; old.len()];
+    let mut kept_new = vec![false; new.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            kept_old[i] = true;
+            kept_new[j] = true;
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let deletes = old.iter().enumerate().filter(|(index, _)| !kept_old[*index]).map(|(index, item)| Change::Delete(Delete { item, index }));
+    let inserts = new.iter().enumerate().filter(|(index, _)| !kept_new[*index]).map(|(index, item)| Change::Insert(Insert { item, index }));
+    deletes.chain(inserts).collect()
+}
+
+/// Number of new items whose k-th occurrence has a k-th occurrence in old,
+/// counted by brute force.
+fn shared_occurrences<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    new.iter()
+        .enumerate()
+        .filter(|(idx, item)| {
+            let occurrence = new[..*idx].iter().filter(|other| other == item).count();
+            old.iter().filter(|other| other == item).count() > occurrence
+        })
+        .count()
+}
+
+/// Applies a batch of changes to references into `old`.
+fn reproduce<'a, T>(old: &'a [T], new_len: usize, changes: &[Change<'a, T>]) -> Option<Vec<&'a T>> {
+    let mut slots = vec![None; new_len];
+    let mut consumed = vec![false; old.len()];
+    for change in changes {
+        match change {
+            Change::Delete(delete) => *consumed.get_mut(delete.index)? = true,
+            Change::Insert(insert) => *slots.get_mut(insert.index)? = Some(insert.item),
+            Change::Move(mv) => {
+                *consumed.get_mut(mv.from_index)? = true;
+                *slots.get_mut(mv.to_index)? = Some(old.get(mv.from_index)?);
+            },
+            Change::Replace(_) => {},
+        }
+    }
+
+    let mut survivors = old.iter().zip(&consumed).filter(|(_, consumed)| !**consumed).map(|(item, _)| item);
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = Some(survivors.next()?);
+    }
+    if survivors.next().is_some() {
+        return None;
+    }
+    for change in changes {
+        if let Change::Replace(replace) = change {
+            *slots.get_mut(replace.index)? = Some(replace.new_item);
+        }
+    }
+    slots.into_iter().collect()
+}
+
+/// Checks `changes` from `algorithm` against the reference: they must turn
+/// `old` into `new`, and their deletes and inserts must number what the
+/// algorithm promises. Myers must be as short as the LCS script; Heckel and
+/// Small must keep every shared occurrence.
+pub fn verify<T: Eq + Hash>(old: &[T], new: &[T], changes: &[Change<'_, T>], algorithm: Algorithm) -> Result<(), DiffError> {
+    match reproduce(old, new.len(), changes) {
+        Some(applied) if applied.iter().copied().eq(new) => {},
+        _ => return Err(DiffError::NotReproduced),
+    }
+
+    let edits = changes.iter().filter(|change| matches!(change, Change::Insert(_) | Change::Delete(_))).count();
+    let expected = match algorithm.select(old, new) {
+        Algorithm::Myers => reference_diff(old, new).len(),
+        _ => old.len() + new.len() - 2 * shared_occurrences(old, new),
+    };
+    if edits != expected {
+        return Err(DiffError::EditCountMismatch { edits, expected });
+    }
+    Ok(())
+}
+
+/// Runs `algorithm` and checks its output with `verify`. Quadratic; meant
+/// for tests and debugging of the fast backends.
+pub fn diff_verified<'a, T: Eq + Hash>(old: &'a [T], new: &'a [T], algorithm: Algorithm) -> Result<Vec<Change<'a, T>>, DiffError> {
+    let changes = algorithm::diff_with_algorithm(old, new, algorithm);
+    verify(old, new, &changes, algorithm)?;
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel::Move;
+
+    const ALGORITHMS: [Algorithm; 4] = [Algorithm::Heckel, Algorithm::Myers, Algorithm::Small, Algorithm::Auto];
+
+    #[test]
+    fn backends_agree() {
+        let old = (0..300).map(|i| (i * 7) % 50).collect::<Vec<_>>();
+        let new = (0..280).map(|i| (i * 3) % 60).collect::<Vec<_>>();
+        let cases: &[(&[u32], &[u32])] = &[(&[], &[]), (&[1, 2, 3], &[3, 1, 2]), (&[1, 1, 2], &[2, 1, 4, 1]), (&old, &new)];
+
+        for (old, new) in cases {
+            for algorithm in ALGORITHMS {
+                assert!(diff_verified(old, new, algorithm).is_ok(), "{:?} {:?} -> {:?}", algorithm, old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn reference_is_minimal() {
+        let old = b"ABCABBA";
+        let new = b"CBABAC";
+        assert_eq!(reference_diff(old, new).len(), 5);
+        assert_eq!(reference_diff(old, old).len(), 0);
+    }
+
+    #[test]
+    fn divergence() {
+        let old = vec![1, 2, 3];
+        let new = vec![3, 1, 2];
+        let mut changes = algorithm::diff_with_algorithm(&old, &new, Algorithm::Heckel);
+        assert!(verify(&old, &new, &changes, Algorithm::Heckel).is_ok());
+        assert_eq!(verify(&old, &new, &changes, Algorithm::Myers), Err(DiffError::EditCountMismatch { edits: 0, expected: 2 }));
+
+        changes.push(Change::Move(Move { item: &old[0], from_index: 0, to_index: 2 }));
+        assert_eq!(verify(&old, &new, &changes, Algorithm::Heckel), Err(DiffError::NotReproduced));
+    }
+}
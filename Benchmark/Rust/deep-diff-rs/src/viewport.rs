@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::hackel::Change;
+use crate::merge;
+
+/// What a virtualized list has to redraw after a batch update.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct RenderPlan {
+    /// Rows of the new list that become visible once scrolled by `scroll_by`.
+    pub visible: Range<usize>,
+    /// Rows by which to shift the scroll offset so the first visible item
+    /// that kept its place stays on screen where it was.
+    pub scroll_by: isize,
+    /// New indices in `visible`, ascending, whose screen slot now shows a
+    /// different or changed item. Every other visible row can be reused.
+    pub rows: Vec<usize>,
+}
+
+/// Plans the redraw of the `visible` rows of a list of `old_len` items
+/// after `changes`. Scrolling keeps the first visible item that kept its
+/// place, or stays put if none did.
+///
+/// # Panics
+///
+/// Panics if `changes` do not describe a batch update of `old_len` items.
+pub fn plan_render<T>(old_len: usize, changes: &[Change<'_, T>], visible: Range<usize>) -> RenderPlan {
+    let side = merge::replay(old_len, changes, &mut HashMap::new());
+    let new_len = side.entries.len();
+
+    let mut new_position = vec![None; old_len];
+    for (new_idx, entry) in side.entries.iter().enumerate() {
+        if let (Some(base), false) = (entry.base, entry.moved) {
+            new_position[base] = Some(new_idx);
+        }
+    }
+    // The viewport may extend past the end of the list; only the rows
+    // holding an item can anchor the scroll.
+    let scroll_by = (visible.start.min(old_len)..visible.end.min(old_len))
+        .find_map(|old_idx| new_position[old_idx].map(|new_idx| new_idx as isize - old_idx as isize))
+        .unwrap_or(0);
+
+    let shift = |idx: usize| ((idx as isize + scroll_by).max(0) as usize).min(new_len);
+    let new_visible = shift(visible.start)..shift(visible.end);
+    let rows = new_visible
+        .clone()
+        .filter(|new_idx| {
+            let entry = &side.entries[*new_idx];
+            let old_idx = *new_idx as isize - scroll_by;
+            entry.item.is_some() || entry.base.map(|base| base as isize) != Some(old_idx)
+        })
+        .collect();
+    RenderPlan { visible: new_visible, scroll_by, rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hackel;
+
+    #[test]
+    fn inserts_above_scroll_away() {
+        let old = (0..100).collect::<Vec<u32>>();
+        let mut new = vec![1000, 1001, 1002];
+        new.extend(&old);
+        let plan = plan_render(old.len(), &hackel::diff(&old, &new), 40..50);
+        assert_eq!(plan, RenderPlan { visible: 43..53, scroll_by: 3, rows: vec![] });
+    }
+
+    #[test]
+    fn only_changed_rows() {
+        let old = (0..20).collect::<Vec<u32>>();
+        let mut new = old.clone();
+        new.remove(12);
+        new.insert(14, 99);
+        new.swap(7, 8);
+        let plan = plan_render(old.len(), &hackel::diff(&old, &new), 5..15);
+        assert_eq!(plan.scroll_by, 0);
+        assert_eq!(plan.visible, 5..15);
+        // The swap, then the rows shifted up by the delete until the insert.
+        assert_eq!(plan.rows, vec![7, 8, 12, 13, 14]);
+    }
+
+    #[test]
+    fn nothing_kept() {
+        let old = vec![1, 2, 3];
+        let new = vec![4, 5];
+        let plan = plan_render(old.len(), &hackel::diff(&old, &new), 0..3);
+        assert_eq!(plan, RenderPlan { visible: 0..2, scroll_by: 0, rows: vec![0, 1] });
+    }
+
+    #[test]
+    fn viewport_past_the_end() {
+        let old = vec![1, 2, 3];
+        let new = vec![4, 5, 6, 7];
+        let plan = plan_render(old.len(), &hackel::diff(&old, &new), 0..10);
+        assert_eq!(plan, RenderPlan { visible: 0..4, scroll_by: 0, rows: vec![0, 1, 2, 3] });
+
+        let new = vec![0, 1, 2, 3];
+        let plan = plan_render(old.len(), &hackel::diff(&old, &new), 2..10);
+        assert_eq!(plan, RenderPlan { visible: 3..4, scroll_by: 1, rows: vec![] });
+    }
+}
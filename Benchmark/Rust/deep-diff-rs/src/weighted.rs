@@ -0,0 +1,197 @@
+use crate::hackel::{self, Change, Index, Step};
+
+/// Price of each kind of change. A replace that also moves pays for both.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Costs {
+    pub insert: u64,
+    pub delete: u64,
+    pub replace: u64,
+    pub r#move: u64,
+}
+
+/// Unit inserts and deletes, with replaces and moves no cheaper than the
+/// delete and insert they stand for: the minimal delete/insert script.
+impl Default for Costs {
+    fn default() -> Self {
+        Costs { insert: 1, delete: 1, replace: 2, r#move: 2 }
+    }
+}
+
+impl Costs {
+    /// Sum of the costs of `changes`.
+    pub fn total<T, I>(&self, changes: &[Change<'_, T, I>]) -> u64 {
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Insert(_) => self.insert,
+                Change::Delete(_) => self.delete,
+                Change::Replace(_) => self.replace,
+                Change::Move(_) => self.r#move,
+            })
+            .sum()
+    }
+
+    /// Cost of the changes `walk_matches` would emit for this matching.
+    fn of_matches<T: PartialEq>(&self, old: &[T], new: &[T], old_match: &[usize], new_match: &[usize]) -> u64 {
+        let mut delete_offsets = vec![0; old_match.len()];
+        let mut total = 0;
+        hackel::walk_matches(old_match, new_match, &mut delete_offsets, |step| {
+            total += match step {
+                Step::Delete(_) => self.delete,
+                Step::Insert(_) => self.insert,
+                Step::Match { old_idx, new_idx, moved } => {
+                    (old[old_idx] != new[new_idx]) as u64 * self.replace + moved as u64 * self.r#move
+                },
+            }
+        }, &mut ());
+        total
+    }
+}
+
+/// Aligns the inputs with an O(n·m) edit distance table priced by `costs`,
+/// where a replace pairs two different items in place. Ties keep equal items
+/// first and prefer deleting and inserting over replacing.
+fn align<T: Eq>(old: &[T], new: &[T], costs: &Costs) -> (Vec<usize>, Vec<usize>) {
+    let width = new.len() + 1;
+    let mut table = vec![0u64; (old.len() + 1) * width];
+    for i in (0..=old.len()).rev() {
+        for j in (0..=new.len()).rev() {
+            table[i * width + j] = if i == old.len() {
+                (new.len() - j) as u64 * costs.insert
+            } else if j == new.len() {
+                (old.len() - i) as u64 * costs.delete
+            } else {
+                let pair = table[(i + 1) * width + j + 1] + if old[i] == new[j] { 0 } else { costs.replace };
+                let delete = table[(i + 1) * width + j] + costs.delete;
+                let insert = table[i * width + j + 1] + costs.insert;
+                pair.min(delete).min(insert)
+            };
+        }
+    }
+
+    let mut old_match = vec![usize::NONE; old.len()];
+    let mut new_match = vec![usize::NONE; new.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        let here = table[i * width + j];
+        if old[i] == new[j] && here == table[(i + 1) * width + j + 1] {
+            old_match[i] = j;
+            new_match[j] = i;
+            i += 1;
+            j += 1;
+        } else if here == table[(i + 1) * width + j] + costs.delete {
+            i += 1;
+        } else if here == table[i * width + j + 1] + costs.insert {
+            j += 1;
+        } else {
+            old_match[i] = j;
+            new_match[j] = i;
+            i += 1;
+            j += 1;
+        }
+    }
+    (old_match, new_match)
+}
+
+/// Diff minimizing the total of `costs` instead of the number of changes.
+///
+/// The alignment step never moves items. Afterwards each inserted item is
+/// paired with an equal deleted one whenever that lowers the total; under
+/// batch indexing every item shifted by such a pairing counts as moved too,
+/// so moves only win when they are much cheaper than a delete and insert.
+///
+/// Quadratic in time and memory; meant for short sequences where the exact
+/// trade-off matters, such as UI animations or audit logs.
+pub fn diff_weighted<'a, T: Eq>(old: &'a [T], new: &'a [T], costs: &Costs) -> Vec<Change<'a, T>> {
+    let (mut old_match, mut new_match) = align(old, new, costs);
+
+    if costs.r#move < costs.delete + costs.insert {
+        let mut total = costs.of_matches(old, new, &old_match, &new_match);
+        for new_idx in 0..new.len() {
+            if new_match[new_idx] != usize::NONE {
+                continue;
+            }
+            let candidate = (0..old.len()).find(|old_idx| old_match[*old_idx] == usize::NONE && old[*old_idx] == new[new_idx]);
+            if let Some(old_idx) = candidate {
+                old_match[old_idx] = new_idx;
+                new_match[new_idx] = old_idx;
+                let paired = costs.of_matches(old, new, &old_match, &new_match);
+                if paired < total {
+                    total = paired;
+                } else {
+                    old_match[old_idx] = usize::NONE;
+                    new_match[new_idx] = usize::NONE;
+                }
+            }
+        }
+    }
+
+    hackel::changes_from_matches(old, new, &old_match, &new_match)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+
+    use crate::apply::apply_to_vec;
+
+    fn assert_applies<T: Clone + PartialEq + Debug>(old: &[T], new: &[T], changes: &[Change<'_, T>]) {
+        let mut model = old.to_vec();
+        apply_to_vec(&mut model, changes);
+        assert_eq!(model, new);
+    }
+
+    #[test]
+    fn default_is_minimal() {
+        let old = b"ABCABBA".to_vec();
+        let new = b"CBABAC".to_vec();
+        let changes = diff_weighted(&old, &new, &Costs::default());
+        assert_eq!(changes.len(), 5);
+        assert_applies(&old, &new, &changes);
+    }
+
+    #[test]
+    fn cheap_replace() {
+        let old = vec!["a", "b", "c"];
+        let new = vec!["a", "x", "c"];
+        let costs = Costs { replace: 1, ..Costs::default() };
+        let changes = diff_weighted(&old, &new, &costs);
+        assert_eq!(costs.total(&changes), 1);
+        assert!(matches!(changes[..], [Change::Replace(ref r)] if r.index == 1));
+        assert_applies(&old, &new, &changes);
+    }
+
+    #[test]
+    fn expensive_replace() {
+        let old = vec!["a", "b"];
+        let new = vec!["x", "y"];
+        let costs = Costs { replace: 10, ..Costs::default() };
+        let changes = diff_weighted(&old, &new, &costs);
+        assert_eq!(costs.total(&changes), 4);
+        assert!(changes.iter().all(|change| matches!(change, Change::Insert(_) | Change::Delete(_))));
+    }
+
+    #[test]
+    fn free_moves() {
+        let old = vec![1, 2, 3];
+        let new = vec![3, 1, 2];
+        let costs = Costs { r#move: 0, ..Costs::default() };
+        let changes = diff_weighted(&old, &new, &costs);
+        assert_eq!(costs.total(&changes), 0);
+        assert!(changes.iter().all(|change| matches!(change, Change::Move(_))));
+        assert_applies(&old, &new, &changes);
+
+        let changes = diff_weighted(&old, &new, &Costs::default());
+        assert_eq!(Costs::default().total(&changes), 2);
+    }
+
+    #[test]
+    fn applies() {
+        let old = (0..60).map(|i| (i * 7) % 13).collect::<Vec<_>>();
+        let new = (0..50).map(|i| (i * 5) % 11).collect::<Vec<_>>();
+        for costs in [Costs::default(), Costs { replace: 1, ..Costs::default() }, Costs { r#move: 0, replace: 3, ..Costs::default() }] {
+            assert_applies(&old, &new, &diff_weighted(&old, &new, &costs));
+        }
+    }
+}
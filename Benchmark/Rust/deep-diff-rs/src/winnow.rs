@@ -0,0 +1,101 @@
+//! Winnowing (Schleimer, Wilkerson and Aiken): a small set of k-gram hashes
+//! per document that is guaranteed to share a fingerprint with any other
+//! document containing a common run of `k + window - 1` items. Comparing
+//! fingerprint sets tells cheaply whether two documents are worth a diff.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// Hash of the k-gram starting at `position`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct Fingerprint {
+    pub hash: u64,
+    pub position: usize,
+}
+
+const BASE: u64 = 0x100_0000_01b3;
+
+/// Rolling hashes of every `k`-gram of `items`; a sequence shorter than `k`
+/// is one gram.
+fn grams<T: Hash>(items: &[T], k: usize) -> Vec<u64> {
+    let k = k.max(1).min(items.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    let hashes = items
+        .iter()
+        .map(|item| {
+            let mut hasher = DefaultHasher::new();
+            item.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect::<Vec<_>>();
+
+    let top = (1..k).fold(1u64, |power, _| power.wrapping_mul(BASE));
+    let mut hash = hashes[..k].iter().fold(0u64, |hash, item| hash.wrapping_mul(BASE).wrapping_add(*item));
+    let mut grams = vec![hash];
+    for idx in k..hashes.len() {
+        hash = hash.wrapping_sub(hashes[idx - k].wrapping_mul(top)).wrapping_mul(BASE).wrapping_add(hashes[idx]);
+        grams.push(hash);
+    }
+    grams
+}
+
+/// Winnowed fingerprints of `items`: of every `window` consecutive k-gram
+/// hashes, the smallest (the rightmost on ties), each recorded once.
+pub fn fingerprints<T: Hash>(items: &[T], k: usize, window: usize) -> Vec<Fingerprint> {
+    let grams = grams(items, k);
+    let mut selected: Vec<Fingerprint> = Vec::new();
+    if grams.is_empty() {
+        return selected;
+    }
+    for (start, hashes) in grams.windows(window.max(1).min(grams.len())).enumerate() {
+        let (offset, hash) = hashes.iter().enumerate().rev().min_by_key(|(_, hash)| **hash).expect("windows are not empty");
+        let position = start + offset;
+        if selected.last().is_none_or(|last| last.position != position) {
+            selected.push(Fingerprint { hash: *hash, position });
+        }
+    }
+    selected
+}
+
+/// Jaccard similarity of the fingerprint hashes, from 0 (nothing shared) to
+/// 1; two empty sets are identical.
+pub fn similarity(a: &[Fingerprint], b: &[Fingerprint]) -> f64 {
+    let a = a.iter().map(|print| print.hash).collect::<HashSet<_>>();
+    let b = b.iter().map(|print| print.hash).collect::<HashSet<_>>();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_runs_share_fingerprints() {
+        let text = "the quick brown fox jumps over the lazy dog".chars().collect::<Vec<_>>();
+        let edited = "a quick brown fox jumped over the lazy cat".chars().collect::<Vec<_>>();
+        let other = "lorem ipsum dolor sit amet consectetur elit".chars().collect::<Vec<_>>();
+        let (k, window) = (5, 4);
+
+        let prints = fingerprints(&text, k, window);
+        assert!(prints.len() < text.len() / 2);
+        assert!(prints.windows(2).all(|pair| pair[0].position < pair[1].position));
+        assert_eq!(similarity(&prints, &prints), 1.0);
+        let near = similarity(&prints, &fingerprints(&edited, k, window));
+        let far = similarity(&prints, &fingerprints(&other, k, window));
+        assert!(near > 0.3 && far < 0.1, "{} {}", near, far);
+    }
+
+    #[test]
+    fn short_and_empty() {
+        assert_eq!(fingerprints(&[1, 2], 5, 4).len(), 1);
+        assert!(fingerprints::<u8>(&[], 5, 4).is_empty());
+        assert_eq!(similarity(&[], &[]), 1.0);
+    }
+}
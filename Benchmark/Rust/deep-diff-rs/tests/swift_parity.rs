@@ -0,0 +1,32 @@
+//! Checks `hackel::diff` against test cases of the Swift DeepDiff library,
+//! transcribed into `fixtures/deepdiff_heckel.json` in its `Change` shape.
+//! Both sides are compared in the form `swift::normalize` leaves them in.
+
+use std::fs;
+use std::path::Path;
+
+use deep_diff_rs::changeset::CowChange;
+use deep_diff_rs::hackel;
+use deep_diff_rs::swift::{normalize, SwiftChange};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Fixture {
+    name: String,
+    old: Vec<String>,
+    new: Vec<String>,
+    changes: Vec<SwiftChange<String>>,
+}
+
+#[test]
+fn heckel_matches_deepdiff() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/deepdiff_heckel.json");
+    let fixtures: Vec<Fixture> = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    assert!(!fixtures.is_empty());
+
+    for fixture in fixtures {
+        let changes = hackel::diff(&fixture.old, &fixture.new);
+        let changes = changes.into_iter().map(|change| SwiftChange::from(CowChange::from(change)));
+        assert_eq!(normalize(changes), normalize(fixture.changes), "{}", fixture.name);
+    }
+}
@@ -1,5 +0,0 @@
-//
-//  Use this file to import your target's public headers that you would like to expose to Swift.
-//
-
-inline void diffWithString(const char* const* old_strs, int old_len, const char* const* new_strs, int new_len);
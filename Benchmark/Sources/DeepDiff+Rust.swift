@@ -6,6 +6,7 @@
 //  Copyright © 2019 Ryo Aoyama. All rights reserved.
 //
 
+import DeepDiffRs
 import Foundation
 
 @inlinable
@@ -13,6 +14,18 @@ func diffStrings(old: [UnsafePointer<CChar>?], new: [UnsafePointer<CChar>?]) {
     diffWithString(old, Int32(old.count), new, Int32(new.count))
 }
 
+func configureRustBenchmark(warmupIterations: UInt32, measuredIterations: UInt32, minTimeNanoseconds: UInt64) {
+    deep_diff_bench_configure(warmupIterations, measuredIterations, minTimeNanoseconds)
+}
+
+func benchmarkStrings(old: [UnsafePointer<CChar>?], new: [UnsafePointer<CChar>?]) -> DeepDiffBenchResult {
+    return deep_diff_bench_run(old, Int32(old.count), new, Int32(new.count))
+}
+
+func measureMemory(old: [UnsafePointer<CChar>?], new: [UnsafePointer<CChar>?]) -> DeepDiffMemoryResult {
+    return deep_diff_bench_memory(old, Int32(old.count), new, Int32(new.count))
+}
+
 @inlinable
 func coercion(_ string: UnsafePointer<CChar>) -> UnsafePointer<CChar>? {
     return string